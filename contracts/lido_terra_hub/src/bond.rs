@@ -43,7 +43,11 @@ pub fn execute_bond(
                 StdError::generic_err("the reward dispatcher contract must have been registered")
             })?)?;
 
-    if bond_type == BondType::BondRewards && info.sender != reward_dispatcher_addr {
+    let bonds_rewards = matches!(
+        bond_type,
+        BondType::BondRewards(_) | BondType::BondRewardsForStLuna(_)
+    );
+    if bonds_rewards && info.sender != reward_dispatcher_addr {
         return Err(StdError::generic_err("unauthorized"));
     }
 
@@ -51,7 +55,9 @@ pub fn execute_bond(
     let current_batch = CURRENT_BATCH.load(deps.storage)?;
     let requested_with_fee = match bond_type {
         BondType::BLuna => current_batch.requested_bluna_with_fee,
-        BondType::StLuna | BondType::BondRewards => current_batch.requested_stluna,
+        BondType::StLuna | BondType::BondRewards(_) | BondType::BondRewardsForStLuna(_) => {
+            current_batch.requested_stluna
+        }
     };
 
     // coin must have be sent along with transaction and it should be in underlying coin denom
@@ -70,6 +76,9 @@ pub fn execute_bond(
             StdError::generic_err(format!("No {} assets are provided to bond", coin_denom))
         })?;
 
+    let hub_address = env.contract.address.to_string();
+    let block_time = env.block.time.seconds();
+
     // check slashing
     let state = slashing(&mut deps, env)?;
 
@@ -78,7 +87,7 @@ pub fn execute_bond(
     // get the total supply
     let mut total_supply = match bond_type {
         BondType::BLuna => query_total_bluna_issued(deps.as_ref()).unwrap_or_default(),
-        BondType::StLuna | BondType::BondRewards => {
+        BondType::StLuna | BondType::BondRewards(_) | BondType::BondRewardsForStLuna(_) => {
             query_total_stluna_issued(deps.as_ref()).unwrap_or_default()
         }
     };
@@ -98,8 +107,10 @@ pub fn execute_bond(
             }
             mint_amount_with_fee
         }
-        BondType::StLuna => decimal_division(payment.amount, state.stluna_exchange_rate),
-        BondType::BondRewards => Uint128::zero(),
+        BondType::StLuna | BondType::BondRewardsForStLuna(_) => {
+            decimal_division(payment.amount, state.stluna_exchange_rate)
+        }
+        BondType::BondRewards(_) => Uint128::zero(),
     };
 
     // total supply should be updated for exchange rate calculation.
@@ -113,12 +124,15 @@ pub fn execute_bond(
                 prev_state.update_bluna_exchange_rate(total_supply, requested_with_fee);
                 Ok(prev_state)
             }
-            BondType::BondRewards => {
+            BondType::BondRewards(release_period) => {
                 prev_state.total_bond_stluna_amount += payment.amount;
+                if release_period > 0 {
+                    prev_state.stream_stluna_rewards(payment.amount, block_time, release_period);
+                }
                 prev_state.update_stluna_exchange_rate(total_supply, requested_with_fee);
                 Ok(prev_state)
             }
-            BondType::StLuna => {
+            BondType::StLuna | BondType::BondRewardsForStLuna(_) => {
                 prev_state.total_bond_stluna_amount += payment.amount;
                 Ok(prev_state)
             }
@@ -138,7 +152,9 @@ pub fn execute_bond(
                 .api
                 .addr_humanize(&validators_registry_contract)?
                 .to_string(),
-            msg: to_binary(&QueryValidators::GetValidatorsForDelegation {})?,
+            msg: to_binary(&QueryValidators::GetValidatorsForDelegation {
+                hub: Some(hub_address),
+            })?,
         }))?;
 
     if validators.is_empty() {
@@ -160,7 +176,7 @@ pub fn execute_bond(
     }
 
     //we don't need to mint stLuna when bonding rewards
-    if bond_type == BondType::BondRewards {
+    if let BondType::BondRewards(_) = bond_type {
         let res = Response::new()
             .add_messages(external_call_msgs)
             .add_attributes(vec![
@@ -171,8 +187,12 @@ pub fn execute_bond(
         return Ok(res);
     }
 
+    let recipient = match &bond_type {
+        BondType::BondRewardsForStLuna(recipient) => deps.api.addr_validate(recipient)?,
+        _ => sender.clone(),
+    };
     let mint_msg = Cw20ExecuteMsg::Mint {
-        recipient: sender.to_string(),
+        recipient: recipient.to_string(),
         amount: mint_amount,
     };
 
@@ -182,12 +202,13 @@ pub fn execute_bond(
             .addr_humanize(&config.bluna_token_contract.ok_or_else(|| {
                 StdError::generic_err("the token contract must have been registered")
             })?)?,
-        BondType::StLuna => deps
-            .api
-            .addr_humanize(&config.stluna_token_contract.ok_or_else(|| {
-                StdError::generic_err("the token contract must have been registered")
-            })?)?,
-        BondType::BondRewards => {
+        BondType::StLuna | BondType::BondRewardsForStLuna(_) => {
+            deps.api
+                .addr_humanize(&config.stluna_token_contract.ok_or_else(|| {
+                    StdError::generic_err("the token contract must have been registered")
+                })?)?
+        }
+        BondType::BondRewards(_) => {
             return Err(StdError::generic_err(
                 "can't mint tokens when bonding rewards",
             ));
@@ -205,6 +226,7 @@ pub fn execute_bond(
         .add_attributes(vec![
             attr("action", "mint"),
             attr("from", sender),
+            attr("to", recipient),
             attr("bonded", payment.amount),
             attr("minted", mint_amount),
         ]);
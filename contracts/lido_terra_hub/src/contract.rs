@@ -141,7 +141,15 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::Bond {} => execute_bond(deps, env, info, BondType::BLuna),
         ExecuteMsg::BondForStLuna {} => execute_bond(deps, env, info, BondType::StLuna),
-        ExecuteMsg::BondRewards {} => execute_bond(deps, env, info, BondType::BondRewards),
+        ExecuteMsg::BondRewards { release_period } => execute_bond(
+            deps,
+            env,
+            info,
+            BondType::BondRewards(release_period.unwrap_or_default()),
+        ),
+        ExecuteMsg::BondRewardsForStLuna { recipient } => {
+            execute_bond(deps, env, info, BondType::BondRewardsForStLuna(recipient))
+        }
         ExecuteMsg::UpdateGlobalIndex { airdrop_hooks } => {
             execute_update_global(deps, env, info, airdrop_hooks)
         }
@@ -390,6 +398,7 @@ fn withdraw_all_rewards(deps: &DepsMut, delegator: String) -> StdResult<Vec<Cosm
 
 fn query_actual_state(deps: Deps, env: Env) -> StdResult<State> {
     let mut state = STATE.load(deps.storage)?;
+    state.release_stluna_rewards(env.block.time.seconds());
     let delegations = deps.querier.query_all_delegations(env.contract.address)?;
     if delegations.is_empty() {
         return Ok(state);
@@ -636,6 +645,7 @@ fn query_state(deps: Deps, env: Env) -> StdResult<StateResponse> {
         prev_hub_balance: state.prev_hub_balance,
         last_unbonded_time: state.last_unbonded_time,
         last_processed_batch: state.last_processed_batch,
+        stluna_unreleased_rewards: state.stluna_unreleased_rewards,
 
         exchange_rate: state.bluna_exchange_rate,
         total_bond_amount: state.total_bond_bluna_amount,
@@ -756,6 +766,7 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response>
         prev_hub_balance: old_state.prev_hub_balance,
         last_unbonded_time: old_state.last_unbonded_time,
         last_processed_batch: old_state.last_processed_batch,
+        ..Default::default()
     };
     STATE.save(deps.storage, &new_state)?;
 
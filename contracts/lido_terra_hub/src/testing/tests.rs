@@ -150,7 +150,7 @@ pub fn do_bond(
         .querier
         .query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: String::from("validators_registry"),
-            msg: to_binary(&QueryValidators::GetValidatorsForDelegation {}).unwrap(),
+            msg: to_binary(&QueryValidators::GetValidatorsForDelegation { hub: None }).unwrap(),
         }))
         .unwrap();
 
@@ -170,7 +170,7 @@ pub fn do_bond_stluna(
         .querier
         .query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: String::from("validators_registry"),
-            msg: to_binary(&QueryValidators::GetValidatorsForDelegation {}).unwrap(),
+            msg: to_binary(&QueryValidators::GetValidatorsForDelegation { hub: None }).unwrap(),
         }))
         .unwrap();
 
@@ -250,6 +250,7 @@ fn proper_initialization() {
         prev_hub_balance: Default::default(),
         last_unbonded_time: env.block.time.seconds(),
         last_processed_batch: 0u64,
+        stluna_unreleased_rewards: Uint128::zero(),
 
         exchange_rate: Decimal::one(),
         total_bond_amount: Uint128::zero(),
@@ -624,7 +625,9 @@ fn proper_bond_rewards() {
     deps.querier
         .with_token_balances(&[(&stluna_token_contract, &[(&addr1, &bond_amount)])]);
 
-    let bond_msg = ExecuteMsg::BondRewards {};
+    let bond_msg = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let info = mock_info(
         &reward_dispatcher_contract,
@@ -675,7 +678,9 @@ fn proper_bond_rewards() {
     );
 
     // no-send funds
-    let failed_bond = ExecuteMsg::BondRewards {};
+    let failed_bond = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let info = mock_info(&reward_dispatcher_contract, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond);
@@ -685,7 +690,9 @@ fn proper_bond_rewards() {
     );
 
     //send other tokens than luna funds
-    let failed_bond = ExecuteMsg::BondRewards {};
+    let failed_bond = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let info = mock_info(&reward_dispatcher_contract, &[coin(10, "ukrt")]);
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond.clone());
@@ -714,12 +721,194 @@ fn proper_bond_rewards() {
         &String::from("random_address"),
         &[coin(bond_amount.u128(), "uluna")],
     );
-    let failed_bond = ExecuteMsg::BondRewards {};
+    let failed_bond = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond).unwrap_err();
     assert_eq!(res, StdError::generic_err("unauthorized"));
 }
 
+#[test]
+fn proper_bond_rewards_with_release_period() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR);
+    set_validator_mock(&mut deps.querier);
+
+    let addr1 = String::from("addr1000");
+    let bond_amount = Uint128::from(10000u64);
+
+    let owner = String::from("owner1");
+    let token_contract = String::from("token");
+    let stluna_token_contract = String::from("stluna_token");
+    let reward_dispatcher_contract = String::from("reward_dispatcher");
+
+    initialize(
+        deps.borrow_mut(),
+        owner,
+        reward_dispatcher_contract.clone(),
+        token_contract.clone(),
+        stluna_token_contract.clone(),
+    );
+    do_register_validator(&mut deps, validator.clone());
+
+    let info = mock_info(&addr1, &[coin(bond_amount.u128(), "uluna")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::BondForStLuna {},
+    )
+    .unwrap();
+    deps.querier.with_token_balances(&[
+        (&stluna_token_contract, &[(&addr1, &bond_amount)]),
+        (&token_contract, &[]),
+    ]);
+    set_delegation(&mut deps.querier, validator.clone(), 10000, "uluna");
+
+    // the rewards are released over 100 seconds
+    let mut env = mock_env();
+    let info = mock_info(
+        &reward_dispatcher_contract,
+        &[coin(bond_amount.u128(), "uluna")],
+    );
+    let bond_msg = ExecuteMsg::BondRewards {
+        release_period: Some(100),
+    };
+    execute(deps.as_mut(), env.clone(), info, bond_msg).unwrap();
+    set_delegation(&mut deps.querier, validator, 20000, "uluna");
+
+    let query_state: StateResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), State {}).unwrap()).unwrap();
+    assert_eq!(
+        query_state.total_bond_stluna_amount,
+        bond_amount + bond_amount
+    );
+    assert_eq!(query_state.stluna_unreleased_rewards, bond_amount);
+    assert_eq!(query_state.stluna_exchange_rate, Decimal::one());
+
+    // the exchange rate rises with the elapsed time
+    env.block.time = env.block.time.plus_seconds(50);
+    let query_state: StateResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), State {}).unwrap()).unwrap();
+    assert_eq!(
+        query_state.stluna_unreleased_rewards,
+        Uint128::from(5000u64)
+    );
+    assert_eq!(
+        query_state.stluna_exchange_rate,
+        Decimal::from_ratio(3u128, 2u128)
+    );
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let query_state: StateResponse =
+        from_binary(&query(deps.as_ref(), env, State {}).unwrap()).unwrap();
+    assert_eq!(query_state.stluna_unreleased_rewards, Uint128::zero());
+    assert_eq!(
+        query_state.stluna_exchange_rate,
+        Decimal::from_ratio(2u128, 1u128)
+    );
+}
+
+#[test]
+fn proper_bond_rewards_for_stluna() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR);
+    set_validator_mock(&mut deps.querier);
+
+    let addr1 = String::from("addr1000");
+    let fee_recipient = String::from("lido_fee");
+    let bond_amount = Uint128::from(10000u64);
+
+    let owner = String::from("owner1");
+    let token_contract = String::from("token");
+    let stluna_token_contract = String::from("stluna_token");
+    let reward_dispatcher_contract = String::from("reward_dispatcher");
+
+    initialize(
+        deps.borrow_mut(),
+        owner,
+        reward_dispatcher_contract.clone(),
+        token_contract,
+        stluna_token_contract.clone(),
+    );
+
+    do_register_validator(&mut deps, validator);
+
+    let info = mock_info(&addr1, &[coin(bond_amount.u128(), "uluna")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::BondForStLuna {},
+    )
+    .unwrap();
+    deps.querier
+        .with_token_balances(&[(&stluna_token_contract, &[(&addr1, &bond_amount)])]);
+
+    // the rewards double the exchange rate
+    let info = mock_info(
+        &reward_dispatcher_contract,
+        &[coin(bond_amount.u128(), "uluna")],
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::BondRewards {
+            release_period: None,
+        },
+    )
+    .unwrap();
+
+    let bond_msg = ExecuteMsg::BondRewardsForStLuna {
+        recipient: fee_recipient.clone(),
+    };
+
+    // only the rewards dispatcher can bond the rewards
+    let info = mock_info(&addr1, &[coin(1000, "uluna")]);
+    let res = execute(deps.as_mut(), mock_env(), info, bond_msg.clone()).unwrap_err();
+    assert_eq!(res, StdError::generic_err("unauthorized"));
+
+    let info = mock_info(&reward_dispatcher_contract, &[coin(1000, "uluna")]);
+    let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+    assert_eq!(2, res.messages.len());
+
+    match res.messages[0].msg.clone() {
+        CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
+            assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
+            assert_eq!(amount, coin(1000, "uluna"));
+        }
+        _ => panic!("Unexpected message: {:?}", res.messages[0]),
+    }
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: stluna_token_contract,
+            msg: to_binary(&Mint {
+                recipient: fee_recipient,
+                amount: Uint128::from(500u64),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // the exchange rate is kept
+    let query_state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), State {}).unwrap()).unwrap();
+    assert_eq!(
+        query_state.total_bond_stluna_amount,
+        Uint128::from(21000u64)
+    );
+    assert_eq!(
+        query_state.stluna_exchange_rate,
+        Decimal::from_ratio(2u128, 1u128)
+    );
+}
+
 /// Covers if Withdraw message, swap message, and update global index are sent.
 #[test]
 pub fn proper_update_global_index() {
@@ -2426,7 +2615,9 @@ pub fn proper_withdraw_unbonded_stluna() {
         _ => panic!("Unexpected message: {:?}", delegate),
     }
 
-    let bond_msg = ExecuteMsg::BondRewards {};
+    let bond_msg = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let info = mock_info(&String::from("reward"), &[coin(100, "uluna")]);
 
@@ -2647,7 +2838,9 @@ pub fn proper_withdraw_unbonded_both_tokens() {
 
     set_delegation(&mut deps.querier, validator.clone(), 200, "uluna");
 
-    let bond_msg = ExecuteMsg::BondRewards {};
+    let bond_msg = ExecuteMsg::BondRewards {
+        release_period: None,
+    };
 
     let info = mock_info(&String::from("reward"), &[coin(100, "uluna")]);
 
@@ -0,0 +1,489 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Api, Binary, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::{
+    AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration,
+    TokenInfoResponse,
+};
+
+use crate::math::decimal_division;
+use crate::msg::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SharesResponse,
+};
+use crate::querier::query_stluna_exchange_rate;
+use crate::state::{
+    read_allowance, read_shares, store_allowance, store_shares, Config, TokenInfo, CONFIG,
+    TOKEN_INFO,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    let config = Config {
+        hub_contract: deps.api.addr_canonicalize(&msg.hub_contract)?,
+        stluna_token_contract: deps.api.addr_canonicalize(&msg.stluna_token_contract)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    let token_info = TokenInfo {
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        total_shares: Uint128::zero(),
+    };
+    TOKEN_INFO.save(deps.storage, &token_info)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Redeem { amount } => execute_redeem(deps, env, info, amount),
+        ExecuteMsg::Transfer { recipient, amount } => {
+            execute_transfer(deps, env, info, recipient, amount)
+        }
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_update_allowance(deps, env, info, spender, amount, expires, true),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_update_allowance(deps, env, info, spender, amount, expires, false),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => execute_transfer_from(deps, env, info, owner, recipient, amount),
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => execute_send_from(deps, env, info, owner, contract, amount, msg),
+    }
+}
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> StdResult<Response> {
+    // only the stLuna token contract can execute this message
+    let config = CONFIG.load(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.stluna_token_contract {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Deposit { recipient } => execute_deposit(
+            deps,
+            env,
+            cw20_msg.sender.clone(),
+            recipient.unwrap_or(cw20_msg.sender),
+            cw20_msg.amount,
+        ),
+    }
+}
+
+/// Mints the shares for the deposited stLuna, a share is backed by a stLuna
+fn execute_deposit(
+    deps: DepsMut,
+    _env: Env,
+    depositor: String,
+    recipient: String,
+    shares: Uint128,
+) -> StdResult<Response> {
+    if shares.is_zero() {
+        return Err(StdError::generic_err("Invalid zero amount"));
+    }
+    let exchange_rate = query_stluna_exchange_rate(deps.as_ref())?;
+
+    let recipient_raw = deps.api.addr_canonicalize(&recipient)?;
+    let recipient_shares = read_shares(deps.storage, &recipient_raw)?;
+    store_shares(deps.storage, &recipient_raw, recipient_shares + shares)?;
+
+    TOKEN_INFO.update(deps.storage, |mut token_info| -> StdResult<_> {
+        token_info.total_shares += shares;
+        Ok(token_info)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "deposit"),
+        attr("depositor", depositor),
+        attr("recipient", recipient),
+        attr("shares", shares),
+        attr("amount", shares * exchange_rate),
+    ]))
+}
+
+/// Burns the shares worth the amount of Luna and sends the stLuna behind them to the sender
+fn execute_redeem(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> StdResult<Response> {
+    let exchange_rate = query_stluna_exchange_rate(deps.as_ref())?;
+
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let sender_shares = read_shares(deps.storage, &sender_raw)?;
+    let shares = match amount {
+        Some(amount) => {
+            check_balance(sender_shares, exchange_rate, amount)?;
+            amount_to_shares(amount, exchange_rate)?
+        }
+        None => sender_shares,
+    };
+    if shares.is_zero() {
+        return Err(StdError::generic_err("No shares to redeem"));
+    }
+    store_shares(
+        deps.storage,
+        &sender_raw,
+        sender_shares.checked_sub(shares)?,
+    )?;
+
+    TOKEN_INFO.update(deps.storage, |mut token_info| -> StdResult<_> {
+        token_info.total_shares = token_info.total_shares.checked_sub(shares)?;
+        Ok(token_info)
+    })?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let message = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps
+            .api
+            .addr_humanize(&config.stluna_token_contract)?
+            .to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount: shares,
+        })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        attr("action", "redeem"),
+        attr("from", info.sender),
+        attr("shares", shares),
+        attr("amount", shares * exchange_rate),
+    ]))
+}
+
+fn execute_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let (shares, moved_amount) = shares_to_move(deps.as_ref(), info.sender.as_str(), amount)?;
+    move_shares(deps, info.sender.as_str(), &recipient, shares)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer"),
+        attr("from", info.sender),
+        attr("to", recipient),
+        attr("amount", moved_amount),
+        attr("shares", shares),
+    ]))
+}
+
+fn execute_send(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> StdResult<Response> {
+    let (shares, moved_amount) = shares_to_move(deps.as_ref(), info.sender.as_str(), amount)?;
+    move_shares(deps, info.sender.as_str(), &contract, shares)?;
+
+    let message = Cw20ReceiveMsg {
+        sender: info.sender.to_string(),
+        amount: moved_amount,
+        msg,
+    }
+    .into_cosmos_msg(contract.clone())?;
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        attr("action", "send"),
+        attr("from", info.sender),
+        attr("to", contract),
+        attr("amount", moved_amount),
+        attr("shares", shares),
+    ]))
+}
+
+fn execute_update_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    increase: bool,
+) -> StdResult<Response> {
+    if spender == info.sender.as_str() {
+        return Err(StdError::generic_err("Cannot set allowance to own account"));
+    }
+
+    let owner_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let spender_raw = deps.api.addr_canonicalize(&spender)?;
+    let mut allowance = read_allowance(deps.storage, &owner_raw, &spender_raw)?;
+    if let Some(expires) = expires {
+        if expires.is_expired(&env.block) {
+            return Err(StdError::generic_err("Allowance is expired"));
+        }
+        allowance.expires = expires;
+    }
+    allowance.allowance = if increase {
+        allowance.allowance + amount
+    } else {
+        allowance.allowance.saturating_sub(amount)
+    };
+    store_allowance(deps.storage, &owner_raw, &spender_raw, &allowance)?;
+
+    let action = if increase {
+        "increase_allowance"
+    } else {
+        "decrease_allowance"
+    };
+    Ok(Response::new().add_attributes(vec![
+        attr("action", action),
+        attr("owner", info.sender),
+        attr("spender", spender),
+        attr("amount", amount),
+    ]))
+}
+
+fn execute_transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let (shares, moved_amount) = shares_to_move(deps.as_ref(), &owner, amount)?;
+    deduct_allowance(
+        deps.storage,
+        deps.api,
+        &env.block,
+        &owner,
+        info.sender.as_str(),
+        amount,
+        moved_amount,
+    )?;
+    move_shares(deps, &owner, &recipient, shares)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_from"),
+        attr("from", owner),
+        attr("to", recipient),
+        attr("by", info.sender),
+        attr("amount", moved_amount),
+        attr("shares", shares),
+    ]))
+}
+
+fn execute_send_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> StdResult<Response> {
+    let (shares, moved_amount) = shares_to_move(deps.as_ref(), &owner, amount)?;
+    deduct_allowance(
+        deps.storage,
+        deps.api,
+        &env.block,
+        &owner,
+        info.sender.as_str(),
+        amount,
+        moved_amount,
+    )?;
+    move_shares(deps, &owner, &contract, shares)?;
+
+    let message = Cw20ReceiveMsg {
+        sender: info.sender.to_string(),
+        amount: moved_amount,
+        msg,
+    }
+    .into_cosmos_msg(contract.clone())?;
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        attr("action", "send_from"),
+        attr("from", owner),
+        attr("to", contract),
+        attr("by", info.sender),
+        attr("amount", moved_amount),
+        attr("shares", shares),
+    ]))
+}
+
+/// Converts the amount of Luna to the shares rounding down,
+/// so the shares moved are never worth more than the amount
+fn amount_to_shares(amount: Uint128, exchange_rate: Decimal) -> StdResult<Uint128> {
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Invalid zero amount"));
+    }
+    Ok(decimal_division(amount, exchange_rate))
+}
+
+/// Checks the amount of Luna doesn't exceed the balance,
+/// the shares are rounded down and would let a bit more through
+fn check_balance(shares: Uint128, exchange_rate: Decimal, amount: Uint128) -> StdResult<()> {
+    (shares * exchange_rate).checked_sub(amount)?;
+    Ok(())
+}
+
+/// Returns the shares worth the amount of Luna the sender moves along with the amount
+/// they are worth, all of the shares are moved if the amount is the whole balance
+fn shares_to_move(deps: Deps, sender: &str, amount: Uint128) -> StdResult<(Uint128, Uint128)> {
+    let exchange_rate = query_stluna_exchange_rate(deps)?;
+    let mut shares = amount_to_shares(amount, exchange_rate)?;
+
+    let sender_shares = read_shares(deps.storage, &deps.api.addr_canonicalize(sender)?)?;
+    check_balance(sender_shares, exchange_rate, amount)?;
+    // the shares are rounded down and would leave a dust behind the whole balance
+    if amount == sender_shares * exchange_rate {
+        shares = sender_shares;
+    }
+    if shares.is_zero() {
+        return Err(StdError::generic_err(
+            "The amount is worth less than a share",
+        ));
+    }
+    Ok((shares, shares * exchange_rate))
+}
+
+/// Moves the shares from the sender to the recipient
+fn move_shares(deps: DepsMut, sender: &str, recipient: &str, shares: Uint128) -> StdResult<()> {
+    let sender_raw = deps.api.addr_canonicalize(sender)?;
+    let recipient_raw = deps.api.addr_canonicalize(recipient)?;
+    let sender_shares = read_shares(deps.storage, &sender_raw)?;
+    store_shares(
+        deps.storage,
+        &sender_raw,
+        sender_shares.checked_sub(shares)?,
+    )?;
+    let recipient_shares = read_shares(deps.storage, &recipient_raw)?;
+    store_shares(deps.storage, &recipient_raw, recipient_shares + shares)
+}
+
+/// Checks the allowance covers the amount and deducts the amount the moved shares are worth,
+/// which is less than the amount by the rounding of the shares
+fn deduct_allowance(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    owner: &str,
+    spender: &str,
+    amount: Uint128,
+    moved_amount: Uint128,
+) -> StdResult<()> {
+    let owner_raw = api.addr_canonicalize(owner)?;
+    let spender_raw = api.addr_canonicalize(spender)?;
+    let mut allowance = read_allowance(storage, &owner_raw, &spender_raw)?;
+    if allowance.expires.is_expired(block) {
+        return Err(StdError::generic_err("Allowance is expired"));
+    }
+    allowance.allowance.checked_sub(amount)?;
+    allowance.allowance = allowance.allowance.checked_sub(moved_amount)?;
+    store_allowance(storage, &owner_raw, &spender_raw, &allowance)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::Shares { address } => to_binary(&query_shares(deps, address)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Allowance { owner, spender } => {
+            to_binary(&query_allowance(deps, owner, spender)?)
+        }
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+    }
+}
+
+fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
+    let shares = read_shares(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    Ok(BalanceResponse {
+        balance: shares * query_stluna_exchange_rate(deps)?,
+    })
+}
+
+fn query_shares(deps: Deps, address: String) -> StdResult<SharesResponse> {
+    Ok(SharesResponse {
+        shares: read_shares(deps.storage, &deps.api.addr_canonicalize(&address)?)?,
+        exchange_rate: query_stluna_exchange_rate(deps)?,
+    })
+}
+
+fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
+    let token_info = TOKEN_INFO.load(deps.storage)?;
+    Ok(TokenInfoResponse {
+        name: token_info.name,
+        symbol: token_info.symbol,
+        decimals: token_info.decimals,
+        total_supply: token_info.total_shares * query_stluna_exchange_rate(deps)?,
+    })
+}
+
+fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<AllowanceResponse> {
+    let owner_raw = deps.api.addr_canonicalize(&owner)?;
+    let spender_raw = deps.api.addr_canonicalize(&spender)?;
+    read_allowance(deps.storage, &owner_raw, &spender_raw)
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        hub_contract: deps.api.addr_humanize(&config.hub_contract)?.to_string(),
+        stluna_token_contract: deps
+            .api
+            .addr_humanize(&config.stluna_token_contract)?
+            .to_string(),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
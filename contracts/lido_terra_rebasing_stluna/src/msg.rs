@@ -0,0 +1,115 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20::{Cw20ReceiveMsg, Expiration};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub hub_contract: String,
+    pub stluna_token_contract: String,
+}
+
+/// The amounts of the messages are denominated in Luna
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Deposits the stLuna sent with `Cw20HookMsg::Deposit`
+    Receive(Cw20ReceiveMsg),
+    /// Burns the shares worth the amount and sends them back as stLuna,
+    /// the whole balance is redeemed if the amount is none
+    Redeem {
+        amount: Option<Uint128>,
+    },
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Mints the shares to the recipient, the sender of the stLuna if it's none
+    Deposit { recipient: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// The balance of the address in Luna.
+    /// Return type: cw20::BalanceResponse.
+    Balance {
+        address: String,
+    },
+    /// The stLuna held for the address
+    Shares {
+        address: String,
+    },
+    /// The token info with the total supply in Luna.
+    /// Return type: cw20::TokenInfoResponse.
+    TokenInfo {},
+    /// Return type: cw20::AllowanceResponse.
+    Allowance {
+        owner: String,
+        spender: String,
+    },
+    Config {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharesResponse {
+    pub shares: Uint128,
+    pub exchange_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub hub_contract: String,
+    pub stluna_token_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
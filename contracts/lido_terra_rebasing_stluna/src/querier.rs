@@ -0,0 +1,34 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{to_binary, Decimal, Deps, QueryRequest, StdError, StdResult, WasmQuery};
+
+use crate::state::CONFIG;
+use basset::hub::{QueryMsg as HubQueryMsg, StateResponse};
+
+/// Returns the stLuna exchange rate of the hub, the amount of Luna a share is worth
+pub fn query_stluna_exchange_rate(deps: Deps) -> StdResult<Decimal> {
+    let config = CONFIG.load(deps.storage)?;
+    let hub_address = deps.api.addr_humanize(&config.hub_contract)?;
+
+    let state: StateResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::State {})?,
+    }))?;
+
+    if state.stluna_exchange_rate.is_zero() {
+        return Err(StdError::generic_err("the stLuna exchange rate is zero"));
+    }
+    Ok(state.stluna_exchange_rate)
+}
@@ -0,0 +1,84 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage, Uint128};
+use cw20::AllowanceResponse;
+use cw_storage_plus::{Item, Map};
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
+// the stLuna held by the contract for the accounts
+pub const SHARES: Map<&[u8], Uint128> = Map::new("shares");
+// the allowances are denominated in Luna like the balances
+pub const ALLOWANCES: Map<(&[u8], &[u8]), AllowanceResponse> = Map::new("allowances");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub hub_contract: CanonicalAddr,
+    pub stluna_token_contract: CanonicalAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfo {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_shares: Uint128,
+}
+
+pub fn read_shares(storage: &dyn Storage, account: &CanonicalAddr) -> StdResult<Uint128> {
+    Ok(SHARES
+        .may_load(storage, account.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn store_shares(
+    storage: &mut dyn Storage,
+    account: &CanonicalAddr,
+    shares: Uint128,
+) -> StdResult<()> {
+    if shares.is_zero() {
+        SHARES.remove(storage, account.as_slice());
+        Ok(())
+    } else {
+        SHARES.save(storage, account.as_slice(), &shares)
+    }
+}
+
+pub fn read_allowance(
+    storage: &dyn Storage,
+    owner: &CanonicalAddr,
+    spender: &CanonicalAddr,
+) -> StdResult<AllowanceResponse> {
+    Ok(ALLOWANCES
+        .may_load(storage, (owner.as_slice(), spender.as_slice()))?
+        .unwrap_or_default())
+}
+
+pub fn store_allowance(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    spender: &CanonicalAddr,
+    allowance: &AllowanceResponse,
+) -> StdResult<()> {
+    if allowance.allowance.is_zero() {
+        ALLOWANCES.remove(storage, (owner.as_slice(), spender.as_slice()));
+        Ok(())
+    } else {
+        ALLOWANCES.save(storage, (owner.as_slice(), spender.as_slice()), allowance)
+    }
+}
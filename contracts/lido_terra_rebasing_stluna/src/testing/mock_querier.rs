@@ -0,0 +1,102 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use basset::hub::{QueryMsg as HubQueryMsg, StateResponse};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, Empty, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+};
+
+pub const MOCK_HUB_CONTRACT_ADDR: &str = "hub";
+pub const MOCK_STLUNA_TOKEN_CONTRACT_ADDR: &str = "stluna_token";
+
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let contract_addr = String::from(MOCK_CONTRACT_ADDR);
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(&contract_addr, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    stluna_exchange_rate: Decimal,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if *contract_addr == MOCK_HUB_CONTRACT_ADDR =>
+            {
+                match from_binary(msg).unwrap() {
+                    HubQueryMsg::State {} => {
+                        let state = StateResponse {
+                            bluna_exchange_rate: Decimal::one(),
+                            stluna_exchange_rate: self.stluna_exchange_rate,
+                            total_bond_bluna_amount: Uint128::zero(),
+                            total_bond_stluna_amount: Uint128::zero(),
+                            last_index_modification: 0,
+                            prev_hub_balance: Uint128::zero(),
+                            last_unbonded_time: 0,
+                            last_processed_batch: 0,
+                            stluna_unreleased_rewards: Uint128::zero(),
+                            total_bond_amount: Uint128::zero(),
+                            exchange_rate: Decimal::one(),
+                        };
+                        SystemResult::Ok(ContractResult::from(to_binary(&state)))
+                    }
+                    _ => unimplemented!(),
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            stluna_exchange_rate: Decimal::one(),
+        }
+    }
+
+    pub fn with_stluna_exchange_rate(&mut self, stluna_exchange_rate: Decimal) {
+        self.stluna_exchange_rate = stluna_exchange_rate;
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, CosmosMsg, Decimal, OwnedDeps, StdError, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{
+    AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration,
+    TokenInfoResponse,
+};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg, SharesResponse};
+use crate::testing::mock_querier::{
+    mock_dependencies, WasmMockQuerier, MOCK_HUB_CONTRACT_ADDR, MOCK_STLUNA_TOKEN_CONTRACT_ADDR,
+};
+
+fn do_init(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+    let msg = InstantiateMsg {
+        name: "rebasing stluna".to_string(),
+        symbol: "RSTLUNA".to_string(),
+        decimals: 6,
+        hub_contract: MOCK_HUB_CONTRACT_ADDR.to_string(),
+        stluna_token_contract: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+    };
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+fn do_deposit(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    depositor: &str,
+    shares: u128,
+) {
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: depositor.to_string(),
+        amount: Uint128::from(shares),
+        msg: to_binary(&Cw20HookMsg::Deposit { recipient: None }).unwrap(),
+    });
+    let info = mock_info(MOCK_STLUNA_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+fn query_balance(deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, address: &str) -> u128 {
+    let res: BalanceResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.balance.u128()
+}
+
+#[test]
+fn deposit_and_redeem() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(11u128, 10u128));
+
+    // only the stLuna token can deposit
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000u128),
+        msg: to_binary(&Cw20HookMsg::Deposit { recipient: None }).unwrap(),
+    });
+    let info = mock_info("other_token", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, StdError::generic_err("unauthorized"));
+
+    do_deposit(&mut deps, "addr0000", 1000);
+    assert_eq!(query_balance(&deps, "addr0000"), 1100);
+
+    let res: TokenInfoResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TokenInfo {}).unwrap()).unwrap();
+    assert_eq!(res.total_supply, Uint128::from(1100u128));
+
+    // the balance grows with the exchange rate
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(12u128, 10u128));
+    assert_eq!(query_balance(&deps, "addr0000"), 1200);
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::Redeem {
+        amount: Some(Uint128::from(600u128)),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(500u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+    assert_eq!(query_balance(&deps, "addr0000"), 600);
+
+    // redeems the rest
+    let msg = ExecuteMsg::Redeem { amount: None };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem"),
+            attr("from", "addr0000"),
+            attr("shares", "500"),
+            attr("amount", "600"),
+        ]
+    );
+    assert_eq!(query_balance(&deps, "addr0000"), 0);
+
+    let msg = ExecuteMsg::Redeem { amount: None };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, StdError::generic_err("No shares to redeem"));
+}
+
+#[test]
+fn transfer_and_allowances() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(11u128, 10u128));
+    do_deposit(&mut deps, "addr0000", 1000);
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(330u128),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(query_balance(&deps, "addr0000"), 770);
+    assert_eq!(query_balance(&deps, "addr0001"), 330);
+
+    let res: SharesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Shares {
+                address: "addr0001".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.shares, Uint128::from(300u128));
+
+    // can't transfer more than the balance
+    let msg = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(771u128),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::from(220u128),
+        expires: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let spender = mock_info("spender", &[]);
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0002".to_string(),
+        amount: Uint128::from(221u128),
+    };
+    execute(deps.as_mut(), mock_env(), spender.clone(), msg).unwrap_err();
+
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0002".to_string(),
+        amount: Uint128::from(110u128),
+    };
+    execute(deps.as_mut(), mock_env(), spender.clone(), msg).unwrap();
+    assert_eq!(query_balance(&deps, "addr0000"), 660);
+    assert_eq!(query_balance(&deps, "addr0002"), 110);
+
+    let res: AllowanceResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allowance {
+                owner: "addr0000".to_string(),
+                spender: "spender".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.allowance, Uint128::from(110u128));
+
+    // the allowance expires
+    let mut env = mock_env();
+    env.block.height += 10;
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0002".to_string(),
+        amount: Uint128::from(110u128),
+    };
+    let res = execute(deps.as_mut(), env, spender, msg).unwrap_err();
+    assert_eq!(res, StdError::generic_err("Allowance is expired"));
+}
+
+#[test]
+fn transfer_sub_share_and_full_balance() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(115u128, 100u128));
+    do_deposit(&mut deps, "addr0000", 1001);
+    assert_eq!(query_balance(&deps, "addr0000"), 1151);
+
+    // the amount worth less than a share moves nothing
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(1u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("The amount is worth less than a share")
+    );
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::from(1200u128),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let spender = mock_info("spender", &[]);
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(1u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), spender.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("The amount is worth less than a share")
+    );
+
+    // the allowance is deducted by the amount the moved shares are worth,
+    // 100 / 1.15 rounds down to 86 shares worth 98
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(100u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), spender.clone(), msg).unwrap();
+    assert_eq!(res.attributes[5], attr("shares", "86"));
+    let query_allowance = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>| {
+        let res: AllowanceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Allowance {
+                    owner: "addr0000".to_string(),
+                    spender: "spender".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.allowance.u128()
+    };
+    assert_eq!(query_allowance(&deps), 1102);
+    assert_eq!(query_balance(&deps, "addr0000"), 1052);
+
+    // the whole balance moves all of the shares, leaving no dust behind
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(1052u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), spender, msg).unwrap();
+    assert_eq!(res.attributes[5], attr("shares", "915"));
+    assert_eq!(query_allowance(&deps), 50);
+    assert_eq!(query_balance(&deps, "addr0000"), 0);
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: "addr0002".to_string(),
+        amount: Uint128::from(1151u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(res.attributes[4], attr("shares", "1001"));
+    assert_eq!(query_balance(&deps, "addr0001"), 0);
+    assert_eq!(query_balance(&deps, "addr0002"), 1151);
+}
+
+#[test]
+fn send_moved_amount() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(115u128, 100u128));
+    do_deposit(&mut deps, "addr0000", 1001);
+
+    // 100 / 1.15 rounds down to 86 shares worth 98,
+    // the receiver is notified about the amount it actually gets
+    let info = mock_info("addr0000", &[]);
+    let hook_msg = to_binary(&"hook").unwrap();
+    let msg = ExecuteMsg::Send {
+        contract: "contract0000".to_string(),
+        amount: Uint128::from(100u128),
+        msg: hook_msg.clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(
+            Cw20ReceiveMsg {
+                sender: "addr0000".to_string(),
+                amount: Uint128::from(98u128),
+                msg: hook_msg.clone(),
+            }
+            .into_cosmos_msg("contract0000".to_string())
+            .unwrap()
+        )]
+    );
+    assert_eq!(res.attributes[3], attr("amount", "98"));
+    assert_eq!(res.attributes[4], attr("shares", "86"));
+    assert_eq!(query_balance(&deps, "contract0000"), 98);
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(100u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.attributes[3], attr("amount", "98"));
+    assert_eq!(query_balance(&deps, "addr0001"), 98);
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::from(200u128),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let spender = mock_info("spender", &[]);
+    let msg = ExecuteMsg::SendFrom {
+        owner: "addr0000".to_string(),
+        contract: "contract0000".to_string(),
+        amount: Uint128::from(100u128),
+        msg: hook_msg.clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), spender.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(
+            Cw20ReceiveMsg {
+                sender: "spender".to_string(),
+                amount: Uint128::from(98u128),
+                msg: hook_msg,
+            }
+            .into_cosmos_msg("contract0000".to_string())
+            .unwrap()
+        )]
+    );
+    assert_eq!(res.attributes[4], attr("amount", "98"));
+    assert_eq!(query_balance(&deps, "contract0000"), 197);
+
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        amount: Uint128::from(100u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), spender, msg).unwrap();
+    assert_eq!(res.attributes[4], attr("amount", "98"));
+    assert_eq!(query_balance(&deps, "addr0000"), 755);
+}
@@ -15,18 +15,31 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::global::{execute_swap, execute_update_global_index};
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::global::{
+    execute_add_reward_denom, execute_bond_compounded_rewards,
+    execute_complete_reward_denom_migration, execute_migrate_reward_denom, execute_swap,
+    execute_update_global_index, release_rewards,
+};
+use crate::state::{
+    index_holders, read_config, read_holder_stats, read_index_checkpoints, read_state,
+    read_total_pending_rewards, start_holders_indexing, store_config, store_state, Config, State,
+    HOLDER_STATS,
+};
 use crate::user::{
-    execute_claim_rewards, execute_decrease_balance, execute_increase_balance,
-    query_accrued_rewards, query_holder, query_holders,
+    execute_check_received_rewards, execute_claim_rewards, execute_claim_rewards_for,
+    execute_decrease_balance, execute_increase_balance, execute_set_auto_compound,
+    execute_set_claim_operator, execute_set_reward_routes, execute_update_holder_status,
+    execute_withdraw_compounded_rewards, query_accrued_rewards, query_claim_settings,
+    query_compounded_rewards, query_holder, query_holder_status, query_holders,
+    query_holders_by_balance, query_pending_rewards, query_rewards_between,
 };
 use cosmwasm_std::{
-    to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    attr, to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
 };
 
 use basset::reward::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
+    ConfigResponse, ExecuteMsg, IndexCheckpointResponse, IndexCheckpointsResponse, InstantiateMsg,
+    MigrateMsg, QueryMsg, RewardIndexResponse, StateResponse, StatsResponse,
 };
 use terra_cosmwasm::TerraMsgWrapper;
 
@@ -49,6 +62,16 @@ pub fn instantiate(
             global_index: Decimal::zero(),
             total_balance: Uint128::zero(),
             prev_reward_balance: Uint128::zero(),
+            unreleased_rewards: Uint128::zero(),
+            last_release_time: 0,
+            release_end_time: 0,
+            denom_migrations: vec![],
+            compound_balance: Uint128::zero(),
+            compound_index: Decimal::zero(),
+            compound_pending_rewards: Decimal::zero(),
+            compounded_index: Decimal::zero(),
+            compounded_weighted_index: Decimal::zero(),
+            reward_indices: vec![],
         },
     )?;
 
@@ -63,9 +86,65 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> StdResult<Response<TerraMsgWrapper>> {
     match msg {
-        ExecuteMsg::ClaimRewards { recipient } => execute_claim_rewards(deps, env, info, recipient),
+        ExecuteMsg::ClaimRewards {
+            holder,
+            recipient,
+            denom,
+            min_receive,
+            reward_denoms,
+        } => execute_claim_rewards(
+            deps,
+            env,
+            info,
+            holder,
+            recipient,
+            denom,
+            min_receive,
+            reward_denoms,
+        ),
+        ExecuteMsg::ClaimRewardsFor { holders } => {
+            execute_claim_rewards_for(deps, env, info, holders)
+        }
+        ExecuteMsg::SetClaimOperator { operator, enabled } => {
+            execute_set_claim_operator(deps, info, operator, enabled)
+        }
+        ExecuteMsg::SetRewardRoutes { routes } => execute_set_reward_routes(deps, info, routes),
         ExecuteMsg::SwapToRewardDenom {} => execute_swap(deps, env, info),
-        ExecuteMsg::UpdateGlobalIndex {} => execute_update_global_index(deps, env, info),
+        ExecuteMsg::UpdateGlobalIndex { release_period } => {
+            execute_update_global_index(deps, env, info, release_period)
+        }
+        ExecuteMsg::MigrateRewardDenom { new_denom } => {
+            execute_migrate_reward_denom(deps, env, info, new_denom)
+        }
+        ExecuteMsg::CompleteRewardDenomMigration {
+            new_denom,
+            prev_denom_amount,
+            new_denom_balance,
+        } => execute_complete_reward_denom_migration(
+            deps,
+            env,
+            info,
+            new_denom,
+            prev_denom_amount,
+            new_denom_balance,
+        ),
+        ExecuteMsg::AddRewardDenom { denom } => execute_add_reward_denom(deps, info, denom),
+        ExecuteMsg::IndexHolders { limit } => execute_index_holders(deps, limit),
+        ExecuteMsg::BondCompoundedRewards {} => execute_bond_compounded_rewards(deps, env, info),
+        ExecuteMsg::CheckReceivedRewards {
+            recipient,
+            prev_balance,
+            min_receive,
+        } => execute_check_received_rewards(deps, env, info, recipient, prev_balance, min_receive),
+        ExecuteMsg::SetAutoCompound { enabled } => {
+            execute_set_auto_compound(deps, env, info, enabled)
+        }
+        ExecuteMsg::WithdrawCompoundedRewards { recipient } => {
+            execute_withdraw_compounded_rewards(deps, info, recipient)
+        }
+        ExecuteMsg::UpdateHolderStatus { address, status } => {
+            execute_update_holder_status(deps, env, info, address, status)
+        }
         ExecuteMsg::IncreaseBalance { address, amount } => {
             execute_increase_balance(deps, env, info, address, amount)
         }
@@ -76,15 +155,42 @@ pub fn execute(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::AccruedRewards { address } => to_binary(&query_accrued_rewards(deps, address)?),
+        QueryMsg::State {} => to_binary(&query_state(deps, env)?),
+        QueryMsg::AccruedRewards { address } => {
+            to_binary(&query_accrued_rewards(deps, env, address)?)
+        }
+        QueryMsg::PendingRewards { address } => {
+            to_binary(&query_pending_rewards(deps, env, address)?)
+        }
         QueryMsg::Holder { address } => to_binary(&query_holder(deps, address)?),
         QueryMsg::Holders { start_after, limit } => {
             to_binary(&query_holders(deps, start_after, limit)?)
         }
+        QueryMsg::HoldersByBalance {
+            start_after,
+            limit,
+            descending,
+        } => to_binary(&query_holders_by_balance(
+            deps,
+            start_after,
+            limit,
+            descending,
+        )?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps, env)?),
+        QueryMsg::CompoundedRewards { address } => {
+            to_binary(&query_compounded_rewards(deps, address)?)
+        }
+        QueryMsg::ClaimSettings { address } => to_binary(&query_claim_settings(deps, address)?),
+        QueryMsg::HolderStatus { address } => to_binary(&query_holder_status(deps, address)?),
+        QueryMsg::IndexCheckpoints { start_after, limit } => {
+            to_binary(&query_index_checkpoints(deps, start_after, limit)?)
+        }
+        QueryMsg::RewardsBetween { address, from, to } => {
+            to_binary(&query_rewards_between(deps, env, address, from, to)?)
+        }
     }
 }
 
@@ -96,16 +202,78 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     })
 }
 
-fn query_state(deps: Deps) -> StdResult<StateResponse> {
-    let state: State = read_state(deps.storage)?;
+fn query_state(deps: Deps, env: Env) -> StdResult<StateResponse> {
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
     Ok(StateResponse {
         global_index: state.global_index,
         total_balance: state.total_balance,
         prev_reward_balance: state.prev_reward_balance,
+        unreleased_rewards: state.unreleased_rewards,
+        reward_indices: state
+            .reward_indices
+            .into_iter()
+            .map(|reward_index| RewardIndexResponse {
+                denom: reward_index.denom,
+                global_index: reward_index.global_index,
+                prev_reward_balance: reward_index.prev_reward_balance,
+            })
+            .collect(),
     })
 }
 
+fn execute_index_holders(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let indexed = index_holders(deps.storage, limit)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "index_holders"),
+        attr("indexed_holders", indexed.to_string()),
+    ]))
+}
+
+fn query_stats(deps: Deps, env: Env) -> StdResult<StatsResponse> {
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    let stats = read_holder_stats(deps.storage)?;
+    let total_pending_rewards = read_total_pending_rewards(
+        deps.storage,
+        state.global_index,
+        &deps.api.addr_canonicalize(env.contract.address.as_str())?,
+    )?;
+    Ok(StatsResponse {
+        holder_count: stats.holder_count,
+        total_balance: state.total_balance,
+        total_pending_rewards: total_pending_rewards * Uint128::new(1),
+        holders_indexed: stats.indexed_till.is_none(),
+    })
+}
+
+fn query_index_checkpoints(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<IndexCheckpointsResponse> {
+    let checkpoints = read_index_checkpoints(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|checkpoint| IndexCheckpointResponse {
+            time: checkpoint.time,
+            global_index: checkpoint.global_index,
+            total_balance: checkpoint.total_balance,
+            rewards: checkpoint.rewards,
+        })
+        .collect();
+    Ok(IndexCheckpointsResponse { checkpoints })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    // the holders stored before the holder stats were kept are indexed once,
+    // the rest of them is indexed by IndexHolders if they don't fit into the first batch
+    if HOLDER_STATS.may_load(deps.storage)?.is_none() {
+        start_holders_indexing(deps.storage)?;
+        index_holders(deps.storage, None)?;
+    }
     Ok(Response::default())
 }
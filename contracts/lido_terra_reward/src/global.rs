@@ -12,19 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::state::{read_config, read_state, store_state, State};
+use crate::state::{
+    read_config, read_state, store_config, store_index_checkpoint, store_state, Config,
+    DenomMigration, Holder, IndexCheckpoint, RewardIndex, State,
+};
+use crate::user::{accrue_held_compounded_rewards, calculate_decimal_rewards};
 
-use crate::math::decimal_summation_in_256;
+use crate::math::{
+    decimal_multiplication_in_256, decimal_subtraction_in_256, decimal_summation_in_256,
+};
 
-use crate::querier::query_rewards_dispatcher_contract_address;
+use crate::querier::{
+    query_hub_owner, query_max_swap_deviation, query_reward_balance,
+    query_rewards_dispatcher_contract_address, query_underlying_coin_denom, CW20_DENOM_PREFIX,
+};
+use basset::compute_swap_deviation;
+use basset::hub::ExecuteMsg as HubExecuteMsg;
+use basset::reward::ExecuteMsg;
 use cosmwasm_std::{
-    attr, Decimal, DepsMut, Env, MessageInfo, Response, StdError, StdResult, SubMsg,
+    attr, to_binary, Attribute, Coin, Decimal, DepsMut, Env, Fraction, MessageInfo, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use terra_cosmwasm::{
+    create_swap_msg, ExchangeRatesResponse, SwapResponse, TerraMsgWrapper, TerraQuerier,
 };
-use terra_cosmwasm::{create_swap_msg, ExchangeRatesResponse, TerraMsgWrapper, TerraQuerier};
 
 /// Swap all native tokens to reward_denom
 /// Only hub_contract is allowed to execute
-#[allow(clippy::if_same_then_else)]
 pub fn execute_swap(
     deps: DepsMut,
     env: Env,
@@ -43,11 +57,15 @@ pub fn execute_swap(
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    let max_swap_deviation = query_max_swap_deviation(deps.as_ref(), owner_addr)?;
+
     let contr_addr = env.contract.address;
     let balance = deps.querier.query_all_balances(contr_addr)?;
     let mut messages: Vec<SubMsg<TerraMsgWrapper>> = Vec::new();
+    let mut skipped_attrs: Vec<Attribute> = Vec::new();
 
     let reward_denom = config.reward_denom;
+    let state = read_state(deps.storage)?;
 
     let mut is_listed = true;
 
@@ -58,21 +76,33 @@ pub fn execute_swap(
     }
 
     for coin in balance {
-        if coin.denom == reward_denom.clone() {
+        // the rewards in the other reward denoms are distributed as they are
+        if coin.denom == reward_denom.clone()
+            || state.reward_indices.iter().any(|i| i.denom == coin.denom)
+        {
             continue;
         }
-        if is_listed {
-            messages.push(SubMsg::new(create_swap_msg(coin, reward_denom.to_string())));
-        } else if query_exchange_rates(&deps, reward_denom.clone(), vec![coin.denom.clone()])
-            .is_ok()
+        if !is_listed
+            && query_exchange_rates(&deps, reward_denom.clone(), vec![coin.denom.clone()]).is_err()
         {
-            messages.push(SubMsg::new(create_swap_msg(coin, reward_denom.to_string())));
+            continue;
         }
+        // the coins swapped at a worse rate than the limit stay on the contract till the next swap
+        if let Some(max_deviation) = max_swap_deviation {
+            let deviation = query_swap_deviation(&deps, &coin, reward_denom.as_str())?;
+            if deviation > max_deviation {
+                skipped_attrs.push(attr("skipped_swap", coin.to_string()));
+                skipped_attrs.push(attr("skipped_swap_deviation", deviation.to_string()));
+                continue;
+            }
+        }
+        messages.push(SubMsg::new(create_swap_msg(coin, reward_denom.to_string())));
     }
 
     let res = Response::new()
         .add_submessages(messages)
-        .add_attributes(vec![attr("action", "swap")]);
+        .add_attributes(vec![attr("action", "swap")])
+        .add_attributes(skipped_attrs);
 
     Ok(res)
 }
@@ -80,9 +110,10 @@ pub fn execute_swap(
 /// Increase global_index according to claimed rewards amount
 /// Only hub_contract is allowed to execute
 pub fn execute_update_global_index(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    release_period: Option<u64>,
 ) -> StdResult<Response<TerraMsgWrapper>> {
     let mut state: State = read_state(deps.storage)?;
 
@@ -109,7 +140,7 @@ pub fn execute_update_global_index(
     // Load the reward contract balance
     let balance = deps
         .querier
-        .query_balance(env.contract.address, reward_denom.as_str())?;
+        .query_balance(env.contract.address.clone(), reward_denom.as_str())?;
 
     let previous_balance = state.prev_reward_balance;
 
@@ -118,22 +149,470 @@ pub fn execute_update_global_index(
 
     state.prev_reward_balance = balance.amount;
 
-    // global_index += claimed_rewards / total_balance;
-    state.global_index = decimal_summation_in_256(
+    let now = env.block.time.seconds();
+    release_rewards(&mut state, now);
+    match release_period {
+        // the rest of the previous rewards is released together with the new ones
+        Some(period) if period > 0 => {
+            state.unreleased_rewards += claimed_rewards;
+            state.last_release_time = now;
+            state.release_end_time = now + period;
+        }
+        // global_index += claimed_rewards / total_balance;
+        _ => add_to_global_index(&mut state, claimed_rewards),
+    }
+    let mut attributes = vec![
+        attr("action", "update_global_index"),
+        attr("claimed_rewards", claimed_rewards),
+    ];
+    // the other reward denoms are updated before the compound pool is swapped
+    // so that the swapped coins are not counted as their rewards
+    attributes.extend(update_reward_indices(&deps, &env, &mut state)?);
+    let (messages, compound_attrs) = compound_rewards(&mut deps, &env, &config, &mut state)?;
+    attributes.extend(compound_attrs);
+    store_state(deps.storage, &state)?;
+    store_index_checkpoint(
+        deps.storage,
+        &IndexCheckpoint {
+            time: now,
+            global_index: state.global_index,
+            total_balance: state.total_balance,
+            rewards: claimed_rewards,
+        },
+    )?;
+
+    let res = Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes);
+
+    Ok(res)
+}
+
+/// Adds the rewards received in each of the other reward denoms to its global index at once
+fn update_reward_indices(
+    deps: &DepsMut,
+    env: &Env,
+    state: &mut State,
+) -> StdResult<Vec<Attribute>> {
+    let mut attributes: Vec<Attribute> = vec![];
+    let compounded_index = state.compounded_index;
+    for reward_index in state.reward_indices.iter_mut() {
+        let balance = query_reward_balance(
+            deps.as_ref(),
+            env.contract.address.clone(),
+            reward_index.denom.as_str(),
+        )?;
+        let claimed_rewards = balance.checked_sub(reward_index.prev_reward_balance)?;
+        reward_index.prev_reward_balance = balance;
+        if claimed_rewards.is_zero() {
+            continue;
+        }
+        let index_delta = Decimal::from_ratio(claimed_rewards, state.total_balance);
+        reward_index.global_index =
+            decimal_summation_in_256(reward_index.global_index, index_delta);
+        reward_index.compounded_weighted_index = decimal_summation_in_256(
+            reward_index.compounded_weighted_index,
+            decimal_multiplication_in_256(compounded_index, index_delta),
+        );
+        attributes.push(attr(
+            "claimed_rewards",
+            format!("{}{}", claimed_rewards, reward_index.denom),
+        ));
+    }
+    Ok(attributes)
+}
+
+/// Moves the rewards of the auto-compounding holders to the compound pool and swaps the pool
+/// to the underlying coin of the hub to be bonded. The rewards of the bLuna compounded earlier
+/// and held by the contract are accrued to its holders by compounded_weighted_index instead.
+fn compound_rewards(
+    deps: &mut DepsMut,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+) -> StdResult<(Vec<SubMsg<TerraMsgWrapper>>, Vec<Attribute>)> {
+    accrue_compound_rewards(state);
+
+    let amount = state.compound_pending_rewards * Uint128::new(1);
+    if amount.is_zero() {
+        return Ok((vec![], vec![]));
+    }
+
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let underlying_denom = query_underlying_coin_denom(deps.as_ref(), hub_addr.clone())?;
+    let offer_coin = Coin {
+        denom: config.reward_denom.clone(),
+        amount,
+    };
+    let mut messages: Vec<SubMsg<TerraMsgWrapper>> = Vec::new();
+    if offer_coin.denom != underlying_denom {
+        // the pool is kept till the next update if it is too small to be swapped
+        let querier = TerraQuerier::new(&deps.querier);
+        let swap: SwapResponse =
+            querier.query_swap(offer_coin.clone(), underlying_denom.as_str())?;
+        if swap.receive.amount.is_zero() {
+            return Ok((vec![], vec![]));
+        }
+        // the pool swapped at a worse rate than the limit is kept till the next update as well
+        let dispatcher_addr =
+            deps.api
+                .addr_humanize(&query_rewards_dispatcher_contract_address(
+                    deps.as_ref(),
+                    hub_addr,
+                )?)?;
+        if let Some(max_deviation) = query_max_swap_deviation(deps.as_ref(), dispatcher_addr)? {
+            let deviation = query_swap_deviation(deps, &offer_coin, underlying_denom.as_str())?;
+            if deviation > max_deviation {
+                return Ok((
+                    vec![],
+                    vec![
+                        attr("skipped_swap", offer_coin.to_string()),
+                        attr("skipped_swap_deviation", deviation.to_string()),
+                    ],
+                ));
+            }
+        }
+        messages.push(SubMsg::new(create_swap_msg(offer_coin, underlying_denom)));
+    }
+    messages.push(SubMsg::new(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::BondCompoundedRewards {})?,
+        funds: vec![],
+    }));
+
+    state.compound_pending_rewards = decimal_subtraction_in_256(
+        state.compound_pending_rewards,
+        Decimal::from_ratio(amount, Uint128::new(1)),
+    );
+    state.prev_reward_balance = state.prev_reward_balance.checked_sub(amount)?;
+
+    Ok((messages, vec![]))
+}
+
+/// Bond the underlying coin balance of the contract, the minted bLuna is credited to the
+/// auto-compounding holders when the token contract increases the balance of the contract
+/// Only the contract itself is allowed to execute
+pub fn execute_bond_compounded_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let underlying_denom = query_underlying_coin_denom(deps.as_ref(), hub_addr.clone())?;
+    let mut balance = deps
+        .querier
+        .query_balance(env.contract.address, underlying_denom.as_str())?;
+    // the rewards distributed in the underlying denom are not bonded
+    let state = read_state(deps.storage)?;
+    if let Some(reward_index) = state
+        .reward_indices
+        .iter()
+        .find(|i| i.denom == underlying_denom)
+    {
+        balance.amount = balance
+            .amount
+            .saturating_sub(reward_index.prev_reward_balance);
+    }
+
+    let mut res = Response::new().add_attributes(vec![
+        attr("action", "bond_compounded_rewards"),
+        attr("amount", balance.to_string()),
+    ]);
+    if !balance.amount.is_zero() {
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: hub_addr.to_string(),
+            msg: to_binary(&HubExecuteMsg::Bond {})?,
+            funds: vec![balance],
+        });
+    }
+
+    Ok(res)
+}
+
+/// Adds the rewards accrued by the auto-compounding balance since the last call to the pool
+pub fn accrue_compound_rewards(state: &mut State) {
+    let rewards = calculate_decimal_rewards(
         state.global_index,
-        Decimal::from_ratio(claimed_rewards, state.total_balance),
+        state.compound_index,
+        state.compound_balance,
+    );
+    state.compound_pending_rewards =
+        decimal_summation_in_256(state.compound_pending_rewards, rewards);
+    state.compound_index = state.global_index;
+}
+
+/// Adds the part of the unreleased rewards linearly accrued by `now` to the global index
+pub fn release_rewards(state: &mut State, now: u64) {
+    if state.unreleased_rewards.is_zero()
+        || state.total_balance.is_zero()
+        || now <= state.last_release_time
+    {
+        return;
+    }
+
+    let released = if now >= state.release_end_time {
+        state.unreleased_rewards
+    } else {
+        state.unreleased_rewards.multiply_ratio(
+            now - state.last_release_time,
+            state.release_end_time - state.last_release_time,
+        )
+    };
+    state.unreleased_rewards -= released;
+    state.last_release_time = now;
+
+    // global_index += released_rewards / total_balance;
+    add_to_global_index(state, released);
+}
+
+/// Adds the rewards to the global index along with compounded_weighted_index,
+/// the bLuna compounded by that time is held by the contract while the rewards accrue
+fn add_to_global_index(state: &mut State, rewards: Uint128) {
+    let index_delta = Decimal::from_ratio(rewards, state.total_balance);
+    state.global_index = decimal_summation_in_256(state.global_index, index_delta);
+    state.compounded_weighted_index = decimal_summation_in_256(
+        state.compounded_weighted_index,
+        decimal_multiplication_in_256(state.compounded_index, index_delta),
     );
+}
+
+/// Swap the balance of the reward denom to the new denom and switch the rewards to it
+/// Only the rewards dispatcher is allowed to execute
+pub fn execute_migrate_reward_denom(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_denom: String,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let owner_addr = deps
+        .api
+        .addr_humanize(&query_rewards_dispatcher_contract_address(
+            deps.as_ref(),
+            hub_addr,
+        )?)?;
+
+    if info.sender != owner_addr {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if new_denom == config.reward_denom {
+        return Err(StdError::generic_err(format!(
+            "the rewards are already paid in {}",
+            new_denom
+        )));
+    }
+    let state = read_state(deps.storage)?;
+    if state.reward_indices.iter().any(|i| i.denom == new_denom) {
+        return Err(StdError::generic_err(format!(
+            "{} is already a reward denom",
+            new_denom
+        )));
+    }
+
+    let contr_addr = env.contract.address;
+    let prev_denom_amount = deps
+        .querier
+        .query_balance(contr_addr.clone(), config.reward_denom.as_str())?
+        .amount;
+    let new_denom_balance = deps
+        .querier
+        .query_balance(contr_addr.clone(), new_denom.as_str())?
+        .amount;
+
+    let mut messages: Vec<SubMsg<TerraMsgWrapper>> = Vec::new();
+    if !prev_denom_amount.is_zero() {
+        messages.push(SubMsg::new(create_swap_msg(
+            Coin {
+                denom: config.reward_denom.clone(),
+                amount: prev_denom_amount,
+            },
+            new_denom.clone(),
+        )));
+    }
+    // the rate of the swap is known only after it is executed
+    messages.push(SubMsg::new(WasmMsg::Execute {
+        contract_addr: contr_addr.to_string(),
+        msg: to_binary(&ExecuteMsg::CompleteRewardDenomMigration {
+            new_denom: new_denom.clone(),
+            prev_denom_amount,
+            new_denom_balance,
+        })?,
+        funds: vec![],
+    }));
+
+    let res = Response::new()
+        .add_submessages(messages)
+        .add_attributes(vec![
+            attr("action", "migrate_reward_denom"),
+            attr("prev_denom", config.reward_denom),
+            attr("new_denom", new_denom),
+        ]);
+
+    Ok(res)
+}
+
+/// Convert the accrued rewards to the new denom at the rate the previous one was swapped at
+/// Only the contract itself is allowed to execute
+pub fn execute_complete_reward_denom_migration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_denom: String,
+    prev_denom_amount: Uint128,
+    new_denom_balance: Uint128,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    accrue_compound_rewards(&mut state);
+
+    let received_amount = deps
+        .querier
+        .query_balance(env.contract.address, new_denom.as_str())?
+        .amount
+        .checked_sub(new_denom_balance)?;
+    let rate = if prev_denom_amount.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(received_amount, prev_denom_amount)
+    };
+
+    // the rest of the new balance is added to the global index by the next update
+    state.prev_reward_balance = state.prev_reward_balance * rate;
+    state.unreleased_rewards = state.unreleased_rewards * rate;
+    state.compound_pending_rewards =
+        decimal_multiplication_in_256(state.compound_pending_rewards, rate);
+    state.denom_migrations.push(DenomMigration {
+        global_index: state.global_index,
+        rate,
+        compounded_weighted_index: state.compounded_weighted_index,
+    });
     store_state(deps.storage, &state)?;
 
-    let attributes = vec![
-        attr("action", "update_global_index"),
-        attr("claimed_rewards", claimed_rewards),
-    ];
-    let res = Response::new().add_attributes(attributes);
+    let mut config = read_config(deps.storage)?;
+    config.reward_denom = new_denom;
+    store_config(deps.storage, &config)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "complete_reward_denom_migration"),
+        attr("received_amount", received_amount),
+        attr("rate", rate.to_string()),
+    ]);
+
+    Ok(res)
+}
+
+// the number of the reward denoms is limited by the gas of the balance updates
+const MAX_REWARD_DENOMS: usize = 10;
+
+/// Start distributing the rewards received in the denom as they are
+/// Only the owner of the hub is allowed to execute
+pub fn execute_add_reward_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    if query_hub_owner(deps.as_ref(), hub_addr)?
+        != deps.api.addr_canonicalize(info.sender.as_str())?
+    {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if let Some(token_addr) = denom.strip_prefix(CW20_DENOM_PREFIX) {
+        deps.api.addr_validate(token_addr)?;
+    }
+    let mut state: State = read_state(deps.storage)?;
+    if denom == config.reward_denom || state.reward_indices.iter().any(|i| i.denom == denom) {
+        return Err(StdError::generic_err(format!(
+            "{} is already a reward denom",
+            denom
+        )));
+    }
+    if state.reward_indices.len() >= MAX_REWARD_DENOMS {
+        return Err(StdError::generic_err(format!(
+            "the number of reward denoms can't be greater than {}",
+            MAX_REWARD_DENOMS
+        )));
+    }
+
+    // the balance the contract already has is added to the index by the next update
+    state.reward_indices.push(RewardIndex {
+        denom: denom.clone(),
+        global_index: Decimal::zero(),
+        prev_reward_balance: Uint128::zero(),
+        compounded_weighted_index: Decimal::zero(),
+    });
+    store_state(deps.storage, &state)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "add_reward_denom"),
+        attr("denom", denom),
+    ]);
 
     Ok(res)
 }
 
+/// Converts the rewards accrued by the holder before the reward denom migrations to the new denom
+pub fn apply_denom_migrations(state: &State, holder: &mut Holder) {
+    for migration in state
+        .denom_migrations
+        .iter()
+        .skip(holder.denom_migrations as usize)
+    {
+        // the rewards of the auto-compounding holders are accrued to the compound pool,
+        // the excluded holders accrue no rewards
+        let rewards = if holder.auto_compound || holder.excluded {
+            Decimal::zero()
+        } else {
+            calculate_decimal_rewards(migration.global_index, holder.index, holder.balance)
+        };
+        accrue_held_compounded_rewards(
+            holder,
+            migration.global_index,
+            migration.compounded_weighted_index,
+        );
+        holder.pending_rewards = decimal_multiplication_in_256(
+            decimal_summation_in_256(rewards, holder.pending_rewards),
+            migration.rate,
+        );
+        holder.index = migration.global_index;
+    }
+    holder.denom_migrations = state.denom_migrations.len() as u32;
+}
+
+/// Returns the deviation of the market swap of the coin from the oracle exchange rate
+fn query_swap_deviation(deps: &DepsMut, offer_coin: &Coin, ask_denom: &str) -> StdResult<Decimal> {
+    let querier = TerraQuerier::new(&deps.querier);
+    let ask_2_offer_xchg_rate =
+        query_exchange_rates(deps, ask_denom.to_string(), vec![offer_coin.denom.clone()])?
+            .exchange_rates
+            .into_iter()
+            .find(|item| item.quote_denom == offer_coin.denom)
+            .ok_or_else(|| StdError::generic_err("no exchange rate for the swap"))?
+            .exchange_rate;
+    let offer_2_ask_xchg_rate = ask_2_offer_xchg_rate
+        .inv()
+        .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?;
+
+    let swap_response: SwapResponse = querier.query_swap(offer_coin.clone(), ask_denom)?;
+    Ok(compute_swap_deviation(
+        offer_coin.amount * offer_2_ask_xchg_rate,
+        swap_response.receive.amount,
+    ))
+}
+
 pub fn query_exchange_rates(
     deps: &DepsMut,
     base_denom: String,
@@ -12,10 +12,71 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use basset::hub::{ConfigResponse, QueryMsg};
+use basset::hub::{ConfigResponse, Parameters, QueryMsg};
 use cosmwasm_std::{
-    to_binary, Addr, CanonicalAddr, Deps, QueryRequest, StdError, StdResult, WasmQuery,
+    to_binary, Addr, CanonicalAddr, Coin, Decimal, Deps, QueryRequest, StdError, StdResult,
+    Uint128, WasmQuery,
 };
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The rewards dispatcher depends on this crate, so its messages are mirrored here
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardsDispatcherQueryMsg {
+    Config {},
+    PendingRewards {},
+}
+
+/// The part of the rewards dispatcher config used by the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardsDispatcherConfigResponse {
+    pub max_swap_deviation: Option<Decimal>,
+}
+
+/// The part of the pending rewards of the rewards dispatcher used by the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardsDispatcherPendingRewardsResponse {
+    pub bluna_rewards: Coin,
+}
+
+/// The transfer message of the bLuna token, the contract depends on an older cw20
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenQueryMsg {
+    Balance { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenBalanceResponse {
+    pub balance: Uint128,
+}
+
+/// The prefix of the reward denoms of the cw20 tokens
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Returns the balance of the contract in the reward denom, native or cw20
+pub fn query_reward_balance(deps: Deps, contract_addr: Addr, denom: &str) -> StdResult<Uint128> {
+    match denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token_addr) => {
+            let res: TokenBalanceResponse =
+                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: token_addr.to_string(),
+                    msg: to_binary(&TokenQueryMsg::Balance {
+                        address: contract_addr.to_string(),
+                    })?,
+                }))?;
+            Ok(res.balance)
+        }
+        None => Ok(deps.querier.query_balance(contract_addr, denom)?.amount),
+    }
+}
 
 pub fn query_token_contract_address(
     deps: Deps,
@@ -33,6 +94,15 @@ pub fn query_token_contract_address(
     )
 }
 
+pub fn query_hub_owner(deps: Deps, hub_contract_addr: Addr) -> StdResult<CanonicalAddr> {
+    let conf: ConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_contract_addr.to_string(),
+        msg: to_binary(&QueryMsg::Config {})?,
+    }))?;
+
+    deps.api.addr_canonicalize(conf.owner.as_str())
+}
+
 pub fn query_rewards_dispatcher_contract_address(
     deps: Deps,
     hub_contract_addr: Addr,
@@ -50,3 +120,39 @@ pub fn query_rewards_dispatcher_contract_address(
             .as_str(),
     )
 }
+
+pub fn query_max_swap_deviation(
+    deps: Deps,
+    rewards_dispatcher_contract_addr: Addr,
+) -> StdResult<Option<Decimal>> {
+    let conf: RewardsDispatcherConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: rewards_dispatcher_contract_addr.to_string(),
+            msg: to_binary(&RewardsDispatcherQueryMsg::Config {})?,
+        }))?;
+
+    Ok(conf.max_swap_deviation)
+}
+
+/// Returns the estimate of the rewards the next dispatch sends to the contract
+pub fn query_pending_dispatch_rewards(
+    deps: Deps,
+    rewards_dispatcher_contract_addr: Addr,
+) -> StdResult<Coin> {
+    let res: RewardsDispatcherPendingRewardsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: rewards_dispatcher_contract_addr.to_string(),
+            msg: to_binary(&RewardsDispatcherQueryMsg::PendingRewards {})?,
+        }))?;
+
+    Ok(res.bluna_rewards)
+}
+
+pub fn query_underlying_coin_denom(deps: Deps, hub_contract_addr: Addr) -> StdResult<String> {
+    let params: Parameters = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_contract_addr.to_string(),
+        msg: to_binary(&QueryMsg::Parameters {})?,
+    }))?;
+
+    Ok(params.underlying_coin_denom)
+}
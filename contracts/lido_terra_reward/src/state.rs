@@ -12,17 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Decimal, Deps, Order, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    Addr, Api, Binary, CanonicalAddr, Decimal, Deps, Order, StdError, StdResult, Storage, Uint128,
+};
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::global::apply_denom_migrations;
+use crate::math::{
+    decimal_multiplication_in_256, decimal_subtraction_in_256, decimal_summation_in_256,
+};
 use basset::reward::HolderResponse;
 use cw_storage_plus::{Bound, Item, Map};
 
 pub const STATE: Item<State> = Item::new("\u{0}\u{5}state");
 pub const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
 pub const HOLDERS: Map<&[u8], Holder> = Map::new("holders");
+// the holders with a nonzero balance keyed by the balance followed by the address
+pub const HOLDERS_BY_BALANCE: Map<&[u8], ()> = Map::new("holders_by_balance");
+pub const HOLDER_STATS: Item<HolderStats> = Item::new("holder_stats");
+pub const CLAIM_SETTINGS: Map<&[u8], ClaimSettings> = Map::new("claim_settings");
+pub const INDEX_CHECKPOINTS: Map<&[u8], IndexCheckpoint> = Map::new("index_checkpoints");
+pub const HOLDER_CHECKPOINTS: Map<(&[u8], &[u8]), HolderCheckpoint> =
+    Map::new("holder_checkpoints");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -43,6 +56,57 @@ pub struct State {
     pub global_index: Decimal,
     pub total_balance: Uint128,
     pub prev_reward_balance: Uint128,
+    // the part of the claimed rewards not yet added to the global index
+    #[serde(default)]
+    pub unreleased_rewards: Uint128,
+    #[serde(default)]
+    pub last_release_time: u64,
+    #[serde(default)]
+    pub release_end_time: u64,
+    // the reward denom migrations the rewards accrued by the holders are converted by
+    #[serde(default)]
+    pub denom_migrations: Vec<DenomMigration>,
+    // the total balance of the auto-compounding holders
+    #[serde(default)]
+    pub compound_balance: Uint128,
+    // the global index the rewards of the auto-compounding holders are accrued till
+    #[serde(default)]
+    pub compound_index: Decimal,
+    // the rewards of the auto-compounding holders not yet swapped and bonded
+    #[serde(default)]
+    pub compound_pending_rewards: Decimal,
+    // the bLuna compounded per unit of the auto-compounding balance
+    #[serde(default)]
+    pub compounded_index: Decimal,
+    // the increments of the global index weighted by compounded_index at their time,
+    // the rewards of the compounded bLuna held by the contract are accrued by it
+    #[serde(default)]
+    pub compounded_weighted_index: Decimal,
+    // the indices of the rewards distributed in the other denoms than the reward denom
+    #[serde(default)]
+    pub reward_indices: Vec<RewardIndex>,
+}
+
+/// The global index of the rewards distributed in `denom` as they are
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardIndex {
+    pub denom: String,
+    pub global_index: Decimal,
+    pub prev_reward_balance: Uint128,
+    // the global index increments weighted by compounded_index of the state,
+    // the rewards of the compounded bLuna held by the contract are accrued by it
+    #[serde(default)]
+    pub compounded_weighted_index: Decimal,
+}
+
+/// The rewards accrued till the global index reached `global_index` are paid in the new denom
+/// at the `rate` the balance of the previous denom was swapped at
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomMigration {
+    pub global_index: Decimal,
+    pub rate: Decimal,
+    #[serde(default)]
+    pub compounded_weighted_index: Decimal,
 }
 
 pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -58,6 +122,40 @@ pub struct Holder {
     pub balance: Uint128,
     pub index: Decimal,
     pub pending_rewards: Decimal,
+    // the number of the reward denom migrations applied to the pending rewards
+    #[serde(default)]
+    pub denom_migrations: u32,
+    #[serde(default)]
+    pub auto_compound: bool,
+    #[serde(default)]
+    pub compounded_index: Decimal,
+    // the bLuna compounded for the holder and not withdrawn yet
+    #[serde(default)]
+    pub compounded_rewards: Decimal,
+    // the global and the weighted indices the rewards of the compounded bLuna
+    // of the holder are accrued till, unset before the first update of the holder
+    #[serde(default)]
+    pub compounded_global_index: Option<Decimal>,
+    #[serde(default)]
+    pub compounded_weighted_index: Decimal,
+    // the balance of the excluded holder is not counted in the total balance
+    #[serde(default)]
+    pub excluded: bool,
+    // the rewards of the holder are claimed to the beneficiary
+    #[serde(default)]
+    pub beneficiary: Option<CanonicalAddr>,
+    // the indices of the holder in the other denoms, a missing one is zero
+    #[serde(default)]
+    pub reward_indices: Vec<HolderRewardIndex>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderRewardIndex {
+    pub denom: String,
+    pub index: Decimal,
+    pub pending_rewards: Decimal,
+    #[serde(default)]
+    pub compounded_weighted_index: Decimal,
 }
 
 // This is similar to HashMap<holder's address, Hodler>
@@ -66,6 +164,12 @@ pub fn store_holder(
     holder_address: &CanonicalAddr,
     holder: &Holder,
 ) -> StdResult<()> {
+    let mut stats = read_holder_stats(storage)?;
+    if let Some(prev_holder) = HOLDERS.may_load(storage, holder_address.as_slice())? {
+        unindex_holder(storage, &mut stats, holder_address, &prev_holder);
+    }
+    index_holder(storage, &mut stats, holder_address, holder)?;
+    HOLDER_STATS.save(storage, &stats)?;
     HOLDERS.save(storage, holder_address.as_slice(), holder)
 }
 
@@ -77,10 +181,345 @@ pub fn read_holder(storage: &dyn Storage, holder_address: &CanonicalAddr) -> Std
             balance: Uint128::zero(),
             index: Decimal::zero(),
             pending_rewards: Decimal::zero(),
+            denom_migrations: 0,
+            auto_compound: false,
+            compounded_index: Decimal::zero(),
+            compounded_rewards: Decimal::zero(),
+            compounded_global_index: None,
+            compounded_weighted_index: Decimal::zero(),
+            excluded: false,
+            beneficiary: None,
+            reward_indices: vec![],
         }),
     }
 }
 
+/// HolderStats is kept up to date with the holders by `store_holder`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HolderStats {
+    // the number of the holders with a nonzero balance
+    pub holder_count: u64,
+    // the sum of the pending rewards settled to the holders at their last updates,
+    // the rewards accrued by the global index since are not settled yet
+    pub settled_pending_rewards: Decimal,
+    // the balance of the holders accruing the rewards to their pending rewards
+    // and the sum of the balances weighted by the indices of the holders,
+    // the rewards not settled yet are global_index * rewarded_balance - weighted_index
+    #[serde(default)]
+    pub rewarded_balance: Uint128,
+    #[serde(default)]
+    pub weighted_index: Decimal,
+    // the last holder indexed while the holders stored before the stats were kept
+    // are indexed, the holders after it are indexed by the next batches
+    #[serde(default)]
+    pub indexed_till: Option<Vec<u8>>,
+}
+
+impl HolderStats {
+    fn is_indexed(&self, holder_address: &CanonicalAddr) -> bool {
+        match &self.indexed_till {
+            Some(last) => holder_address.as_slice() <= last.as_slice(),
+            None => true,
+        }
+    }
+}
+
+pub fn read_holder_stats(storage: &dyn Storage) -> StdResult<HolderStats> {
+    Ok(HOLDER_STATS.may_load(storage)?.unwrap_or_default())
+}
+
+/// Returns the pending rewards of the holders at `global_index`, the settled ones along with
+/// the ones accrued since. The rewards of the bLuna held by the contract are accrued
+/// to the holders it is compounded for and are not counted.
+pub fn read_total_pending_rewards(
+    storage: &dyn Storage,
+    global_index: Decimal,
+    contract_address: &CanonicalAddr,
+) -> StdResult<Decimal> {
+    let stats = read_holder_stats(storage)?;
+    let mut rewarded_balance = stats.rewarded_balance;
+    let mut weighted_index = stats.weighted_index;
+    let contract_holder = read_holder(storage, contract_address)?;
+    if stats.is_indexed(contract_address) && accrues_pending_rewards(&contract_holder) {
+        rewarded_balance = rewarded_balance.saturating_sub(contract_holder.balance);
+        weighted_index =
+            decimal_subtraction_in_256(weighted_index, weighted_holder_index(&contract_holder));
+    }
+
+    let accrued_rewards = decimal_subtraction_in_256(
+        decimal_multiplication_in_256(
+            global_index,
+            Decimal::from_ratio(rewarded_balance, Uint128::new(1)),
+        ),
+        weighted_index,
+    );
+    Ok(decimal_summation_in_256(
+        stats.settled_pending_rewards,
+        accrued_rewards,
+    ))
+}
+
+fn balance_key(balance: Uint128, holder_address: &CanonicalAddr) -> Vec<u8> {
+    let mut key = balance.u128().to_be_bytes().to_vec();
+    key.extend_from_slice(holder_address.as_slice());
+    key
+}
+
+// the rewards of the auto-compounding holders are compounded instead
+fn accrues_pending_rewards(holder: &Holder) -> bool {
+    !holder.auto_compound && !holder.excluded
+}
+
+fn weighted_holder_index(holder: &Holder) -> Decimal {
+    decimal_multiplication_in_256(
+        holder.index,
+        Decimal::from_ratio(holder.balance, Uint128::new(1)),
+    )
+}
+
+fn index_holder(
+    storage: &mut dyn Storage,
+    stats: &mut HolderStats,
+    holder_address: &CanonicalAddr,
+    holder: &Holder,
+) -> StdResult<()> {
+    if !stats.is_indexed(holder_address) {
+        return Ok(());
+    }
+    stats.settled_pending_rewards =
+        decimal_summation_in_256(stats.settled_pending_rewards, holder.pending_rewards);
+    if accrues_pending_rewards(holder) {
+        stats.rewarded_balance += holder.balance;
+        stats.weighted_index =
+            decimal_summation_in_256(stats.weighted_index, weighted_holder_index(holder));
+    }
+    if !holder.balance.is_zero() {
+        stats.holder_count += 1;
+        HOLDERS_BY_BALANCE.save(storage, &balance_key(holder.balance, holder_address), &())?;
+    }
+    Ok(())
+}
+
+fn unindex_holder(
+    storage: &mut dyn Storage,
+    stats: &mut HolderStats,
+    holder_address: &CanonicalAddr,
+    holder: &Holder,
+) {
+    if !stats.is_indexed(holder_address) {
+        return;
+    }
+    stats.settled_pending_rewards =
+        decimal_subtraction_in_256(stats.settled_pending_rewards, holder.pending_rewards);
+    if accrues_pending_rewards(holder) {
+        stats.rewarded_balance = stats.rewarded_balance.saturating_sub(holder.balance);
+        stats.weighted_index =
+            decimal_subtraction_in_256(stats.weighted_index, weighted_holder_index(holder));
+    }
+    if !holder.balance.is_zero() {
+        stats.holder_count = stats.holder_count.saturating_sub(1);
+        HOLDERS_BY_BALANCE.remove(storage, &balance_key(holder.balance, holder_address));
+    }
+}
+
+// the number of the holders indexed by a batch if the limit isn't set
+const DEFAULT_INDEX_HOLDERS_LIMIT: u32 = 100;
+
+/// Starts building the holder stats and the balance index from the holders stored
+/// before they were kept, the holders are indexed by `index_holders` in batches
+pub fn start_holders_indexing(storage: &mut dyn Storage) -> StdResult<()> {
+    HOLDER_STATS.save(
+        storage,
+        &HolderStats {
+            indexed_till: Some(vec![]),
+            ..HolderStats::default()
+        },
+    )
+}
+
+/// Indexes the next `limit` holders and returns the number of them,
+/// the indexing is done once a batch is not full
+pub fn index_holders(storage: &mut dyn Storage, limit: Option<u32>) -> StdResult<usize> {
+    let limit = limit.unwrap_or(DEFAULT_INDEX_HOLDERS_LIMIT) as usize;
+    let mut stats = read_holder_stats(storage)?;
+    let start = match &stats.indexed_till {
+        Some(last) if last.is_empty() => None,
+        Some(last) => Some(Bound::exclusive(last.clone())),
+        None => return Err(StdError::generic_err("the holders are already indexed")),
+    };
+    let holders: Vec<(Vec<u8>, Holder)> = HOLDERS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    // the holders of the batch are indexed once the cursor passes them
+    if let Some((address, _)) = holders.last() {
+        stats.indexed_till = Some(address.clone());
+    }
+    for (address, holder) in holders.iter() {
+        index_holder(
+            storage,
+            &mut stats,
+            &CanonicalAddr::from(address.clone()),
+            holder,
+        )?;
+    }
+    if holders.len() < limit {
+        stats.indexed_till = None;
+    }
+    HOLDER_STATS.save(storage, &stats)?;
+    Ok(holders.len())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ClaimSettings {
+    // the addresses allowed to claim the rewards of the holder
+    pub operators: Vec<CanonicalAddr>,
+    pub routes: Vec<RewardRouteInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardRouteInfo {
+    pub recipient: CanonicalAddr,
+    pub share: Decimal,
+    pub msg: Option<Binary>,
+}
+
+pub fn store_claim_settings(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    settings: &ClaimSettings,
+) -> StdResult<()> {
+    CLAIM_SETTINGS.save(storage, holder_address.as_slice(), settings)
+}
+
+pub fn read_claim_settings(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+) -> StdResult<ClaimSettings> {
+    Ok(CLAIM_SETTINGS
+        .may_load(storage, holder_address.as_slice())?
+        .unwrap_or_default())
+}
+
+/// IndexCheckpoint keeps the state of the global index after an update
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexCheckpoint {
+    pub time: u64,
+    pub global_index: Decimal,
+    pub total_balance: Uint128,
+    // the rewards claimed by the update
+    pub rewards: Uint128,
+}
+
+/// HolderCheckpoint keeps the balance of the holder accruing the rewards after a change
+/// and the global index at the time of the change
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderCheckpoint {
+    pub time: u64,
+    pub balance: Uint128,
+    pub index: Decimal,
+}
+
+/// The checkpoints older than the period are pruned, except the latest of them
+/// the rewards of the period are calculated from
+pub const CHECKPOINTS_RETENTION_PERIOD: u64 = 365 * 24 * 60 * 60;
+// the number of the outdated checkpoints removed by a write at most
+const PRUNE_CHECKPOINTS_LIMIT: usize = 2;
+
+// the keys of the outdated checkpoints to remove, the last of them is kept
+fn outdated_checkpoints(keys: Box<dyn Iterator<Item = Vec<u8>> + '_>) -> Vec<Vec<u8>> {
+    let mut keys: Vec<Vec<u8>> = keys.take(PRUNE_CHECKPOINTS_LIMIT + 1).collect();
+    keys.pop();
+    keys
+}
+
+/// Stores the checkpoint and prunes the ones older than CHECKPOINTS_RETENTION_PERIOD
+pub fn store_index_checkpoint(
+    storage: &mut dyn Storage,
+    checkpoint: &IndexCheckpoint,
+) -> StdResult<()> {
+    INDEX_CHECKPOINTS.save(storage, &checkpoint.time.to_be_bytes(), checkpoint)?;
+
+    let cutoff = checkpoint.time.saturating_sub(CHECKPOINTS_RETENTION_PERIOD);
+    let end = Bound::inclusive(cutoff.to_be_bytes().to_vec());
+    let outdated =
+        outdated_checkpoints(INDEX_CHECKPOINTS.keys(storage, None, Some(end), Order::Ascending));
+    for key in outdated {
+        INDEX_CHECKPOINTS.remove(storage, &key);
+    }
+    Ok(())
+}
+
+/// Returns the latest index checkpoint made at or before `time`
+pub fn read_index_checkpoint_at(
+    storage: &dyn Storage,
+    time: u64,
+) -> StdResult<Option<IndexCheckpoint>> {
+    let end = Bound::inclusive(time.to_be_bytes().to_vec());
+    INDEX_CHECKPOINTS
+        .range(storage, None, Some(end), Order::Descending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
+
+/// Stores the checkpoint and prunes the ones older than CHECKPOINTS_RETENTION_PERIOD
+pub fn store_holder_checkpoint(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    checkpoint: &HolderCheckpoint,
+) -> StdResult<()> {
+    HOLDER_CHECKPOINTS.save(
+        storage,
+        (holder_address.as_slice(), &checkpoint.time.to_be_bytes()),
+        checkpoint,
+    )?;
+
+    let cutoff = checkpoint.time.saturating_sub(CHECKPOINTS_RETENTION_PERIOD);
+    let end = Bound::inclusive(cutoff.to_be_bytes().to_vec());
+    let outdated = outdated_checkpoints(HOLDER_CHECKPOINTS.prefix(holder_address.as_slice()).keys(
+        storage,
+        None,
+        Some(end),
+        Order::Ascending,
+    ));
+    for key in outdated {
+        HOLDER_CHECKPOINTS.remove(storage, (holder_address.as_slice(), &key));
+    }
+    Ok(())
+}
+
+/// Returns the latest checkpoint of the holder made at or before `time`
+pub fn read_holder_checkpoint_at(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+    time: u64,
+) -> StdResult<Option<HolderCheckpoint>> {
+    let end = Bound::inclusive(time.to_be_bytes().to_vec());
+    HOLDER_CHECKPOINTS
+        .prefix(holder_address.as_slice())
+        .range(storage, None, Some(end), Order::Descending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
+
+/// Returns the checkpoints of the holder made after `from` and at or before `to`
+pub fn read_holder_checkpoints_between(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+    from: u64,
+    to: u64,
+) -> StdResult<Vec<HolderCheckpoint>> {
+    let start = Bound::exclusive(from.to_be_bytes().to_vec());
+    let end = Bound::inclusive(to.to_be_bytes().to_vec());
+    HOLDER_CHECKPOINTS
+        .prefix(holder_address.as_slice())
+        .range(storage, Some(start), Some(end), Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -91,12 +530,14 @@ pub fn read_holders(
 ) -> StdResult<Vec<HolderResponse>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(deps.api, start_after.map(Addr::unchecked))?.map(Bound::exclusive);
+    let state = read_state(deps.storage)?;
 
     HOLDERS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|elem| {
-            let (k, v) = elem?;
+            let (k, mut v) = elem?;
+            apply_denom_migrations(&state, &mut v);
             let address: String = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
             Ok(HolderResponse {
                 address,
@@ -108,6 +549,61 @@ pub fn read_holders(
         .collect()
 }
 
+/// Returns the holders with a nonzero balance ordered by the balance,
+/// the ties are ordered by the address
+pub fn read_holders_by_balance(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+    order: Order,
+) -> StdResult<Vec<HolderResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_key = match start_after {
+        Some(address) => {
+            let address_raw = deps.api.addr_canonicalize(address.as_str())?;
+            let holder = read_holder(deps.storage, &address_raw)?;
+            Some(balance_key(holder.balance, &address_raw))
+        }
+        None => None,
+    };
+    let (start, end) = match order {
+        Order::Ascending => (start_key.map(Bound::exclusive), None),
+        Order::Descending => (None, start_key.map(Bound::exclusive)),
+    };
+    let state = read_state(deps.storage)?;
+
+    HOLDERS_BY_BALANCE
+        .keys(deps.storage, start, end, order)
+        .take(limit)
+        .map(|key| {
+            let address_raw = CanonicalAddr::from(key[16..].to_vec());
+            let mut holder = read_holder(deps.storage, &address_raw)?;
+            apply_denom_migrations(&state, &mut holder);
+            Ok(HolderResponse {
+                address: deps.api.addr_humanize(&address_raw)?.to_string(),
+                balance: holder.balance,
+                index: holder.index,
+                pending_rewards: holder.pending_rewards,
+            })
+        })
+        .collect()
+}
+
+pub fn read_index_checkpoints(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<IndexCheckpoint>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|time| Bound::exclusive(time.to_be_bytes().to_vec()));
+
+    INDEX_CHECKPOINTS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(api: &dyn Api, start_after: Option<Addr>) -> StdResult<Option<Vec<u8>>> {
     match start_after {
@@ -167,6 +663,16 @@ mod test {
                 global_index: Default::default(),
                 total_balance: Default::default(),
                 prev_reward_balance: Default::default(),
+                unreleased_rewards: Default::default(),
+                last_release_time: Default::default(),
+                release_end_time: Default::default(),
+                denom_migrations: Default::default(),
+                compound_balance: Default::default(),
+                compound_index: Default::default(),
+                compound_pending_rewards: Default::default(),
+                compounded_index: Default::default(),
+                compounded_weighted_index: Default::default(),
+                reward_indices: Default::default(),
             },
         )
         .unwrap();
@@ -209,6 +715,15 @@ mod test {
                     balance: Uint128::from(200u128),
                     index: Default::default(),
                     pending_rewards: Default::default(),
+                    denom_migrations: Default::default(),
+                    auto_compound: Default::default(),
+                    compounded_index: Default::default(),
+                    compounded_rewards: Default::default(),
+                    compounded_global_index: Default::default(),
+                    compounded_weighted_index: Default::default(),
+                    excluded: Default::default(),
+                    beneficiary: Default::default(),
+                    reward_indices: Default::default(),
                 },
             )
             .unwrap();
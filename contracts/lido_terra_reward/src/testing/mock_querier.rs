@@ -12,16 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use basset::hub::ConfigResponse;
+use crate::querier::{
+    RewardsDispatcherConfigResponse, RewardsDispatcherPendingRewardsResponse,
+    RewardsDispatcherQueryMsg,
+};
+use basset::hub::{ConfigResponse, Parameters, QueryMsg as HubQueryMsg};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier, QuerierResult,
-    QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, Fraction, OwnedDeps,
+    Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use std::str::FromStr;
 use terra_cosmwasm::{
-    ExchangeRateItem, ExchangeRatesResponse, TaxCapResponse, TaxRateResponse, TerraQuery,
-    TerraQueryWrapper, TerraRoute,
+    ExchangeRateItem, ExchangeRatesResponse, SwapResponse, TaxCapResponse, TaxRateResponse,
+    TerraQuery, TerraQueryWrapper, TerraRoute,
 };
 
 pub const MOCK_HUB_CONTRACT_ADDR: &str = "hub";
@@ -46,6 +50,10 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
+    max_swap_deviation: Option<Decimal>,
+    pending_dispatch_rewards: Coin,
+    // the share the market swaps lose against the oracle rate
+    swap_spread: Decimal,
 }
 
 impl Querier for WasmMockQuerier {
@@ -104,15 +112,47 @@ impl WasmMockQuerier {
                         }
                         _ => panic!("DO NOT ENTER HERE"),
                     }
+                } else if &TerraRoute::Market == route {
+                    match query_data {
+                        TerraQuery::Swap {
+                            offer_coin,
+                            ask_denom,
+                        } => {
+                            let mut receive = offer_coin.amount
+                                * Decimal::from_str("22.1").unwrap().inv().unwrap();
+                            // the usdr market is thin, the swap returns half of the oracle rate
+                            if offer_coin.denom == "usdr" {
+                                receive = receive.multiply_ratio(1u128, 2u128);
+                            }
+                            receive = receive - receive * self.swap_spread;
+                            SystemResult::Ok(ContractResult::from(to_binary(&SwapResponse {
+                                receive: Coin {
+                                    denom: ask_denom.to_string(),
+                                    amount: receive,
+                                },
+                            })))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
                 } else {
                     panic!("DO NOT ENTER HERE")
                 }
             }
-            QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr,
-                msg: _,
-            }) => {
-                if *contract_addr == MOCK_HUB_CONTRACT_ADDR {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if *contract_addr == MOCK_HUB_CONTRACT_ADDR
+                    && from_binary(msg) == Ok(HubQueryMsg::Parameters {})
+                {
+                    let params = Parameters {
+                        epoch_period: 30,
+                        underlying_coin_denom: "uluna".to_string(),
+                        unbonding_period: 210,
+                        peg_recovery_fee: Decimal::zero(),
+                        er_threshold: Decimal::one(),
+                        reward_denom: "uusd".to_string(),
+                        paused: None,
+                    };
+                    SystemResult::Ok(ContractResult::from(to_binary(&params)))
+                } else if *contract_addr == MOCK_HUB_CONTRACT_ADDR {
                     let config = ConfigResponse {
                         owner: String::from("owner1"),
                         reward_dispatcher_contract: Some(String::from(
@@ -128,6 +168,21 @@ impl WasmMockQuerier {
                         token_contract: Some(String::from(MOCK_TOKEN_CONTRACT_ADDR)),
                     };
                     SystemResult::Ok(ContractResult::from(to_binary(&config)))
+                } else if *contract_addr == MOCK_REWARDS_DISPATCHER_ADDR {
+                    match from_binary(msg).unwrap() {
+                        RewardsDispatcherQueryMsg::Config {} => {
+                            let config = RewardsDispatcherConfigResponse {
+                                max_swap_deviation: self.max_swap_deviation,
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&config)))
+                        }
+                        RewardsDispatcherQueryMsg::PendingRewards {} => {
+                            let res = RewardsDispatcherPendingRewardsResponse {
+                                bluna_rewards: self.pending_dispatch_rewards.clone(),
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                        }
+                    }
                 } else {
                     unimplemented!()
                 }
@@ -139,6 +194,29 @@ impl WasmMockQuerier {
 
 impl WasmMockQuerier {
     pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            max_swap_deviation: None,
+            pending_dispatch_rewards: Coin::new(0, "uusd"),
+            swap_spread: Decimal::zero(),
+        }
+    }
+
+    pub fn with_swap_spread(&mut self, swap_spread: Decimal) {
+        self.swap_spread = swap_spread;
+    }
+
+    pub fn with_max_swap_deviation(&mut self, max_swap_deviation: Option<Decimal>) {
+        self.max_swap_deviation = max_swap_deviation;
+    }
+
+    pub fn with_pending_dispatch_rewards(&mut self, rewards: Coin) {
+        self.pending_dispatch_rewards = rewards;
+    }
+
+    pub fn with_native_balances(&mut self, balances: &[(&str, &[Coin])]) {
+        for (addr, balance) in balances {
+            self.base.update_balance(*addr, balance.to_vec());
+        }
     }
 }
@@ -31,22 +31,31 @@
 //!      });
 //! 4. Anywhere you see query(deps.as_ref(), mock_env(),...) you must replace it with query(&mut deps, ...)
 
-use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, StdError, SubMsg, Uint128,
+    attr, from_binary, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, StdError,
+    SubMsg, Uint128, WasmMsg,
 };
-use terra_cosmwasm::create_swap_msg;
+use terra_cosmwasm::{create_swap_msg, create_swap_send_msg};
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::math::{decimal_multiplication_in_256, decimal_subtraction_in_256};
-use crate::state::{store_holder, store_state, Holder, State};
+use crate::querier::TokenExecuteMsg;
+use crate::state::{
+    start_holders_indexing, store_holder, store_state, Holder, State, CHECKPOINTS_RETENTION_PERIOD,
+    HOLDER_STATS,
+};
 use crate::testing::mock_querier::{
     mock_dependencies, MOCK_HUB_CONTRACT_ADDR, MOCK_REWARDS_DISPATCHER_ADDR,
     MOCK_TOKEN_CONTRACT_ADDR,
 };
+use basset::deduct_tax;
 use basset::reward::{
-    ConfigResponse, ExecuteMsg, HolderResponse, HoldersResponse, InstantiateMsg, QueryMsg,
-    StateResponse,
+    AccruedRewardsResponse, ClaimSettingsResponse, CompoundedRewardsResponse, ConfigResponse,
+    ExecuteMsg, HolderResponse, HolderStatus, HolderStatusResponse, HoldersResponse,
+    IndexCheckpointResponse, IndexCheckpointsResponse, InstantiateMsg, MigrateMsg,
+    PendingRewardsResponse, QueryMsg, RewardIndexResponse, RewardRoute, RewardsBetweenResponse,
+    StateResponse, StatsResponse,
 };
 use std::str::FromStr;
 
@@ -86,7 +95,9 @@ fn proper_init() {
         StateResponse {
             global_index: Decimal::zero(),
             total_balance: Uint128::new(0u128),
-            prev_reward_balance: Uint128::zero()
+            prev_reward_balance: Uint128::zero(),
+            unreleased_rewards: Uint128::zero(),
+            reward_indices: vec![],
         }
     );
 }
@@ -153,6 +164,51 @@ pub fn swap_to_reward_denom() {
     );
 }
 
+#[test]
+pub fn swap_to_reward_denom_max_deviation() {
+    let mut deps = mock_dependencies(&[
+        Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::new(1000u128),
+        },
+        Coin {
+            denom: "usdr".to_string(),
+            amount: Uint128::new(50u128),
+        },
+    ]);
+    deps.querier
+        .with_max_swap_deviation(Some(Decimal::from_str("0.1").unwrap()));
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let info = mock_info(String::from(MOCK_REWARDS_DISPATCHER_ADDR).as_str(), &[]);
+    let msg = ExecuteMsg::SwapToRewardDenom {};
+
+    // the usdr swap returns half of the oracle rate and stays on the contract
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(create_swap_msg(
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::new(1000u128),
+            },
+            DEFAULT_REWARD_DENOM.to_string()
+        ))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("skipped_swap", "50usdr"),
+            attr("skipped_swap_deviation", "0.5"),
+        ]
+    );
+}
+
 #[test]
 fn update_global_index() {
     let mut deps = mock_dependencies(&[Coin {
@@ -165,7 +221,9 @@ fn update_global_index() {
 
     instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
 
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
 
     // Failed unauthorized try
     let info = mock_info("addr0000", &[]);
@@ -187,6 +245,16 @@ fn update_global_index() {
             global_index: Decimal::zero(),
             total_balance: Uint128::from(100u128),
             prev_reward_balance: Uint128::zero(),
+            unreleased_rewards: Uint128::zero(),
+            last_release_time: 0,
+            release_end_time: 0,
+            denom_migrations: vec![],
+            compound_balance: Uint128::zero(),
+            compound_index: Decimal::zero(),
+            compound_pending_rewards: Decimal::zero(),
+            compounded_index: Decimal::zero(),
+            compounded_weighted_index: Decimal::zero(),
+            reward_indices: vec![],
         },
     )
     .unwrap();
@@ -202,7 +270,9 @@ fn update_global_index() {
         StateResponse {
             global_index: Decimal::one(),
             total_balance: Uint128::from(100u128),
-            prev_reward_balance: Uint128::from(100u128)
+            prev_reward_balance: Uint128::from(100u128),
+            unreleased_rewards: Uint128::zero(),
+            reward_indices: vec![],
         }
     );
 }
@@ -256,7 +326,9 @@ fn increase_balance() {
     // claimed_rewards = 100, total_balance = 100
     // global_index == 1
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
@@ -335,7 +407,9 @@ fn increase_balance_with_decimals() {
     // claimed_rewards = 100000 , total_balance = 11
     // global_index == 9077.727272727272727272
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
@@ -420,7 +494,9 @@ fn decrease_balance() {
     // claimed_rewards = 100, total_balance = 100
     // global_index == 1
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
@@ -492,10 +568,18 @@ fn claim_rewards() {
     // claimed_rewards = 100, total_balance = 100
     // global_index == 1
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
@@ -513,11 +597,17 @@ fn claim_rewards() {
     // claimed_rewards = 100, total_balance = 100
     // global_index == 1
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
         recipient: Some(String::from("addr0001")),
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
     };
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -576,10 +666,18 @@ fn claim_rewards_with_decimals() {
     // global_index ==
     let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
 
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
@@ -623,7 +721,9 @@ fn claim_rewards_with_decimals() {
         StateResponse {
             global_index: index,
             total_balance: Uint128::new(11u128),
-            prev_reward_balance: Uint128::new(1)
+            prev_reward_balance: Uint128::new(1),
+            unreleased_rewards: Uint128::zero(),
+            reward_indices: vec![],
         }
     );
 }
@@ -802,6 +902,16 @@ fn proper_prev_balance() {
             global_index,
             total_balance: all_balance,
             prev_reward_balance: rewards,
+            unreleased_rewards: Uint128::zero(),
+            last_release_time: 0,
+            release_end_time: 0,
+            denom_migrations: vec![],
+            compound_balance: Uint128::zero(),
+            compound_index: Decimal::zero(),
+            compound_pending_rewards: Decimal::zero(),
+            compounded_index: Decimal::zero(),
+            compounded_weighted_index: Decimal::zero(),
+            reward_indices: vec![],
         },
     )
     .unwrap();
@@ -810,6 +920,15 @@ fn proper_prev_balance() {
         balance: amount1,
         index: Decimal::from_str("0").unwrap(),
         pending_rewards: Decimal::from_str("0").unwrap(),
+        denom_migrations: 0,
+        auto_compound: false,
+        compounded_index: Decimal::zero(),
+        compounded_rewards: Decimal::zero(),
+        compounded_global_index: None,
+        compounded_weighted_index: Decimal::zero(),
+        excluded: false,
+        beneficiary: None,
+        reward_indices: vec![],
     };
     store_holder(
         &mut deps.storage,
@@ -825,6 +944,15 @@ fn proper_prev_balance() {
         balance: amount2,
         index: Decimal::from_str("0").unwrap(),
         pending_rewards: Decimal::from_str("0").unwrap(),
+        denom_migrations: 0,
+        auto_compound: false,
+        compounded_index: Decimal::zero(),
+        compounded_rewards: Decimal::zero(),
+        compounded_global_index: None,
+        compounded_weighted_index: Decimal::zero(),
+        excluded: false,
+        beneficiary: None,
+        reward_indices: vec![],
     };
     store_holder(
         &mut deps.storage,
@@ -840,6 +968,15 @@ fn proper_prev_balance() {
         balance: amount3,
         index: Decimal::from_str("0").unwrap(),
         pending_rewards: Decimal::from_str("0").unwrap(),
+        denom_migrations: 0,
+        auto_compound: false,
+        compounded_index: Decimal::zero(),
+        compounded_rewards: Decimal::zero(),
+        compounded_global_index: None,
+        compounded_weighted_index: Decimal::zero(),
+        excluded: false,
+        beneficiary: None,
+        reward_indices: vec![],
     };
     store_holder(
         &mut deps.storage,
@@ -851,15 +988,33 @@ fn proper_prev_balance() {
     )
     .unwrap();
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
     let info = mock_info("addr0000", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
     let info = mock_info("addr0001", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
     let info = mock_info("addr0002", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -870,7 +1025,9 @@ fn proper_prev_balance() {
         StateResponse {
             global_index,
             total_balance: all_balance,
-            prev_reward_balance: Uint128::new(1)
+            prev_reward_balance: Uint128::new(1),
+            unreleased_rewards: Uint128::zero(),
+            reward_indices: vec![],
         }
     );
 
@@ -931,3 +1088,1688 @@ fn proper_prev_balance() {
         }
     );
 }
+
+#[test]
+fn update_global_index_with_release_period() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // claimed_rewards = 100 are released over 100 seconds
+    let mut env = mock_env();
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: Some(100),
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state_response,
+        StateResponse {
+            global_index: Decimal::zero(),
+            total_balance: Uint128::from(100u128),
+            prev_reward_balance: Uint128::from(100u128),
+            unreleased_rewards: Uint128::from(100u128),
+            reward_indices: vec![],
+        }
+    );
+
+    // the global index grows with the elapsed time
+    env.block.time = env.block.time.plus_seconds(25);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert_eq!(accrued.rewards, Uint128::from(25u128));
+
+    // only the released rewards can be claimed
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("rewards", "25")));
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let res = query(deps.as_ref(), env, QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state_response,
+        StateResponse {
+            global_index: Decimal::one(),
+            total_balance: Uint128::from(100u128),
+            prev_reward_balance: Uint128::from(75u128),
+            unreleased_rewards: Uint128::zero(),
+            reward_indices: vec![],
+        }
+    );
+}
+
+#[test]
+fn migrate_reward_denom() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), dispatcher_info.clone(), msg).unwrap();
+
+    // the holder joins after the rewards in uusd are distributed
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0001"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::MigrateRewardDenom {
+        new_denom: "ukrw".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        dispatcher_info.clone(),
+        ExecuteMsg::MigrateRewardDenom {
+            new_denom: "uusd".to_string(),
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the rewards are already paid in uusd")
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info.clone(), msg).unwrap();
+    let complete_msg = ExecuteMsg::CompleteRewardDenomMigration {
+        new_denom: "ukrw".to_string(),
+        prev_denom_amount: Uint128::from(100u128),
+        new_denom_balance: Uint128::zero(),
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(
+                Coin::new(100u128, "uusd"),
+                "ukrw".to_string()
+            )),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&complete_msg).unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        complete_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    // 100uusd are swapped to 150ukrw
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(150u128, "ukrw")])]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        complete_msg,
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_response.reward_denom, "ukrw");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.global_index, Decimal::one());
+    assert_eq!(state_response.prev_reward_balance, Uint128::from(150u128));
+
+    let accrued_rewards = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AccruedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+        accrued.rewards
+    };
+    assert_eq!(accrued_rewards(&deps, "addr0000"), Uint128::from(150u128));
+    assert_eq!(accrued_rewards(&deps, "addr0001"), Uint128::zero());
+
+    // the next rewards are distributed in ukrw
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(350u128, "ukrw")])]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("claimed_rewards", "200")));
+    assert_eq!(accrued_rewards(&deps, "addr0000"), Uint128::from(250u128));
+    assert_eq!(accrued_rewards(&deps, "addr0001"), Uint128::from(100u128));
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("rewards", "250")));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Holder {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let holder_response: HolderResponse = from_binary(&res).unwrap();
+    assert_eq!(holder_response.index, Decimal::from_ratio(2u128, 1u128));
+    assert_eq!(holder_response.pending_rewards, Decimal::zero());
+}
+
+#[test]
+fn claim_rewards_in_denom() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // claimed_rewards = 100, total_balance = 100
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the tax is deducted from the rewards paid in uusd
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: Some(Uint128::from(100u128)),
+        reward_denoms: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("received amount 99uusd is less than the minimum receive amount 100")
+    );
+
+    // 100uusd are swapped to 4uluna
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: Some(String::from("addr0001")),
+        denom: Some("uluna".to_string()),
+        min_receive: Some(Uint128::from(5u128)),
+        reward_denoms: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("received amount 4uluna is less than the minimum receive amount 5")
+    );
+
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: Some(String::from("addr0001")),
+        denom: Some("uluna".to_string()),
+        min_receive: Some(Uint128::from(4u128)),
+        reward_denoms: None,
+    };
+    deps.querier
+        .with_native_balances(&[("addr0001", &[Coin::new(10u128, "uluna")])]);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_send_msg(
+                String::from("addr0001"),
+                Coin::new(100u128, "uusd"),
+                "uluna".to_string(),
+            )),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::CheckReceivedRewards {
+                    recipient: String::from("addr0001"),
+                    prev_balance: Coin::new(10u128, "uluna"),
+                    min_receive: Uint128::from(4u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("rewards", "100")));
+    assert!(res.attributes.contains(&attr("received", "4uluna")));
+
+    // the swap executed after the simulation returns less
+    let check_msg = ExecuteMsg::CheckReceivedRewards {
+        recipient: String::from("addr0001"),
+        prev_balance: Coin::new(10u128, "uluna"),
+        min_receive: Uint128::from(4u128),
+    };
+    deps.querier
+        .with_native_balances(&[("addr0001", &[Coin::new(13u128, "uluna")])]);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, check_msg.clone());
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), check_msg.clone());
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("received amount 3uluna is less than the minimum receive amount 4")
+    );
+
+    deps.querier
+        .with_native_balances(&[("addr0001", &[Coin::new(14u128, "uluna")])]);
+    let res = execute(deps.as_mut(), mock_env(), info, check_msg).unwrap();
+    assert!(res.attributes.contains(&attr("received", "4uluna")));
+}
+
+#[test]
+fn auto_compound_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(300u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [("addr0000", 100u128), ("addr0001", 200u128)] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let compounded_rewards = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CompoundedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let compounded: CompoundedRewardsResponse = from_binary(&res).unwrap();
+        compounded
+    };
+    assert_eq!(
+        compounded_rewards(&deps, "addr0000"),
+        CompoundedRewardsResponse {
+            auto_compound: true,
+            compounded_rewards: Uint128::zero(),
+        }
+    );
+
+    // claimed_rewards = 300, total_balance = 300, the rewards of addr0000 are compounded
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(
+                Coin::new(100u128, "uusd"),
+                "uluna".to_string()
+            )),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::BondCompoundedRewards {}).unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.prev_reward_balance, Uint128::from(200u128));
+
+    for (address, rewards) in [("addr0000", 0u128), ("addr0001", 200u128)] {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AccruedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+        assert_eq!(accrued.rewards, Uint128::from(rewards));
+    }
+
+    // the swapped Luna is bonded
+    let msg = ExecuteMsg::BondCompoundedRewards {};
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR,
+        &[Coin::new(200u128, "uusd"), Coin::new(4u128, "uluna")],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&basset::hub::ExecuteMsg::Bond {}).unwrap(),
+            funds: vec![Coin::new(4u128, "uluna")],
+        })]
+    );
+
+    // the minted bLuna is credited to addr0000
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from(MOCK_CONTRACT_ADDR),
+        amount: Uint128::from(4u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
+    assert_eq!(
+        compounded_rewards(&deps, "addr0000").compounded_rewards,
+        Uint128::from(4u128)
+    );
+    assert_eq!(
+        compounded_rewards(&deps, "addr0001").compounded_rewards,
+        Uint128::zero()
+    );
+
+    // addr0001 opts in after the bLuna is minted
+    let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap();
+
+    // claimed_rewards = 304, total_balance = 304, the rewards of the bLuna held by
+    // the contract are accrued to addr0000 it was compounded for
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(504u128, "uusd")])]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(create_swap_msg(
+            Coin::new(300u128, "uusd"),
+            "uluna".to_string()
+        ))
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.prev_reward_balance, Uint128::from(204u128));
+    for (address, rewards) in [("addr0000", 4u128), ("addr0001", 200u128)] {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AccruedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+        assert_eq!(accrued.rewards, Uint128::from(rewards));
+    }
+
+    let msg = ExecuteMsg::WithdrawCompoundedRewards { recipient: None };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("No rewards have been compounded yet")
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: MOCK_TOKEN_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&TokenExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: Uint128::from(4u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    // addr0000 stops compounding after opting out
+    let msg = ExecuteMsg::SetAutoCompound { enabled: false };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        compounded_rewards(&deps, "addr0000"),
+        CompoundedRewardsResponse {
+            auto_compound: false,
+            compounded_rewards: Uint128::zero(),
+        }
+    );
+
+    // the rewards of the compounded bLuna stay claimable after it is withdrawn
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("rewards", "4")));
+}
+
+#[test]
+fn auto_compound_rewards_max_swap_deviation() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(300u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(300u128),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the Luna market is thin, the pool stays on the contract till the next update
+    deps.querier.with_swap_spread(Decimal::percent(50));
+    deps.querier
+        .with_max_swap_deviation(Some(Decimal::from_str("0.1").unwrap()));
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        dispatcher_info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert!(res.attributes.contains(&attr("skipped_swap", "300uusd")));
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.prev_reward_balance, Uint128::from(300u128));
+
+    deps.querier.with_swap_spread(Decimal::zero());
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(create_swap_msg(
+            Coin::new(300u128, "uusd"),
+            "uluna".to_string()
+        ))
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.prev_reward_balance, Uint128::zero());
+}
+
+#[test]
+fn claim_rewards_by_operator_through_routes() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1000u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // claimed_rewards = 1000, total_balance = 100
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let holder_info = mock_info("addr0000", &[]);
+    let route = |recipient: &str, share: u64, msg: Option<Binary>| RewardRoute {
+        recipient: recipient.to_string(),
+        share: Decimal::percent(share),
+        msg,
+    };
+    let msg = ExecuteMsg::SetRewardRoutes {
+        routes: vec![route("treasury", 0, None)],
+    };
+    let res = execute(deps.as_mut(), mock_env(), holder_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("reward route share is zero")
+    );
+
+    let msg = ExecuteMsg::SetRewardRoutes {
+        routes: vec![route("treasury", 70, None), route("payout", 40, None)],
+    };
+    let res = execute(deps.as_mut(), mock_env(), holder_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("reward route shares can't sum up to more than one")
+    );
+
+    let hook_msg = to_binary("distribute").unwrap();
+    let routes = vec![
+        route("treasury", 20, None),
+        route("payout", 30, Some(hook_msg.clone())),
+    ];
+    let msg = ExecuteMsg::SetRewardRoutes {
+        routes: routes.clone(),
+    };
+    execute(deps.as_mut(), mock_env(), holder_info.clone(), msg).unwrap();
+
+    let claim_msg = ExecuteMsg::ClaimRewards {
+        holder: Some(String::from("addr0000")),
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let operator_info = mock_info("operator0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        operator_info.clone(),
+        claim_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    let msg = ExecuteMsg::SetClaimOperator {
+        operator: String::from("operator0000"),
+        enabled: true,
+    };
+    execute(deps.as_mut(), mock_env(), holder_info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ClaimSettings {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let settings: ClaimSettingsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        settings,
+        ClaimSettingsResponse {
+            operators: vec![String::from("operator0000")],
+            routes,
+        }
+    );
+
+    // the operator can't redirect the rewards of the holder
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        operator_info.clone(),
+        ExecuteMsg::ClaimRewards {
+            holder: Some(String::from("addr0000")),
+            recipient: Some(String::from("operator0000")),
+            denom: None,
+            min_receive: None,
+            reward_denoms: None,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the operators can't choose the recipient of the rewards")
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        operator_info.clone(),
+        ExecuteMsg::ClaimRewards {
+            holder: Some(String::from("addr0000")),
+            recipient: None,
+            denom: Some(String::from("uluna")),
+            min_receive: Some(Uint128::zero()),
+            reward_denoms: None,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the operators can't choose the denom of the rewards")
+    );
+
+    // the rest of the rewards is sent to the holder
+    let res = execute(deps.as_mut(), mock_env(), operator_info, claim_msg).unwrap();
+    let querier = deps.as_ref().querier;
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: vec![deduct_tax(&querier, Coin::new(200u128, "uusd")).unwrap()],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("payout"),
+                msg: hook_msg,
+                funds: vec![deduct_tax(&querier, Coin::new(300u128, "uusd")).unwrap()],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![deduct_tax(&querier, Coin::new(500u128, "uusd")).unwrap()],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("rewards", "1000")));
+}
+
+#[test]
+fn excluded_and_delegated_holders() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [
+        ("addr0000", 100u128),
+        ("pair", 100u128),
+        ("custody", 200u128),
+    ] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    let exclude_msg = ExecuteMsg::UpdateHolderStatus {
+        address: String::from("pair"),
+        status: HolderStatus::Excluded {},
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        exclude_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    let owner_info = mock_info("owner1", &[]);
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), exclude_msg).unwrap();
+    let msg = ExecuteMsg::UpdateHolderStatus {
+        address: String::from("custody"),
+        status: HolderStatus::Delegated {
+            beneficiary: String::from("market"),
+        },
+    };
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+    let holder_status = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HolderStatus {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let status: HolderStatusResponse = from_binary(&res).unwrap();
+        status.status
+    };
+    assert_eq!(holder_status(&deps, "pair"), HolderStatus::Excluded {});
+    assert_eq!(
+        holder_status(&deps, "custody"),
+        HolderStatus::Delegated {
+            beneficiary: String::from("market")
+        }
+    );
+
+    // the balance of the pair doesn't change the total balance
+    for msg in [
+        ExecuteMsg::IncreaseBalance {
+            address: String::from("pair"),
+            amount: Uint128::from(50u128),
+        },
+        ExecuteMsg::DecreaseBalance {
+            address: String::from("pair"),
+            amount: Uint128::from(100u128),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.total_balance, Uint128::from(300u128));
+
+    // claimed_rewards = 300, total_balance = 300
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(300u128, "uusd")])]);
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    for (address, rewards) in [("addr0000", 100u128), ("pair", 0u128), ("custody", 200u128)] {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AccruedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+        assert_eq!(accrued.rewards, Uint128::from(rewards));
+    }
+
+    // the rewards of the custody are sent to the beneficiary
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: Some(String::from("custody")),
+        recipient: Some(String::from("addr0002")),
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("market", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: String::from("market"),
+            amount: vec![deduct_tax(&deps.as_ref().querier, Coin::new(200u128, "uusd")).unwrap()],
+        })]
+    );
+
+    let msg = ExecuteMsg::UpdateHolderStatus {
+        address: String::from("pair"),
+        status: HolderStatus::Regular {},
+    };
+    execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.total_balance, Uint128::from(350u128));
+}
+
+#[test]
+fn distribute_rewards_in_other_denoms() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for address in ["addr0000", "addr0001"] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(100u128),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    let add_msg = ExecuteMsg::AddRewardDenom {
+        denom: String::from("ukrw"),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        add_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    let owner_info = mock_info("owner1", &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        add_msg.clone(),
+    )
+    .unwrap();
+    for denom in ["ukrw", DEFAULT_REWARD_DENOM] {
+        let msg = ExecuteMsg::AddRewardDenom {
+            denom: denom.to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner_info.clone(), msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err(format!("{} is already a reward denom", denom))
+        );
+    }
+
+    // the rewards in ukrw are not swapped
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR,
+        &[
+            Coin::new(200u128, "uusd"),
+            Coin::new(400u128, "ukrw"),
+            Coin::new(50u128, "uinr"),
+        ],
+    )]);
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        dispatcher_info.clone(),
+        ExecuteMsg::SwapToRewardDenom {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(create_swap_msg(
+            Coin::new(50u128, "uinr"),
+            DEFAULT_REWARD_DENOM.to_string()
+        ))]
+    );
+
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR,
+        &[Coin::new(200u128, "uusd"), Coin::new(400u128, "ukrw")],
+    )]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("claimed_rewards", "400ukrw")));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state_response.reward_indices,
+        vec![RewardIndexResponse {
+            denom: String::from("ukrw"),
+            global_index: Decimal::from_ratio(2u128, 1u128),
+            prev_reward_balance: Uint128::from(400u128),
+        }]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        accrued,
+        AccruedRewardsResponse {
+            rewards: Uint128::from(100u128),
+            all_rewards: vec![Coin::new(100u128, "uusd"), Coin::new(200u128, "ukrw")],
+        }
+    );
+
+    // only the chosen denoms are claimed
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: Some(vec![String::from("uinr")]),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("uinr is not a reward denom")
+    );
+
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: Some(vec![String::from("ukrw")]),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![deduct_tax(&deps.as_ref().querier, Coin::new(200u128, "ukrw")).unwrap()],
+        })]
+    );
+    assert!(res.attributes.contains(&attr("other_rewards", "200ukrw")));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert_eq!(accrued.all_rewards, vec![Coin::new(100u128, "uusd")]);
+
+    // every denom is claimed by default
+    let msg = ExecuteMsg::ClaimRewards {
+        holder: None,
+        recipient: None,
+        denom: None,
+        min_receive: None,
+        reward_denoms: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![
+                    deduct_tax(&deps.as_ref().querier, Coin::new(100u128, "uusd")).unwrap()
+                ],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![
+                    deduct_tax(&deps.as_ref().querier, Coin::new(200u128, "ukrw")).unwrap()
+                ],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn distribute_other_denoms_to_compounded_rewards() {
+    let mut deps = mock_dependencies(&[Coin::new(300u128, "uusd")]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [("addr0000", 100u128), ("addr0001", 200u128)] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+    let msg = ExecuteMsg::AddRewardDenom {
+        denom: String::from("ukrw"),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner1", &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the 100uusd of addr0000 are compounded into 4 bLuna held by the contract
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        dispatcher_info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+    let msg_increase = ExecuteMsg::IncreaseBalance {
+        address: String::from(MOCK_CONTRACT_ADDR),
+        amount: Uint128::from(4u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info, msg_increase).unwrap();
+
+    // only the rewards of addr0001 are pending, the ones of addr0000 are compounded
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: StatsResponse = from_binary(&res).unwrap();
+    assert_eq!(stats.total_pending_rewards, Uint128::from(200u128));
+
+    // claimed_rewards = 608ukrw, total_balance = 304, the 8ukrw of the bLuna held by
+    // the contract are accrued to addr0000 it was compounded for
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR,
+        &[Coin::new(200u128, "uusd"), Coin::new(608u128, "ukrw")],
+    )]);
+    let res = execute(deps.as_mut(), mock_env(), dispatcher_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("claimed_rewards", "608ukrw")));
+
+    let accrued_ukrw = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &str| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AccruedRewards {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+        let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+        accrued
+            .all_rewards
+            .into_iter()
+            .find(|coin| coin.denom == "ukrw")
+            .map_or(Uint128::zero(), |coin| coin.amount)
+    };
+    assert_eq!(accrued_ukrw(&deps, MOCK_CONTRACT_ADDR), Uint128::zero());
+
+    // every unit is claimed by the holders
+    for (address, rewards) in [("addr0000", 208u128), ("addr0001", 400u128)] {
+        assert_eq!(accrued_ukrw(&deps, address), Uint128::from(rewards));
+        let msg = ExecuteMsg::ClaimRewards {
+            holder: None,
+            recipient: None,
+            denom: None,
+            min_receive: None,
+            reward_denoms: Some(vec![String::from("ukrw")]),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(address, &[]), msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("other_rewards", format!("{}ukrw", rewards))));
+        assert_eq!(accrued_ukrw(&deps, address), Uint128::zero());
+    }
+}
+
+#[test]
+fn rewards_between_checkpoints() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let mut env = mock_env();
+    let start = env.block.time.seconds();
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for address in ["addr0000", "addr0001"] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(100u128),
+        };
+        execute(deps.as_mut(), env.clone(), token_info.clone(), msg).unwrap();
+    }
+
+    // claimed_rewards = 200, total_balance = 200
+    env.block.time = env.block.time.plus_seconds(1000);
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(200u128, "uusd")])]);
+    let dispatcher_info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        dispatcher_info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(1000);
+    let decrease_msg = ExecuteMsg::DecreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(50u128),
+    };
+    execute(deps.as_mut(), env.clone(), token_info, decrease_msg).unwrap();
+
+    // claimed_rewards = 150, total_balance = 150
+    env.block.time = env.block.time.plus_seconds(1000);
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(350u128, "uusd")])]);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        dispatcher_info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::IndexCheckpoints {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let checkpoints: IndexCheckpointsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        checkpoints.checkpoints,
+        vec![
+            IndexCheckpointResponse {
+                time: start + 1000,
+                global_index: Decimal::one(),
+                total_balance: Uint128::from(200u128),
+                rewards: Uint128::from(200u128),
+            },
+            IndexCheckpointResponse {
+                time: start + 3000,
+                global_index: Decimal::from_ratio(2u128, 1u128),
+                total_balance: Uint128::from(150u128),
+                rewards: Uint128::from(150u128),
+            },
+        ]
+    );
+
+    for (address, from, to, rewards) in [
+        ("addr0000", start, start + 5000, 150u128),
+        ("addr0000", start, start + 1500, 100u128),
+        ("addr0000", start + 1500, start + 5000, 50u128),
+        ("addr0000", start + 3500, start + 5000, 0u128),
+        ("addr0001", start, start + 5000, 200u128),
+        ("addr0002", start, start + 5000, 0u128),
+    ] {
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RewardsBetween {
+                address: String::from(address),
+                from,
+                to,
+            },
+        )
+        .unwrap();
+        let response: RewardsBetweenResponse = from_binary(&res).unwrap();
+        assert_eq!(response.rewards, Uint128::from(rewards));
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::RewardsBetween {
+            address: String::from("addr0000"),
+            from: start + 1,
+            to: start,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the start of the range can't be later than its end")
+    );
+
+    // claimed_rewards = 150, total_balance = 150, the checkpoints older than the retention
+    // period are pruned except the latest of them
+    env.block.time = env
+        .block
+        .time
+        .plus_seconds(CHECKPOINTS_RETENTION_PERIOD + 2000);
+    let retained_from = start + 5000;
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(500u128, "uusd")])]);
+    execute(deps.as_mut(), env.clone(), dispatcher_info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::IndexCheckpoints {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let checkpoints: IndexCheckpointsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        checkpoints
+            .checkpoints
+            .iter()
+            .map(|checkpoint| checkpoint.time)
+            .collect::<Vec<u64>>(),
+        vec![start + 3000, env.block.time.seconds()]
+    );
+
+    let rewards_between = |from: u64| {
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RewardsBetween {
+                address: String::from("addr0000"),
+                from,
+                to: env.block.time.seconds(),
+            },
+        )
+    };
+    let response: RewardsBetweenResponse =
+        from_binary(&rewards_between(retained_from).unwrap()).unwrap();
+    assert_eq!(response.rewards, Uint128::from(50u128));
+    assert_eq!(
+        rewards_between(retained_from - 1).unwrap_err(),
+        StdError::generic_err(format!(
+            "the checkpoints made before {} are pruned",
+            retained_from
+        ))
+    );
+}
+
+#[test]
+fn pending_rewards() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [("addr0000", 100u128), ("addr0001", 300u128)] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    // claimed_rewards = 400, total_balance = 400
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(400u128, "uusd")])]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+
+    // 200uusd are on the contract and 600uusd are going to be dispatched
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR, &[Coin::new(600u128, "uusd")])]);
+    deps.querier
+        .with_pending_dispatch_rewards(Coin::new(600u128, "uusd"));
+
+    for (address, rewards, pending_rewards) in [
+        ("addr0000", 300u128, 200u128),
+        ("addr0001", 900u128, 600u128),
+        ("addr0002", 0u128, 0u128),
+    ] {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PendingRewards {
+                address: String::from(address),
+            },
+        )
+        .unwrap();
+        let response: PendingRewardsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            response,
+            PendingRewardsResponse {
+                rewards: Uint128::from(rewards),
+                pending_rewards: Uint128::from(pending_rewards),
+            }
+        );
+    }
+}
+
+#[test]
+fn claim_rewards_for_holders() {
+    let mut deps = mock_dependencies(&[Coin::new(400u128, "uusd")]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [("addr0000", 100u128), ("addr0001", 300u128)] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    // claimed_rewards = 400, total_balance = 400
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SetRewardRoutes {
+        routes: vec![RewardRoute {
+            recipient: String::from("treasury"),
+            share: Decimal::percent(50),
+            msg: None,
+        }],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetClaimOperator {
+        operator: String::from("operator0000"),
+        enabled: true,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    let claim_msg = ExecuteMsg::ClaimRewardsFor {
+        holders: vec![
+            String::from("addr0000"),
+            String::from("addr0001"),
+            String::from("addr0000"),
+        ],
+    };
+    let operator_info = mock_info("operator0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        operator_info.clone(),
+        claim_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+
+    execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        operator_info.clone(),
+        claim_msg.clone(),
+    )
+    .unwrap();
+    let querier = deps.as_ref().querier;
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: vec![deduct_tax(&querier, Coin::new(50u128, "uusd")).unwrap()],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![deduct_tax(&querier, Coin::new(50u128, "uusd")).unwrap()],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![deduct_tax(&querier, Coin::new(300u128, "uusd")).unwrap()],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("rewards", "400")));
+    assert!(res.attributes.contains(&attr("claimed_holders", "2")));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.prev_reward_balance, Uint128::zero());
+
+    // nothing is left to claim
+    let res = execute(deps.as_mut(), mock_env(), operator_info, claim_msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("No rewards have accrued yet")
+    );
+}
+
+#[test]
+fn holder_stats_and_holders_by_balance() {
+    let mut deps = mock_dependencies(&[Coin::new(400u128, "uusd")]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for (address, amount) in [
+        ("addr0000", 100u128),
+        ("addr0001", 300u128),
+        ("addr0002", 200u128),
+    ] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+    let msg = ExecuteMsg::DecreaseBalance {
+        address: String::from("addr0002"),
+        amount: Uint128::from(200u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+
+    // claimed_rewards = 400, total_balance = 400
+    let info = mock_info(MOCK_REWARDS_DISPATCHER_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {
+        release_period: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the rewards of addr0000 are settled to its pending rewards
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
+
+    let expected_stats = StatsResponse {
+        holder_count: 2,
+        total_balance: Uint128::from(500u128),
+        total_pending_rewards: Uint128::from(400u128),
+        holders_indexed: true,
+    };
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: StatsResponse = from_binary(&res).unwrap();
+    assert_eq!(stats, expected_stats);
+
+    let holder = |address: &str, balance: u128, index: u64, pending_rewards: u64| HolderResponse {
+        address: String::from(address),
+        balance: Uint128::from(balance),
+        index: Decimal::from_ratio(index, 1u64),
+        pending_rewards: Decimal::from_ratio(pending_rewards, 1u64),
+    };
+    let query_holders_by_balance = |start_after: Option<&str>, descending: Option<bool>| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HoldersByBalance {
+                start_after: start_after.map(String::from),
+                limit: None,
+                descending,
+            },
+        )
+        .unwrap();
+        let response: HoldersResponse = from_binary(&res).unwrap();
+        response.holders
+    };
+    assert_eq!(
+        query_holders_by_balance(None, None),
+        vec![
+            holder("addr0000", 200, 1, 100),
+            holder("addr0001", 300, 0, 0)
+        ]
+    );
+    assert_eq!(
+        query_holders_by_balance(None, Some(true)),
+        vec![
+            holder("addr0001", 300, 0, 0),
+            holder("addr0000", 200, 1, 100)
+        ]
+    );
+    assert_eq!(
+        query_holders_by_balance(Some("addr0001"), Some(true)),
+        vec![holder("addr0000", 200, 1, 100)]
+    );
+    assert_eq!(
+        query_holders_by_balance(Some("addr0000"), None),
+        vec![holder("addr0001", 300, 0, 0)]
+    );
+
+    // the stats of the holders stored before them are built by the migration
+    HOLDER_STATS.remove(&mut deps.storage);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: StatsResponse = from_binary(&res).unwrap();
+    assert_eq!(stats, expected_stats);
+}
+
+#[test]
+fn index_holders_in_batches() {
+    let mut deps = mock_dependencies(&[]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    for address in ["addr0000", "addr0001", "addr0002"] {
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(100u128),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+
+    // the holders are stored before the holder stats were kept
+    HOLDER_STATS.remove(&mut deps.storage);
+    start_holders_indexing(&mut deps.storage).unwrap();
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::IndexHolders { limit: Some(1) };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "index_holders"),
+            attr("indexed_holders", "1"),
+        ]
+    );
+
+    let query_stats = |deps: Deps| -> StatsResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::Stats {}).unwrap()).unwrap()
+    };
+    let stats = query_stats(deps.as_ref());
+    assert_eq!(stats.holder_count, 1);
+    assert!(!stats.holders_indexed);
+
+    // the holders not indexed yet don't affect the stats, so the count doesn't underflow
+    for address in ["addr0000", "addr0001", "addr0002"] {
+        let msg = ExecuteMsg::DecreaseBalance {
+            address: String::from(address),
+            amount: Uint128::from(100u128),
+        };
+        execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+    }
+    assert_eq!(query_stats(deps.as_ref()).holder_count, 0);
+
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0001"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
+
+    // the indexing is done once a batch is not full
+    let msg = ExecuteMsg::IndexHolders { limit: Some(2) };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.attributes[1], attr("indexed_holders", "0"));
+
+    let expected_stats = StatsResponse {
+        holder_count: 1,
+        total_balance: Uint128::from(100u128),
+        total_pending_rewards: Uint128::zero(),
+        holders_indexed: true,
+    };
+    assert_eq!(query_stats(deps.as_ref()), expected_stats);
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the holders are already indexed")
+    );
+}
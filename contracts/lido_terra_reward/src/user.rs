@@ -12,86 +12,428 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::querier::query_token_contract_address;
+use crate::global::{accrue_compound_rewards, apply_denom_migrations, release_rewards};
+use crate::querier::{
+    query_hub_owner, query_pending_dispatch_rewards, query_rewards_dispatcher_contract_address,
+    query_token_contract_address, TokenExecuteMsg, CW20_DENOM_PREFIX,
+};
 use crate::state::{
-    read_config, read_holder, read_holders, read_state, store_holder, store_state, Config, Holder,
-    State,
+    read_claim_settings, read_config, read_holder, read_holder_checkpoint_at,
+    read_holder_checkpoints_between, read_holders, read_holders_by_balance,
+    read_index_checkpoint_at, read_state, store_claim_settings, store_holder,
+    store_holder_checkpoint, store_state, Config, Holder, HolderCheckpoint, HolderRewardIndex,
+    RewardRouteInfo, State, CHECKPOINTS_RETENTION_PERIOD,
+};
+use basset::reward::{
+    AccruedRewardsResponse, ClaimSettingsResponse, CompoundedRewardsResponse, ExecuteMsg,
+    HolderResponse, HolderStatus, HolderStatusResponse, HoldersResponse, PendingRewardsResponse,
+    RewardRoute, RewardsBetweenResponse,
 };
-use basset::reward::{AccruedRewardsResponse, HolderResponse, HoldersResponse};
 
 use cosmwasm_std::{
-    attr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Uint128,
+    attr, to_binary, Addr, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 
 use crate::math::{
     decimal_multiplication_in_256, decimal_subtraction_in_256, decimal_summation_in_256,
 };
 use basset::deduct_tax;
-use terra_cosmwasm::TerraMsgWrapper;
+use terra_cosmwasm::{create_swap_send_msg, SwapResponse, TerraMsgWrapper, TerraQuerier};
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_claim_rewards(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
+    holder: Option<String>,
     recipient: Option<String>,
+    denom: Option<String>,
+    min_receive: Option<Uint128>,
+    reward_denoms: Option<Vec<String>>,
 ) -> StdResult<Response<TerraMsgWrapper>> {
-    let holder_addr = info.sender;
-    let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
-    let recipient = match recipient {
+    let holder_addr = match holder {
         Some(value) => deps.api.addr_validate(value.as_str())?,
-        None => holder_addr.clone(),
+        None => info.sender.clone(),
     };
 
-    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
     let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
     let config: Config = read_config(deps.storage)?;
 
-    let reward_with_decimals =
-        calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
+    let claim = claim_holder_rewards(
+        &deps,
+        &mut state,
+        &config,
+        &info.sender,
+        &holder_addr,
+        recipient,
+        &reward_denoms,
+    )?;
+    if claim.rewards.is_zero() && claim.other_rewards.is_empty() {
+        return Err(StdError::generic_err("No rewards have accrued yet"));
+    }
+    // the rewards claimed by an operator are paid as they are, the recipient is either
+    // the holder or the beneficiary the rewards of the holder are delegated to
+    if (denom.is_some() || min_receive.is_some())
+        && info.sender != holder_addr
+        && info.sender != claim.recipient
+    {
+        return Err(StdError::generic_err(
+            "the operators can't choose the denom of the rewards",
+        ));
+    }
 
-    let all_reward_with_decimals =
-        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
-    let rewards = all_reward_with_decimals * Uint128::new(1);
-    let decimals = all_reward_with_decimals - Decimal::from_ratio(rewards, Uint128::new(1));
+    let reward_coin = Coin {
+        denom: config.reward_denom.clone(),
+        amount: claim.rest,
+    };
+    let (msgs, received): (Vec<CosmosMsg<TerraMsgWrapper>>, Coin) = match denom {
+        _ if reward_coin.amount.is_zero() => (vec![], reward_coin),
+        // the rewards are swapped and sent to the recipient by the market module at once
+        Some(denom) if denom != config.reward_denom => {
+            let querier = TerraQuerier::new(&deps.querier);
+            let swap: SwapResponse = querier.query_swap(reward_coin.clone(), denom.as_str())?;
+            let mut msgs = vec![create_swap_send_msg(
+                claim.recipient.to_string(),
+                reward_coin,
+                denom.clone(),
+            )];
+            // the simulation may differ from the swap executed after it,
+            // so the amount received is checked once the swap is done
+            if let Some(min_receive) = min_receive {
+                let prev_balance = deps
+                    .querier
+                    .query_balance(claim.recipient.clone(), denom.as_str())?;
+                msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: env.contract.address.to_string(),
+                    msg: to_binary(&ExecuteMsg::CheckReceivedRewards {
+                        recipient: claim.recipient.to_string(),
+                        prev_balance,
+                        min_receive,
+                    })?,
+                    funds: vec![],
+                }));
+            }
+            (msgs, swap.receive)
+        }
+        _ => {
+            let coin = deduct_tax(&deps.querier, reward_coin)?;
+            (
+                vec![CosmosMsg::Bank(BankMsg::Send {
+                    to_address: claim.recipient.to_string(),
+                    amount: vec![coin.clone()],
+                })],
+                coin,
+            )
+        }
+    };
 
-    if rewards.is_zero() {
-        return Err(StdError::generic_err("No rewards have accrued yet"));
+    if let Some(min_receive) = min_receive {
+        if received.amount < min_receive {
+            return Err(StdError::generic_err(format!(
+                "received amount {} is less than the minimum receive amount {}",
+                received, min_receive
+            )));
+        }
     }
 
-    let new_balance = (state.prev_reward_balance.checked_sub(rewards))?;
+    let new_balance = (state.prev_reward_balance.checked_sub(claim.rewards))?;
     state.prev_reward_balance = new_balance;
     store_state(deps.storage, &state)?;
+    store_holder(deps.storage, &claim.holder_address, &claim.holder)?;
 
-    holder.pending_rewards = decimals;
-    holder.index = state.global_index;
-    store_holder(deps.storage, &holder_addr_raw, &holder)?;
+    let mut attributes = vec![
+        attr("action", "claim_reward"),
+        attr("holder_address", holder_addr),
+        attr("rewards", claim.rewards),
+        attr("received", received.to_string()),
+    ];
+    if !claim.other_rewards.is_empty() {
+        let other_rewards: Vec<String> =
+            claim.other_rewards.iter().map(|c| c.to_string()).collect();
+        attributes.push(attr("other_rewards", other_rewards.join(",")));
+    }
+    let res = Response::new()
+        .add_attributes(attributes)
+        .add_messages(claim.route_messages)
+        .add_messages(msgs)
+        .add_messages(claim.other_messages);
 
-    let bank_msg: CosmosMsg<TerraMsgWrapper> = CosmosMsg::Bank(BankMsg::Send {
-        to_address: recipient.to_string(),
-        amount: vec![deduct_tax(
-            &deps.querier,
-            Coin {
-                denom: config.reward_denom,
-                amount: rewards,
-            },
-        )?],
-    });
+    Ok(res)
+}
+
+pub fn execute_check_received_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    prev_balance: Coin,
+    min_receive: Uint128,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let balance = deps
+        .querier
+        .query_balance(recipient, prev_balance.denom.as_str())?;
+    let received = Coin {
+        amount: balance.amount.saturating_sub(prev_balance.amount),
+        denom: balance.denom,
+    };
+    if received.amount < min_receive {
+        return Err(StdError::generic_err(format!(
+            "received amount {} is less than the minimum receive amount {}",
+            received, min_receive
+        )));
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "check_received_rewards"),
+        attr("received", received.to_string()),
+    ]))
+}
+
+/// Claims the rewards of each of the holders to them or to their routes,
+/// the holders with no rewards are skipped
+pub fn execute_claim_rewards_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    holders: Vec<String>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    let config: Config = read_config(deps.storage)?;
+
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut total_rewards = Uint128::zero();
+    let mut claimed_holders: Vec<(CanonicalAddr, Holder)> = vec![];
+    for holder in holders {
+        let holder_addr = deps.api.addr_validate(holder.as_str())?;
+        let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
+        // the holders are stored after all of them are claimed, so a repeated holder is skipped
+        if claimed_holders
+            .iter()
+            .any(|(addr, _)| *addr == holder_addr_raw)
+        {
+            continue;
+        }
+        let claim = claim_holder_rewards(
+            &deps,
+            &mut state,
+            &config,
+            &info.sender,
+            &holder_addr,
+            None,
+            &None,
+        )?;
+        if claim.rewards.is_zero() && claim.other_rewards.is_empty() {
+            continue;
+        }
+
+        messages.extend(claim.route_messages);
+        if !claim.rest.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: claim.recipient.to_string(),
+                amount: vec![deduct_tax(
+                    &deps.querier,
+                    Coin {
+                        denom: config.reward_denom.clone(),
+                        amount: claim.rest,
+                    },
+                )?],
+            }));
+        }
+        messages.extend(claim.other_messages);
+        total_rewards += claim.rewards;
+        claimed_holders.push((claim.holder_address, claim.holder));
+    }
+
+    if claimed_holders.is_empty() {
+        return Err(StdError::generic_err("No rewards have accrued yet"));
+    }
+
+    // the balance is updated once for all of the holders
+    state.prev_reward_balance = state.prev_reward_balance.checked_sub(total_rewards)?;
+    store_state(deps.storage, &state)?;
+    for (holder_addr, holder) in claimed_holders.iter() {
+        store_holder(deps.storage, holder_addr, holder)?;
+    }
 
     let res = Response::new()
         .add_attributes(vec![
-            attr("action", "claim_reward"),
-            attr("holder_address", holder_addr),
-            attr("rewards", rewards),
+            attr("action", "claim_rewards_for"),
+            attr("claimed_holders", claimed_holders.len().to_string()),
+            attr("rewards", total_rewards),
         ])
-        .add_message(bank_msg);
+        .add_messages(messages);
 
     Ok(res)
 }
 
+/// The rewards of a holder claimed by the sender
+struct HolderClaim {
+    holder_address: CanonicalAddr,
+    // the holder settled by the claim, it is stored by the caller
+    holder: Holder,
+    recipient: Addr,
+    // the rewards in the reward denom, the routes take their shares out of them
+    rewards: Uint128,
+    // the rewards in the reward denom left to the recipient after the routes
+    rest: Uint128,
+    route_messages: Vec<CosmosMsg<TerraMsgWrapper>>,
+    other_rewards: Vec<Coin>,
+    other_messages: Vec<CosmosMsg<TerraMsgWrapper>>,
+}
+
+/// Settles the rewards of the holder and returns the messages of the routes and of the rewards
+/// in the other denoms. The balances of the other denoms are updated in the state, the balance
+/// of the reward denom is left to the caller.
+fn claim_holder_rewards(
+    deps: &DepsMut,
+    state: &mut State,
+    config: &Config,
+    sender: &Addr,
+    holder_addr: &Addr,
+    recipient: Option<String>,
+    reward_denoms: &Option<Vec<String>>,
+) -> StdResult<HolderClaim> {
+    let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
+    let mut claim_settings = read_claim_settings(deps.storage, &holder_addr_raw)?;
+    if sender != holder_addr
+        && !claim_settings.operators.contains(&sender_raw)
+        && holder.beneficiary.as_ref() != Some(&sender_raw)
+    {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+    let recipient = match (&holder.beneficiary, recipient) {
+        // the rewards of the delegated holder are sent to the beneficiary only
+        (Some(beneficiary), _) => {
+            claim_settings.routes.clear();
+            deps.api.addr_humanize(beneficiary)?
+        }
+        (None, Some(_)) if sender != holder_addr => {
+            return Err(StdError::generic_err(
+                "the operators can't choose the recipient of the rewards",
+            ));
+        }
+        (None, Some(value)) => deps.api.addr_validate(value.as_str())?,
+        (None, None) => holder_addr.clone(),
+    };
+
+    apply_denom_migrations(state, &mut holder);
+    accrue_holder_reward_indices(state, &mut holder, false);
+
+    if let Some(reward_denoms) = reward_denoms {
+        for reward_denom in reward_denoms {
+            if *reward_denom != config.reward_denom
+                && !state
+                    .reward_indices
+                    .iter()
+                    .any(|i| i.denom == *reward_denom)
+            {
+                return Err(StdError::generic_err(format!(
+                    "{} is not a reward denom",
+                    reward_denom
+                )));
+            }
+        }
+    }
+    let is_claimed = |reward_denom: &String| match reward_denoms {
+        Some(denoms) => denoms.contains(reward_denom),
+        None => true,
+    };
+
+    let reward_with_decimals = calculate_holder_rewards(state, &holder);
+    credit_compounded_rewards(state, &mut holder);
+
+    let all_reward_with_decimals =
+        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
+    let rewards = if is_claimed(&config.reward_denom) {
+        all_reward_with_decimals * Uint128::new(1)
+    } else {
+        Uint128::zero()
+    };
+    let decimals = all_reward_with_decimals - Decimal::from_ratio(rewards, Uint128::new(1));
+
+    // the rewards in the other denoms are sent to the recipient as they are
+    let mut other_messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut other_rewards: Vec<Coin> = vec![];
+    for holder_index in holder.reward_indices.iter_mut() {
+        let amount = holder_index.pending_rewards * Uint128::new(1);
+        if amount.is_zero() || !is_claimed(&holder_index.denom) {
+            continue;
+        }
+        holder_index.pending_rewards =
+            holder_index.pending_rewards - Decimal::from_ratio(amount, Uint128::new(1));
+        if let Some(reward_index) = state
+            .reward_indices
+            .iter_mut()
+            .find(|i| i.denom == holder_index.denom)
+        {
+            reward_index.prev_reward_balance =
+                reward_index.prev_reward_balance.checked_sub(amount)?;
+        }
+        let coin = Coin {
+            denom: holder_index.denom.clone(),
+            amount,
+        };
+        other_messages.push(reward_coin_msg(deps, recipient.to_string(), coin.clone())?);
+        other_rewards.push(coin);
+    }
+
+    // the routes take their shares before the rest is sent to the recipient
+    let mut route_messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut routed_rewards = Uint128::zero();
+    for route in claim_settings.routes {
+        let amount = rewards * route.share;
+        if amount.is_zero() {
+            continue;
+        }
+        routed_rewards += amount;
+        let coin = deduct_tax(
+            &deps.querier,
+            Coin {
+                denom: config.reward_denom.clone(),
+                amount,
+            },
+        )?;
+        let route_recipient = deps.api.addr_humanize(&route.recipient)?.to_string();
+        route_messages.push(match route.msg {
+            Some(msg) => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: route_recipient,
+                msg,
+                funds: vec![coin],
+            }),
+            None => CosmosMsg::Bank(BankMsg::Send {
+                to_address: route_recipient,
+                amount: vec![coin],
+            }),
+        });
+    }
+
+    holder.pending_rewards = decimals;
+    holder.index = state.global_index;
+
+    Ok(HolderClaim {
+        holder_address: holder_addr_raw,
+        holder,
+        recipient,
+        rewards,
+        rest: rewards.checked_sub(routed_rewards)?,
+        route_messages,
+        other_rewards,
+        other_messages,
+    })
+}
+
 pub fn execute_increase_balance(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     address: String,
     amount: Uint128,
@@ -111,18 +453,50 @@ pub fn execute_increase_balance(
     }
 
     let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    accrue_compound_rewards(&mut state);
     let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
+    apply_denom_migrations(&state, &mut holder);
+    accrue_holder_reward_indices(
+        &state,
+        &mut holder,
+        address == env.contract.address.as_str(),
+    );
+    init_holder_checkpoints(deps.storage, &address_raw, &holder)?;
+
+    // the bLuna minted for the compounded rewards is credited to the auto-compounding holders
+    if address == env.contract.address.as_str() && !state.compound_balance.is_zero() {
+        state.compounded_index = decimal_summation_in_256(
+            state.compounded_index,
+            Decimal::from_ratio(amount, state.compound_balance),
+        );
+    }
 
-    // get decimals
-    let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
+    // the rewards of the bLuna held by the contract are accrued to the holders it is compounded for
+    let rewards = if address == env.contract.address.as_str() {
+        Decimal::zero()
+    } else {
+        calculate_holder_rewards(&state, &holder)
+    };
+    credit_compounded_rewards(&state, &mut holder);
 
     holder.index = state.global_index;
     holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
     holder.balance += amount;
-    state.total_balance += amount;
+    if !holder.excluded {
+        state.total_balance += amount;
+    }
+    if holder.auto_compound {
+        state.compound_balance += amount;
+    }
 
     store_holder(deps.storage, &address_raw, &holder)?;
     store_state(deps.storage, &state)?;
+    store_holder_checkpoint(
+        deps.storage,
+        &address_raw,
+        &holder_checkpoint(&holder, env.block.time.seconds(), state.global_index),
+    )?;
 
     let attributes = vec![
         attr("action", "increase_balance"),
@@ -136,7 +510,7 @@ pub fn execute_increase_balance(
 
 pub fn execute_decrease_balance(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     address: String,
     amount: Uint128,
@@ -153,7 +527,16 @@ pub fn execute_decrease_balance(
     }
 
     let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    accrue_compound_rewards(&mut state);
     let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
+    apply_denom_migrations(&state, &mut holder);
+    accrue_holder_reward_indices(
+        &state,
+        &mut holder,
+        address == env.contract.address.as_str(),
+    );
+    init_holder_checkpoints(deps.storage, &address_raw, &holder)?;
     if holder.balance < amount {
         return Err(StdError::generic_err(format!(
             "Decrease amount cannot exceed user balance: {}",
@@ -161,15 +544,31 @@ pub fn execute_decrease_balance(
         )));
     }
 
-    let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
+    // the rewards of the bLuna held by the contract are accrued to the holders it is compounded for
+    let rewards = if address == env.contract.address.as_str() {
+        Decimal::zero()
+    } else {
+        calculate_holder_rewards(&state, &holder)
+    };
+    credit_compounded_rewards(&state, &mut holder);
 
     holder.index = state.global_index;
     holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
     holder.balance = (holder.balance.checked_sub(amount))?;
-    state.total_balance = (state.total_balance.checked_sub(amount))?;
+    if !holder.excluded {
+        state.total_balance = (state.total_balance.checked_sub(amount))?;
+    }
+    if holder.auto_compound {
+        state.compound_balance = state.compound_balance.checked_sub(amount)?;
+    }
 
     store_holder(deps.storage, &address_raw, &holder)?;
     store_state(deps.storage, &state)?;
+    store_holder_checkpoint(
+        deps.storage,
+        &address_raw,
+        &holder_checkpoint(&holder, env.block.time.seconds(), state.global_index),
+    )?;
 
     let attributes = vec![
         attr("action", "decrease_balance"),
@@ -182,22 +581,169 @@ pub fn execute_decrease_balance(
     Ok(res)
 }
 
-pub fn query_accrued_rewards(deps: Deps, address: String) -> StdResult<AccruedRewardsResponse> {
-    let global_index = read_state(deps.storage)?.global_index;
+pub fn query_accrued_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<AccruedRewardsResponse> {
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
 
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
-    let reward_with_decimals =
-        calculate_decimal_rewards(global_index, holder.index, holder.balance);
+    let mut holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    apply_denom_migrations(&state, &mut holder);
+    accrue_holder_reward_indices(
+        &state,
+        &mut holder,
+        address == env.contract.address.as_str(),
+    );
+    credit_compounded_rewards(&state, &mut holder);
+    let reward_with_decimals = calculate_holder_rewards(&state, &holder);
     let all_reward_with_decimals =
         decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
 
     let rewards = all_reward_with_decimals * Uint128::new(1);
 
-    Ok(AccruedRewardsResponse { rewards })
+    let config: Config = read_config(deps.storage)?;
+    let mut all_rewards = vec![Coin {
+        denom: config.reward_denom,
+        amount: rewards,
+    }];
+    for holder_index in holder.reward_indices {
+        all_rewards.push(Coin {
+            denom: holder_index.denom,
+            amount: holder_index.pending_rewards * Uint128::new(1),
+        });
+    }
+    all_rewards.retain(|coin| !coin.amount.is_zero());
+
+    Ok(AccruedRewardsResponse {
+        rewards,
+        all_rewards,
+    })
+}
+
+pub fn query_pending_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<PendingRewardsResponse> {
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    let config: Config = read_config(deps.storage)?;
+
+    let mut holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    apply_denom_migrations(&state, &mut holder);
+    credit_compounded_rewards(&state, &mut holder);
+    let accrued_rewards = decimal_summation_in_256(
+        calculate_holder_rewards(&state, &holder),
+        holder.pending_rewards,
+    );
+
+    // the rewards sent to the contract but not added to the global index yet
+    // and the ones still with the validators and the rewards dispatcher
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address, config.reward_denom.as_str())?;
+    let mut rewards =
+        balance.amount.saturating_sub(state.prev_reward_balance) + state.unreleased_rewards;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let dispatcher_addr = deps
+        .api
+        .addr_humanize(&query_rewards_dispatcher_contract_address(deps, hub_addr)?)?;
+    let dispatch_rewards = query_pending_dispatch_rewards(deps, dispatcher_addr)?;
+    if dispatch_rewards.denom == config.reward_denom {
+        rewards += dispatch_rewards.amount;
+    }
+
+    let pending_rewards = if state.total_balance.is_zero() {
+        Uint128::zero()
+    } else {
+        rewards.multiply_ratio(rewarded_balance(&holder), state.total_balance)
+    };
+
+    Ok(PendingRewardsResponse {
+        rewards: accrued_rewards * Uint128::new(1) + pending_rewards,
+        pending_rewards,
+    })
+}
+
+pub fn query_rewards_between(
+    deps: Deps,
+    env: Env,
+    address: String,
+    from: u64,
+    to: u64,
+) -> StdResult<RewardsBetweenResponse> {
+    if from > to {
+        return Err(StdError::generic_err(
+            "the start of the range can't be later than its end",
+        ));
+    }
+    let retained_from = env
+        .block
+        .time
+        .seconds()
+        .saturating_sub(CHECKPOINTS_RETENTION_PERIOD);
+    if from < retained_from {
+        return Err(StdError::generic_err(format!(
+            "the checkpoints made before {} are pruned",
+            retained_from
+        )));
+    }
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+
+    // the balance of the holder not changed since the checkpoints were introduced
+    let initial_checkpoint = match read_holder_checkpoint_at(deps.storage, &address_raw, u64::MAX)?
+    {
+        Some(_) => None,
+        None => {
+            let state: State = read_state(deps.storage)?;
+            let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
+            apply_denom_migrations(&state, &mut holder);
+            Some(holder_checkpoint(&holder, 0, holder.index))
+        }
+    };
+    let checkpoint_at = |time: u64| -> StdResult<Option<HolderCheckpoint>> {
+        Ok(read_holder_checkpoint_at(deps.storage, &address_raw, time)?
+            .or_else(|| initial_checkpoint.clone()))
+    };
+    // the global index is only growing, so the latest known value is the greatest one
+    let index_at = |time: u64| -> StdResult<Decimal> {
+        let global_index = read_index_checkpoint_at(deps.storage, time)?
+            .map_or(Decimal::zero(), |checkpoint| checkpoint.global_index);
+        let holder_index =
+            checkpoint_at(time)?.map_or(Decimal::zero(), |checkpoint| checkpoint.index);
+        Ok(global_index.max(holder_index))
+    };
+
+    // the balance of the holder is constant between its checkpoints
+    let mut times = vec![from];
+    for checkpoint in read_holder_checkpoints_between(deps.storage, &address_raw, from, to)? {
+        times.push(checkpoint.time);
+    }
+    if times.last() != Some(&to) {
+        times.push(to);
+    }
+
+    let mut rewards = Decimal::zero();
+    for range in times.windows(2) {
+        let balance = checkpoint_at(range[0])?.map_or(Uint128::zero(), |c| c.balance);
+        rewards = decimal_summation_in_256(
+            rewards,
+            calculate_decimal_rewards(index_at(range[1])?, index_at(range[0])?, balance),
+        );
+    }
+
+    Ok(RewardsBetweenResponse {
+        rewards: rewards * Uint128::new(1),
+    })
 }
 
 pub fn query_holder(deps: Deps, address: String) -> StdResult<HolderResponse> {
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    let state: State = read_state(deps.storage)?;
+    let mut holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    apply_denom_migrations(&state, &mut holder);
+    credit_compounded_rewards(&state, &mut holder);
     Ok(HolderResponse {
         address,
         balance: holder.balance,
@@ -206,6 +752,19 @@ pub fn query_holder(deps: Deps, address: String) -> StdResult<HolderResponse> {
     })
 }
 
+pub fn query_compounded_rewards(
+    deps: Deps,
+    address: String,
+) -> StdResult<CompoundedRewardsResponse> {
+    let state: State = read_state(deps.storage)?;
+    let mut holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    credit_compounded_rewards(&state, &mut holder);
+    Ok(CompoundedRewardsResponse {
+        auto_compound: holder.auto_compound,
+        compounded_rewards: holder.compounded_rewards * Uint128::new(1),
+    })
+}
+
 pub fn query_holders(
     deps: Deps,
     start_after: Option<String>,
@@ -222,8 +781,472 @@ pub fn query_holders(
     Ok(HoldersResponse { holders })
 }
 
+pub fn query_holders_by_balance(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    descending: Option<bool>,
+) -> StdResult<HoldersResponse> {
+    let start_after = match start_after {
+        Some(value) => Some(deps.api.addr_validate(value.as_str())?),
+        None => None,
+    };
+    let order = if descending.unwrap_or(false) {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+    let holders: Vec<HolderResponse> = read_holders_by_balance(deps, start_after, limit, order)?;
+
+    Ok(HoldersResponse { holders })
+}
+
+pub fn execute_set_auto_compound(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let holder_addr_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if holder_addr_raw == deps.api.addr_canonicalize(env.contract.address.as_str())? {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    accrue_compound_rewards(&mut state);
+    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
+    apply_denom_migrations(&state, &mut holder);
+    accrue_holder_reward_indices(&state, &mut holder, false);
+    init_holder_checkpoints(deps.storage, &holder_addr_raw, &holder)?;
+
+    let rewards = calculate_holder_rewards(&state, &holder);
+    credit_compounded_rewards(&state, &mut holder);
+    holder.index = state.global_index;
+    holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
+
+    if enabled && holder.excluded {
+        return Err(StdError::generic_err(
+            "excluded holders can't compound rewards",
+        ));
+    }
+
+    // the rewards accrued before opting in stay claimable
+    if enabled && !holder.auto_compound {
+        state.compound_balance += holder.balance;
+    } else if !enabled && holder.auto_compound {
+        state.compound_balance = state.compound_balance.checked_sub(holder.balance)?;
+    }
+    holder.auto_compound = enabled;
+
+    store_holder(deps.storage, &holder_addr_raw, &holder)?;
+    store_state(deps.storage, &state)?;
+    store_holder_checkpoint(
+        deps.storage,
+        &holder_addr_raw,
+        &holder_checkpoint(&holder, env.block.time.seconds(), state.global_index),
+    )?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "set_auto_compound"),
+        attr("holder_address", info.sender),
+        attr("enabled", enabled.to_string()),
+    ]);
+
+    Ok(res)
+}
+
+pub fn execute_withdraw_compounded_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let holder_addr = info.sender;
+    let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
+    let recipient = match recipient {
+        Some(value) => deps.api.addr_validate(value.as_str())?,
+        None => holder_addr.clone(),
+    };
+
+    let state: State = read_state(deps.storage)?;
+    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
+    apply_denom_migrations(&state, &mut holder);
+    accrue_holder_reward_indices(&state, &mut holder, false);
+    credit_compounded_rewards(&state, &mut holder);
+
+    let amount = holder.compounded_rewards * Uint128::new(1);
+    if amount.is_zero() {
+        return Err(StdError::generic_err("No rewards have been compounded yet"));
+    }
+    holder.compounded_rewards =
+        holder.compounded_rewards - Decimal::from_ratio(amount, Uint128::new(1));
+    store_holder(deps.storage, &holder_addr_raw, &holder)?;
+
+    let config: Config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let token_addr = deps
+        .api
+        .addr_humanize(&query_token_contract_address(deps.as_ref(), hub_addr)?)?;
+
+    let res = Response::new()
+        .add_attributes(vec![
+            attr("action", "withdraw_compounded_rewards"),
+            attr("holder_address", holder_addr),
+            attr("amount", amount),
+        ])
+        .add_message(WasmMsg::Execute {
+            contract_addr: token_addr.to_string(),
+            msg: to_binary(&TokenExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        });
+
+    Ok(res)
+}
+
+pub fn execute_set_claim_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+    enabled: bool,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let holder_addr_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let operator_raw = deps.api.addr_canonicalize(&operator)?;
+
+    let mut settings = read_claim_settings(deps.storage, &holder_addr_raw)?;
+    settings.operators.retain(|o| *o != operator_raw);
+    if enabled {
+        settings.operators.push(operator_raw);
+    }
+    store_claim_settings(deps.storage, &holder_addr_raw, &settings)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "set_claim_operator"),
+        attr("holder_address", info.sender),
+        attr("operator", operator),
+        attr("enabled", enabled.to_string()),
+    ]);
+
+    Ok(res)
+}
+
+pub fn execute_update_holder_status(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    status: HolderStatus,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    if query_hub_owner(deps.as_ref(), hub_addr)?
+        != deps.api.addr_canonicalize(info.sender.as_str())?
+    {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let mut state: State = read_state(deps.storage)?;
+    release_rewards(&mut state, env.block.time.seconds());
+    accrue_compound_rewards(&mut state);
+    let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
+    apply_denom_migrations(&state, &mut holder);
+
+    // the rewards accrued before the update stay with the holder
+    accrue_holder_reward_indices(
+        &state,
+        &mut holder,
+        address == env.contract.address.as_str(),
+    );
+    init_holder_checkpoints(deps.storage, &address_raw, &holder)?;
+    let rewards = calculate_holder_rewards(&state, &holder);
+    credit_compounded_rewards(&state, &mut holder);
+    holder.index = state.global_index;
+    holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
+
+    let excluded = matches!(status, HolderStatus::Excluded {});
+    if excluded && !holder.excluded {
+        state.total_balance = state.total_balance.checked_sub(holder.balance)?;
+        if holder.auto_compound {
+            state.compound_balance = state.compound_balance.checked_sub(holder.balance)?;
+            holder.auto_compound = false;
+        }
+    } else if !excluded && holder.excluded {
+        state.total_balance += holder.balance;
+    }
+    holder.excluded = excluded;
+    holder.beneficiary = match status {
+        HolderStatus::Delegated { beneficiary } => Some(deps.api.addr_canonicalize(&beneficiary)?),
+        _ => None,
+    };
+
+    store_holder(deps.storage, &address_raw, &holder)?;
+    store_state(deps.storage, &state)?;
+    store_holder_checkpoint(
+        deps.storage,
+        &address_raw,
+        &holder_checkpoint(&holder, env.block.time.seconds(), state.global_index),
+    )?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "update_holder_status"),
+        attr("holder_address", address),
+    ]);
+
+    Ok(res)
+}
+
+pub fn query_holder_status(deps: Deps, address: String) -> StdResult<HolderStatusResponse> {
+    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    let status = match holder.beneficiary {
+        Some(beneficiary) => HolderStatus::Delegated {
+            beneficiary: deps.api.addr_humanize(&beneficiary)?.to_string(),
+        },
+        None if holder.excluded => HolderStatus::Excluded {},
+        None => HolderStatus::Regular {},
+    };
+    Ok(HolderStatusResponse { status })
+}
+
+// the number of the routes is limited by the gas of the claims
+const MAX_REWARD_ROUTES: usize = 10;
+
+pub fn execute_set_reward_routes(
+    deps: DepsMut,
+    info: MessageInfo,
+    routes: Vec<RewardRoute>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let holder_addr_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    if routes.len() > MAX_REWARD_ROUTES {
+        return Err(StdError::generic_err(format!(
+            "the number of reward routes can't be greater than {}",
+            MAX_REWARD_ROUTES
+        )));
+    }
+    let mut total_share = Decimal::zero();
+    let mut routes_raw: Vec<RewardRouteInfo> = vec![];
+    for route in routes {
+        if route.share.is_zero() {
+            return Err(StdError::generic_err("reward route share is zero"));
+        }
+        total_share = total_share + route.share;
+        routes_raw.push(RewardRouteInfo {
+            recipient: deps.api.addr_canonicalize(&route.recipient)?,
+            share: route.share,
+            msg: route.msg,
+        });
+    }
+    if total_share > Decimal::one() {
+        return Err(StdError::generic_err(
+            "reward route shares can't sum up to more than one",
+        ));
+    }
+
+    let mut settings = read_claim_settings(deps.storage, &holder_addr_raw)?;
+    settings.routes = routes_raw;
+    store_claim_settings(deps.storage, &holder_addr_raw, &settings)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "set_reward_routes"),
+        attr("holder_address", info.sender),
+    ]);
+
+    Ok(res)
+}
+
+pub fn query_claim_settings(deps: Deps, address: String) -> StdResult<ClaimSettingsResponse> {
+    let settings = read_claim_settings(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    Ok(ClaimSettingsResponse {
+        operators: settings
+            .operators
+            .iter()
+            .map(|operator| Ok(deps.api.addr_humanize(operator)?.to_string()))
+            .collect::<StdResult<Vec<String>>>()?,
+        routes: settings
+            .routes
+            .into_iter()
+            .map(|route| {
+                Ok(RewardRoute {
+                    recipient: deps.api.addr_humanize(&route.recipient)?.to_string(),
+                    share: route.share,
+                    msg: route.msg,
+                })
+            })
+            .collect::<StdResult<Vec<RewardRoute>>>()?,
+    })
+}
+
+// the rewards of the auto-compounding holders are accrued to the compound pool instead,
+// the excluded holders accrue no rewards
+fn calculate_holder_rewards(state: &State, holder: &Holder) -> Decimal {
+    if holder.auto_compound || holder.excluded {
+        return Decimal::zero();
+    }
+    calculate_decimal_rewards(state.global_index, holder.index, holder.balance)
+}
+
+// the balance of the holder accruing the rewards in the reward denom
+fn rewarded_balance(holder: &Holder) -> Uint128 {
+    if holder.auto_compound || holder.excluded {
+        Uint128::zero()
+    } else {
+        holder.balance
+    }
+}
+
+fn holder_checkpoint(holder: &Holder, time: u64, index: Decimal) -> HolderCheckpoint {
+    HolderCheckpoint {
+        time,
+        balance: rewarded_balance(holder),
+        index,
+    }
+}
+
+// the balance the holder had before its first checkpoint is recorded at the zero time
+fn init_holder_checkpoints(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    holder: &Holder,
+) -> StdResult<()> {
+    let checkpoint = holder_checkpoint(holder, 0, holder.index);
+    if checkpoint.balance.is_zero()
+        || read_holder_checkpoint_at(storage, holder_address, u64::MAX)?.is_some()
+    {
+        return Ok(());
+    }
+    store_holder_checkpoint(storage, holder_address, &checkpoint)
+}
+
+// accrue the rewards in the other denoms since the last update of the holder,
+// the excluded holders accrue no rewards
+/// Accrues the rewards in the other denoms to the holder along with the rewards of its
+/// compounded bLuna the same way the reward denom does, the contract holding the compounded
+/// bLuna accrues none of them
+fn accrue_holder_reward_indices(state: &State, holder: &mut Holder, held_by_contract: bool) {
+    for reward_index in state.reward_indices.iter() {
+        let position = holder
+            .reward_indices
+            .iter()
+            .position(|i| i.denom == reward_index.denom);
+        let holder_index = match position {
+            Some(position) => &mut holder.reward_indices[position],
+            None => {
+                holder.reward_indices.push(HolderRewardIndex {
+                    denom: reward_index.denom.clone(),
+                    index: Decimal::zero(),
+                    pending_rewards: Decimal::zero(),
+                    compounded_weighted_index: Decimal::zero(),
+                });
+                holder.reward_indices.last_mut().unwrap()
+            }
+        };
+        if !held_by_contract {
+            let index_delta =
+                decimal_subtraction_in_256(reward_index.global_index, holder_index.index);
+            let mut rewards = decimal_multiplication_in_256(holder.compounded_rewards, index_delta);
+            if !holder.excluded {
+                rewards = decimal_summation_in_256(
+                    rewards,
+                    calculate_decimal_rewards(
+                        reward_index.global_index,
+                        holder_index.index,
+                        holder.balance,
+                    ),
+                );
+            }
+            if holder.auto_compound {
+                let weighted_delta = decimal_subtraction_in_256(
+                    reward_index.compounded_weighted_index,
+                    holder_index.compounded_weighted_index,
+                );
+                let credited_delta =
+                    decimal_multiplication_in_256(holder.compounded_index, index_delta);
+                if weighted_delta > credited_delta {
+                    rewards = decimal_summation_in_256(
+                        rewards,
+                        calculate_decimal_rewards(weighted_delta, credited_delta, holder.balance),
+                    );
+                }
+            }
+            holder_index.pending_rewards =
+                decimal_summation_in_256(holder_index.pending_rewards, rewards);
+        }
+        holder_index.index = reward_index.global_index;
+        holder_index.compounded_weighted_index = reward_index.compounded_weighted_index;
+    }
+}
+
+// send the reward coin to the recipient, the cw20 rewards are transferred by the token contract
+fn reward_coin_msg(
+    deps: &DepsMut,
+    recipient: String,
+    coin: Coin,
+) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
+    Ok(match coin.denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token_addr) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_addr.to_string(),
+            msg: to_binary(&TokenExecuteMsg::Transfer {
+                recipient,
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        }),
+        None => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient,
+            amount: vec![deduct_tax(&deps.querier, coin)?],
+        }),
+    })
+}
+
+// credit the bLuna compounded since the last update to the auto-compounding holder.
+// the rewards the compounded bLuna held by the contract has accrued by now are settled first.
+fn credit_compounded_rewards(state: &State, holder: &mut Holder) {
+    accrue_held_compounded_rewards(holder, state.global_index, state.compounded_weighted_index);
+    if holder.auto_compound {
+        let compounded = calculate_decimal_rewards(
+            state.compounded_index,
+            holder.compounded_index,
+            holder.balance,
+        );
+        holder.compounded_rewards = decimal_summation_in_256(holder.compounded_rewards, compounded);
+    }
+    holder.compounded_index = state.compounded_index;
+}
+
+// accrue the rewards of the compounded bLuna of the holder held by the contract till the global
+// index reaches `global_index`. The bLuna credited since compounded_index of the holder is held
+// since it was minted, so its rewards are the weighted index increments over the balance.
+pub(crate) fn accrue_held_compounded_rewards(
+    holder: &mut Holder,
+    global_index: Decimal,
+    weighted_index: Decimal,
+) {
+    if let Some(prev_global_index) = holder.compounded_global_index {
+        let index_delta = decimal_subtraction_in_256(global_index, prev_global_index);
+        let mut rewards = decimal_multiplication_in_256(holder.compounded_rewards, index_delta);
+        if holder.auto_compound {
+            let weighted_delta =
+                decimal_subtraction_in_256(weighted_index, holder.compounded_weighted_index);
+            let credited_delta =
+                decimal_multiplication_in_256(holder.compounded_index, index_delta);
+            if weighted_delta > credited_delta {
+                rewards = decimal_summation_in_256(
+                    rewards,
+                    calculate_decimal_rewards(weighted_delta, credited_delta, holder.balance),
+                );
+            }
+        }
+        holder.pending_rewards = decimal_summation_in_256(holder.pending_rewards, rewards);
+    }
+    holder.compounded_global_index = Some(global_index);
+    holder.compounded_weighted_index = weighted_index;
+}
+
 // calculate the reward based on the sender's index and the global index.
-fn calculate_decimal_rewards(
+pub(crate) fn calculate_decimal_rewards(
     global_index: Decimal,
     user_index: Decimal,
     user_balance: Uint128,
@@ -16,19 +16,43 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    attr, to_binary, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
-    Fraction, MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
+    attr, to_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, Fraction, MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
-use basset::hub::ExecuteMsg::{BondRewards, UpdateGlobalIndex};
-use basset::{compute_lido_fee, deduct_tax};
+use crate::msg::{
+    AprResponse, BondedToken, BufferedReward, ConfigResponse, DispatchHistoryResponse, ExecuteMsg,
+    FeeRecipient, GetBufferedRewardsResponse, InstantiateMsg, MigrateMsg, NextDispatchResponse,
+    PendingRewardsResponse, QueryMsg, RewardRateHistoryResponse, RewardStream,
+};
+use crate::reward_stream::{
+    canonicalize_reward_streams, humanize_reward_streams, stream_label, stream_targets,
+};
+use crate::state::{
+    read_dispatch_history, read_last_dispatch_time, read_reward_rate_history,
+    read_reward_rate_records_since, store_dispatch_record, store_reward_rate_record, Config,
+    DispatchRecord, FeeRecipientInfo, RewardDestinationInfo, RewardRateRecord, RewardStreamInfo,
+    CONFIG, DISPATCH_DEFERRED, DISPATCH_HISTORY, OLD_CONFIG,
+};
+use crate::swap_route::{
+    execute_receive, execute_remove_swap_route, execute_set_swap_route, execute_swap_hops,
+    query_swap_routes, query_token_balances, simulate_route,
+};
+use crate::terraswap::{Asset, AssetInfo};
+use basset::hub::ExecuteMsg::{BondRewards, BondRewardsForStLuna};
+use basset::hub::{QueryMsg as HubQueryMsg, StateResponse as HubStateResponse};
+use basset::reward::ExecuteMsg::{MigrateRewardDenom, UpdateGlobalIndex};
+use basset::{compute_lido_fee, compute_swap_deviation, deduct_tax};
 use std::ops::Mul;
 use terra_cosmwasm::{
     create_swap_msg, ExchangeRatesResponse, SwapResponse, TerraMsgWrapper, TerraQuerier,
 };
 
+/// The upper bound for the Lido fee rate of the reward streams
+pub fn max_lido_fee_rate() -> Decimal {
+    Decimal::percent(20)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -36,14 +60,26 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    check_fee_rate(msg.stluna_fee_rate)?;
+    check_fee_rate(msg.bluna_fee_rate)?;
+    if let Some(d) = msg.max_swap_deviation {
+        check_max_swap_deviation(d)?;
+    }
     let conf = Config {
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         hub_contract: deps.api.addr_canonicalize(&msg.hub_contract)?,
         bluna_reward_contract: deps.api.addr_canonicalize(&msg.bluna_reward_contract)?,
         bluna_reward_denom: msg.bluna_reward_denom,
         stluna_reward_denom: msg.stluna_reward_denom,
-        lido_fee_address: deps.api.addr_canonicalize(&msg.lido_fee_address)?,
-        lido_fee_rate: msg.lido_fee_rate,
+        lido_fee_recipients: canonicalize_fee_recipients(deps.as_ref(), msg.lido_fee_recipients)?,
+        stluna_fee_rate: msg.stluna_fee_rate,
+        bluna_fee_rate: msg.bluna_fee_rate,
+        lido_fee_in_stluna: false,
+        max_swap_deviation: msg.max_swap_deviation,
+        min_dispatch_amounts: vec![],
+        max_dispatch_interval: 0,
+        rewards_release_period: 0,
+        reward_streams: vec![],
     };
 
     CONFIG.save(deps.storage, &conf)?;
@@ -70,14 +106,37 @@ pub fn execute(
             stluna_total_mint_amount,
         ),
         ExecuteMsg::DispatchRewards {} => execute_dispatch_rewards(deps, env, info),
+        ExecuteMsg::MigrateRewardDenom { token, new_denom } => {
+            execute_migrate_reward_denom(deps, env, info, token, new_denom)
+        }
+        ExecuteMsg::SetSwapRoute {
+            offer_asset_info,
+            hops,
+        } => execute_set_swap_route(deps, info, offer_asset_info, hops),
+        ExecuteMsg::RemoveSwapRoute { offer_asset_info } => {
+            execute_remove_swap_route(deps, info, offer_asset_info)
+        }
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::ExecuteSwapHops {
+            offer_asset_info,
+            hops,
+        } => execute_swap_hops(deps, env, info, offer_asset_info, hops),
         ExecuteMsg::UpdateConfig {
             owner,
             hub_contract,
             bluna_reward_contract,
             stluna_reward_denom,
             bluna_reward_denom,
-            lido_fee_address,
-            lido_fee_rate,
+            lido_fee_recipients,
+            stluna_fee_rate,
+            bluna_fee_rate,
+            lido_fee_in_stluna,
+            max_swap_deviation,
+            clear_max_swap_deviation,
+            min_dispatch_amounts,
+            max_dispatch_interval,
+            rewards_release_period,
+            reward_streams,
         } => execute_update_config(
             deps,
             env,
@@ -87,8 +146,16 @@ pub fn execute(
             bluna_reward_contract,
             stluna_reward_denom,
             bluna_reward_denom,
-            lido_fee_address,
-            lido_fee_rate,
+            lido_fee_recipients,
+            stluna_fee_rate,
+            bluna_fee_rate,
+            lido_fee_in_stluna,
+            max_swap_deviation,
+            clear_max_swap_deviation,
+            min_dispatch_amounts,
+            max_dispatch_interval,
+            rewards_release_period,
+            reward_streams,
         ),
     }
 }
@@ -103,8 +170,16 @@ pub fn execute_update_config(
     bluna_reward_contract: Option<String>,
     stluna_reward_denom: Option<String>,
     bluna_reward_denom: Option<String>,
-    lido_fee_address: Option<String>,
-    lido_fee_rate: Option<Decimal>,
+    lido_fee_recipients: Option<Vec<FeeRecipient>>,
+    stluna_fee_rate: Option<Decimal>,
+    bluna_fee_rate: Option<Decimal>,
+    lido_fee_in_stluna: Option<bool>,
+    max_swap_deviation: Option<Decimal>,
+    clear_max_swap_deviation: Option<bool>,
+    min_dispatch_amounts: Option<Vec<Coin>>,
+    max_dispatch_interval: Option<u64>,
+    rewards_release_period: Option<u64>,
+    reward_streams: Option<Vec<RewardStream>>,
 ) -> StdResult<Response<TerraMsgWrapper>> {
     let conf = CONFIG.load(deps.storage)?;
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
@@ -151,18 +226,104 @@ pub fn execute_update_config(
         ));
     }
 
-    if let Some(r) = lido_fee_rate {
+    if let Some(r) = stluna_fee_rate {
+        check_fee_rate(r)?;
+
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.stluna_fee_rate = r;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(r) = bluna_fee_rate {
+        check_fee_rate(r)?;
+
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.bluna_fee_rate = r;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(f) = lido_fee_in_stluna {
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.lido_fee_in_stluna = f;
+            Ok(last_config)
+        })?;
+    }
+
+    if clear_max_swap_deviation.unwrap_or_default() {
+        if max_swap_deviation.is_some() {
+            return Err(StdError::generic_err(
+                "max swap deviation can't be set and cleared at once",
+            ));
+        }
+
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.max_swap_deviation = None;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(d) = max_swap_deviation {
+        check_max_swap_deviation(d)?;
+
         CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
-            last_config.lido_fee_rate = r;
+            last_config.max_swap_deviation = Some(d);
             Ok(last_config)
         })?;
     }
 
-    if let Some(a) = lido_fee_address {
-        let address_raw = deps.api.addr_canonicalize(&a)?;
+    if let Some(streams) = reward_streams {
+        let streams_raw = canonicalize_reward_streams(deps.as_ref(), &conf, streams)?;
 
         CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
-            last_config.lido_fee_address = address_raw;
+            last_config.reward_streams = streams_raw;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(amounts) = min_dispatch_amounts {
+        let streams = CONFIG.load(deps.storage)?.reward_streams();
+        for (i, amount) in amounts.iter().enumerate() {
+            if !streams.iter().any(|stream| stream.denom == amount.denom) {
+                return Err(StdError::generic_err(format!(
+                    "{} is not a reward denom",
+                    amount.denom
+                )));
+            }
+            if amounts[..i].iter().any(|a| a.denom == amount.denom) {
+                return Err(StdError::generic_err(format!(
+                    "duplicate minimum dispatch amount of {}",
+                    amount.denom
+                )));
+            }
+        }
+
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.min_dispatch_amounts = amounts;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(i) = max_dispatch_interval {
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.max_dispatch_interval = i;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(p) = rewards_release_period {
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.rewards_release_period = p;
+            Ok(last_config)
+        })?;
+    }
+
+    if let Some(recipients) = lido_fee_recipients {
+        let recipients_raw = canonicalize_fee_recipients(deps.as_ref(), recipients)?;
+
+        CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
+            last_config.lido_fee_recipients = recipients_raw;
             Ok(last_config)
         })?;
     }
@@ -170,6 +331,153 @@ pub fn execute_update_config(
     Ok(Response::default())
 }
 
+/// Swaps the balance of the previous reward denom of the token to the new one. The rewards
+/// contract of bLuna does the same and converts the accrued rewards at the rate of its swap.
+pub fn execute_migrate_reward_denom(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: BondedToken,
+    new_denom: String,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    // the hub bonds only its underlying coin
+    if let BondedToken::StLuna = token {
+        return Err(StdError::generic_err(
+            "migrating stluna reward denom is forbidden",
+        ));
+    }
+
+    let prev_denom = config.bluna_reward_denom.clone();
+    if new_denom == prev_denom {
+        return Err(StdError::generic_err(format!(
+            "bluna rewards are already paid in {}",
+            new_denom
+        )));
+    }
+    if config
+        .reward_streams()
+        .iter()
+        .any(|stream| stream.denom == new_denom)
+    {
+        return Err(StdError::generic_err(format!(
+            "{} is already a reward denom",
+            new_denom
+        )));
+    }
+
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address, prev_denom.as_str())?;
+    if !balance.amount.is_zero() {
+        // the whole balance is swapped at once, so the migration waits for a fair market
+        if let Some(max_deviation) = config.max_swap_deviation {
+            let xchg_rates =
+                get_exchange_rates(deps.as_ref(), &[prev_denom.clone(), new_denom.clone()])?;
+            let (_, deviation) = simulate_swap(&deps, &balance, &new_denom, xchg_rates[1].0)?;
+            if deviation > max_deviation {
+                return Err(StdError::generic_err(format!(
+                    "swap deviation {} exceeds max swap deviation {}",
+                    deviation, max_deviation
+                )));
+            }
+        }
+        messages.push(create_swap_msg(balance, new_denom.clone()));
+    }
+
+    for stream in config.reward_streams.iter_mut() {
+        if stream.denom == prev_denom {
+            stream.denom = new_denom.clone();
+        }
+    }
+    // the minimum amount of the previous denom doesn't apply to the new one
+    config
+        .min_dispatch_amounts
+        .retain(|amount| amount.denom != prev_denom);
+    config.bluna_reward_denom = new_denom.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps
+            .api
+            .addr_humanize(&config.bluna_reward_contract)?
+            .to_string(),
+        msg: to_binary(&MigrateRewardDenom {
+            new_denom: new_denom.clone(),
+        })?,
+        funds: vec![],
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "migrate_reward_denom"),
+        attr("prev_denom", prev_denom),
+        attr("new_denom", new_denom),
+    ]))
+}
+
+fn check_max_swap_deviation(max_swap_deviation: Decimal) -> StdResult<()> {
+    if max_swap_deviation > Decimal::one() {
+        return Err(StdError::generic_err(
+            "max swap deviation can't be greater than 1",
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_fee_rate(fee_rate: Decimal) -> StdResult<()> {
+    if fee_rate > max_lido_fee_rate() {
+        return Err(StdError::generic_err(format!(
+            "fee rate can't be greater than {}",
+            max_lido_fee_rate()
+        )));
+    }
+    Ok(())
+}
+
+fn canonicalize_fee_recipients(
+    deps: Deps,
+    recipients: Vec<FeeRecipient>,
+) -> StdResult<Vec<FeeRecipientInfo>> {
+    if recipients.is_empty() {
+        return Err(StdError::generic_err("fee recipients list is empty"));
+    }
+
+    let mut total_share = Decimal::zero();
+    let mut recipients_raw: Vec<FeeRecipientInfo> = vec![];
+    for recipient in recipients {
+        if recipient.share.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "fee share of {} is zero",
+                recipient.address
+            )));
+        }
+        let address = deps.api.addr_canonicalize(&recipient.address)?;
+        if recipients_raw.iter().any(|r| r.address == address) {
+            return Err(StdError::generic_err(format!(
+                "duplicate fee recipient {}",
+                recipient.address
+            )));
+        }
+        total_share = total_share + recipient.share;
+        recipients_raw.push(FeeRecipientInfo {
+            address,
+            share: recipient.share,
+        });
+    }
+
+    if total_share != Decimal::one() {
+        return Err(StdError::generic_err("fee shares must sum up to one"));
+    }
+
+    Ok(recipients_raw)
+}
+
 pub fn execute_swap(
     deps: DepsMut,
     env: Env,
@@ -184,67 +492,139 @@ pub fn execute_swap(
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    let streams = config.reward_streams();
+    let target_denoms: Vec<String> = streams.iter().map(|s| s.denom.clone()).collect();
+
     let contr_addr = env.contract.address;
-    let balance = deps.querier.query_all_balances(contr_addr)?;
-    let (total_luna_rewards_available, total_ust_rewards_available, mut msgs) =
-        convert_to_target_denoms(
-            &deps,
-            balance.clone(),
-            config.stluna_reward_denom.clone(),
-            config.bluna_reward_denom.clone(),
-        )?;
-
-    let (luna_2_ust_rewards_xchg_rate, ust_2_luna_rewards_xchg_rate) = get_exchange_rates(
+    let balance = deps.querier.query_all_balances(contr_addr.clone())?;
+    let (rewards_available, mut msgs, skipped_attrs) = convert_to_target_denoms(
         &deps,
-        config.stluna_reward_denom.as_str(),
-        config.bluna_reward_denom.as_str(),
+        &contr_addr,
+        balance.clone(),
+        &target_denoms,
+        config.max_swap_deviation,
     )?;
 
-    let (offer_coin, ask_denom) = get_swap_info(
-        config,
+    // the swaps wait for the dispatch, otherwise the rewards are split between the streams
+    // by the bonded amounts of a swap the dispatch doesn't follow
+    let rewards_coins: Vec<Coin> = target_denoms
+        .iter()
+        .zip(rewards_available.iter())
+        .map(|(denom, amount)| Coin::new(amount.u128(), denom.as_str()))
+        .collect();
+    let last_dispatch_time = read_last_dispatch_time(deps.storage)?;
+    if !is_dispatch_due(
+        &config,
+        env.block.time.seconds(),
+        last_dispatch_time,
+        &rewards_coins,
+    ) {
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "swap"),
+            attr("initial_balance", format!("{:?}", balance)),
+            attr("dispatch_postponed", "true"),
+        ]));
+    }
+
+    let xchg_rates = get_exchange_rates(deps.as_ref(), &target_denoms)?;
+
+    let swaps = get_swap_info(
+        &streams,
         stluna_total_bonded_amount,
         bluna_total_bonded_amount,
-        total_luna_rewards_available,
-        total_ust_rewards_available,
-        ust_2_luna_rewards_xchg_rate,
-        luna_2_ust_rewards_xchg_rate,
+        &rewards_available,
+        &xchg_rates,
     )?;
 
-    if !offer_coin.amount.is_zero() {
-        msgs.push(create_swap_msg(offer_coin.clone(), ask_denom.clone()));
+    let mut deviation_attrs: Vec<Attribute> = vec![];
+    let mut deferred = false;
+    if let Some(max_deviation) = config.max_swap_deviation {
+        for (offer_coin, ask_denom) in swaps.iter() {
+            if offer_coin.amount.is_zero() {
+                continue;
+            }
+            let offer_2_ask_xchg_rate =
+                get_swap_xchg_rate(&target_denoms, &xchg_rates, &offer_coin.denom, ask_denom);
+            let (_, deviation) =
+                simulate_swap(&deps, offer_coin, ask_denom, offer_2_ask_xchg_rate)?;
+            deviation_attrs.push(attr("swap_deviation", deviation.to_string()));
+            // the rewards can't be split fairly without the swap, so the dispatch waits for the next one
+            deferred = deferred || deviation > max_deviation;
+        }
+    }
+    if deferred {
+        DISPATCH_DEFERRED.save(deps.storage, &true)?;
+        deviation_attrs.push(attr("dispatch_deferred", "true"));
+    } else {
+        DISPATCH_DEFERRED.remove(deps.storage);
+        for (offer_coin, ask_denom) in swaps.iter() {
+            if !offer_coin.amount.is_zero() {
+                msgs.push(create_swap_msg(offer_coin.clone(), ask_denom.clone()));
+            }
+        }
     }
 
-    let res = Response::new().add_messages(msgs).add_attributes(vec![
+    // the exchange rates are the ones between the first stream and the others
+    let labels: Vec<String> = streams
+        .iter()
+        .map(|stream| stream_label(&config, stream))
+        .collect();
+    let mut res = Response::new().add_messages(msgs).add_attributes(vec![
         attr("action", "swap"),
         attr("initial_balance", format!("{:?}", balance)),
-        attr(
-            "luna_2_ust_rewards_xchg_rate",
-            luna_2_ust_rewards_xchg_rate.to_string(),
-        ),
-        attr(
-            "ust_2_luna_rewards_xchg_rate",
-            ust_2_luna_rewards_xchg_rate.to_string(),
-        ),
-        attr("total_luna_rewards_available", total_luna_rewards_available),
-        attr("total_ust_rewards_available", total_ust_rewards_available),
-        attr("offer_coin_denom", offer_coin.denom),
-        attr("offer_coin_amount", offer_coin.amount),
-        attr("ask_denom", ask_denom),
     ]);
+    for (label, xchg_rate) in labels.iter().zip(xchg_rates.iter()).skip(1) {
+        res = res.add_attributes(vec![
+            attr(
+                format!("{}_2_{}_rewards_xchg_rate", labels[0], label),
+                xchg_rate.0.to_string(),
+            ),
+            attr(
+                format!("{}_2_{}_rewards_xchg_rate", label, labels[0]),
+                xchg_rate.1.to_string(),
+            ),
+        ]);
+    }
+    for ((label, stream), amount) in labels.iter().zip(streams.iter()).zip(rewards_available) {
+        res = res.add_attribute(
+            format!("total_{}_rewards_available", label),
+            Coin::new(amount.u128(), stream.denom.as_str()).to_string(),
+        );
+    }
+    for (offer_coin, ask_denom) in swaps {
+        res = res.add_attributes(vec![
+            attr("offer_coin_denom", offer_coin.denom),
+            attr("offer_coin_amount", offer_coin.amount),
+            attr("ask_denom", ask_denom),
+        ]);
+    }
 
-    Ok(res)
+    Ok(res
+        .add_attributes(deviation_attrs)
+        .add_attributes(skipped_attrs))
 }
 
-#[allow(clippy::needless_collect)]
+/// Returns the rewards available in the target denoms along with the swap messages
+/// for the other denoms and tokens and the attributes of the swaps skipped for exceeding
+/// max_swap_deviation, the skipped assets stay in the buffer till the next swap.
+/// The assets with a swap route are swapped through their Terraswap pairs,
+/// the other native denoms are swapped at the market into the last target denom
+/// if the oracle has a rate for them
+#[allow(clippy::needless_collect, clippy::type_complexity)]
 pub(crate) fn convert_to_target_denoms(
     deps: &DepsMut,
+    contract_addr: &Addr,
     balance: Vec<Coin>,
-    denom_to_keep: String,
-    denom_to_xchg: String,
-) -> StdResult<(Uint128, Uint128, Vec<CosmosMsg<TerraMsgWrapper>>)> {
-    let terra_querier = TerraQuerier::new(&deps.querier);
-    let mut total_luna_available: Uint128 = Uint128::zero();
-    let mut total_usd_available: Uint128 = Uint128::zero();
+    target_denoms: &[String],
+    max_swap_deviation: Option<Decimal>,
+) -> StdResult<(
+    Vec<Uint128>,
+    Vec<CosmosMsg<TerraMsgWrapper>>,
+    Vec<Attribute>,
+)> {
+    let mut rewards_available: Vec<Uint128> = vec![Uint128::zero(); target_denoms.len()];
+    let denom_to_xchg = target_denoms[target_denoms.len() - 1].clone();
+    let target_index = |denom: &str| target_denoms.iter().position(|d| d == denom);
 
     let denoms: Vec<String> = balance.iter().map(|item| item.denom.clone()).collect();
     let exchange_rates = query_exchange_rates(deps, denom_to_xchg.clone(), denoms)?;
@@ -254,30 +634,103 @@ pub(crate) fn convert_to_target_denoms(
         .map(|item| item.quote_denom.clone())
         .collect();
     let mut msgs: Vec<CosmosMsg<TerraMsgWrapper>> = Vec::new();
+    let mut skipped_attrs: Vec<Attribute> = Vec::new();
 
-    for coin in balance {
-        if !known_denoms.contains(&coin.denom) {
-            continue;
+    let mut assets: Vec<Asset> = balance
+        .iter()
+        .filter(|coin| target_index(&coin.denom).is_none())
+        .map(|coin| Asset {
+            info: AssetInfo::NativeToken {
+                denom: coin.denom.clone(),
+            },
+            amount: coin.amount,
+        })
+        .collect();
+    assets.append(&mut query_token_balances(deps.as_ref(), contract_addr)?);
+    let mut routed_denoms: Vec<String> = vec![];
+    for asset in assets {
+        let simulation = match simulate_route(
+            deps.as_ref(),
+            contract_addr,
+            &asset.info,
+            asset.amount,
+            max_swap_deviation,
+        )? {
+            Some(simulation) => simulation,
+            None => continue,
+        };
+        routed_denoms.push(asset.info.to_string());
+        // the route ends in a denom that is no longer a target one
+        let index = match target_index(&simulation.ask_denom) {
+            Some(index) => index,
+            None => continue,
+        };
+        if let Some(max_deviation) = max_swap_deviation {
+            if simulation.spread > max_deviation {
+                skipped_attrs.push(attr("skipped_swap", asset.to_string()));
+                skipped_attrs.push(attr(
+                    "skipped_swap_deviation",
+                    simulation.spread.to_string(),
+                ));
+                continue;
+            }
         }
+        rewards_available[index] += simulation.return_amount;
+        msgs.extend(simulation.msgs);
+    }
 
-        if coin.denom == denom_to_keep {
-            total_luna_available += coin.amount;
+    for coin in balance {
+        if !known_denoms.contains(&coin.denom) || routed_denoms.contains(&coin.denom) {
             continue;
         }
 
-        if coin.denom == denom_to_xchg {
-            total_usd_available += coin.amount;
+        if let Some(index) = target_index(&coin.denom) {
+            rewards_available[index] += coin.amount;
             continue;
         }
 
-        let swap_response: SwapResponse =
-            terra_querier.query_swap(coin.clone(), denom_to_xchg.as_str())?;
-        total_usd_available += swap_response.receive.amount;
+        let xchg_2_coin_rate = exchange_rates
+            .exchange_rates
+            .iter()
+            .find(|item| item.quote_denom == coin.denom)
+            .map(|item| item.exchange_rate)
+            .unwrap_or_default();
+        let coin_2_xchg_rate = xchg_2_coin_rate
+            .inv()
+            .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?;
+        let (receive_amount, deviation) =
+            simulate_swap(deps, &coin, denom_to_xchg.as_str(), coin_2_xchg_rate)?;
+        if let Some(max_deviation) = max_swap_deviation {
+            if deviation > max_deviation {
+                skipped_attrs.push(attr("skipped_swap", coin.to_string()));
+                skipped_attrs.push(attr("skipped_swap_deviation", deviation.to_string()));
+                continue;
+            }
+        }
+        rewards_available[target_denoms.len() - 1] += receive_amount;
 
         msgs.push(create_swap_msg(coin, denom_to_xchg.to_string()));
     }
 
-    Ok((total_luna_available, total_usd_available, msgs))
+    Ok((rewards_available, msgs, skipped_attrs))
+}
+
+/// Simulates the market swap of the offer coin and returns the amount received along with
+/// its deviation from the amount promised by the oracle exchange rate
+pub(crate) fn simulate_swap(
+    deps: &DepsMut,
+    offer_coin: &Coin,
+    ask_denom: &str,
+    offer_2_ask_xchg_rate: Decimal,
+) -> StdResult<(Uint128, Decimal)> {
+    let terra_querier = TerraQuerier::new(&deps.querier);
+    let swap_response: SwapResponse = terra_querier.query_swap(offer_coin.clone(), ask_denom)?;
+    let expected_amount = offer_coin.amount.mul(offer_2_ask_xchg_rate);
+
+    Ok((
+        swap_response.receive.amount,
+        compute_swap_deviation(expected_amount, swap_response.receive.amount),
+    ))
 }
 
 pub(crate) fn query_exchange_rates(
@@ -290,67 +743,122 @@ pub(crate) fn query_exchange_rates(
     Ok(res)
 }
 
+/// Returns the exchange rates of the first denom to each of the denoms and back
 pub(crate) fn get_exchange_rates(
-    deps: &DepsMut,
-    denom_a: &str,
-    denom_b: &str,
-) -> StdResult<(Decimal, Decimal)> {
-    let terra_querier = TerraQuerier::new(&deps.querier);
-    let a_2_b_xchg_rates = terra_querier
-        .query_exchange_rates(denom_a.to_string(), vec![denom_b.to_string()])?
-        .exchange_rates;
+    deps: Deps,
+    denoms: &[String],
+) -> StdResult<Vec<(Decimal, Decimal)>> {
+    let base_denom = denoms
+        .first()
+        .ok_or_else(|| StdError::generic_err("no denoms to get the exchange rates of"))?
+        .clone();
+    let quote_denoms: Vec<String> = denoms
+        .iter()
+        .filter(|denom| **denom != base_denom)
+        .cloned()
+        .collect();
+    let base_2_quote_xchg_rates = if quote_denoms.is_empty() {
+        vec![]
+    } else {
+        let terra_querier = TerraQuerier::new(&deps.querier);
+        terra_querier
+            .query_exchange_rates(base_denom.clone(), quote_denoms)?
+            .exchange_rates
+    };
 
-    Ok((
-        a_2_b_xchg_rates[0].exchange_rate,
-        a_2_b_xchg_rates[0]
-            .exchange_rate
-            .inv()
-            .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?,
-    ))
+    denoms
+        .iter()
+        .map(|denom| {
+            if *denom == base_denom {
+                return Ok((Decimal::one(), Decimal::one()));
+            }
+            let rate = base_2_quote_xchg_rates
+                .iter()
+                .find(|item| item.quote_denom == *denom)
+                .ok_or_else(|| StdError::generic_err(format!("no exchange rate for {}", denom)))?
+                .exchange_rate;
+            Ok((
+                rate,
+                rate.inv()
+                    .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?,
+            ))
+        })
+        .collect()
+}
+
+/// Returns the exchange rate between two target denoms made of the rates to the first one
+fn get_swap_xchg_rate(
+    denoms: &[String],
+    xchg_rates: &[(Decimal, Decimal)],
+    offer_denom: &str,
+    ask_denom: &str,
+) -> Decimal {
+    let offer = denoms.iter().position(|d| d == offer_denom).unwrap_or(0);
+    let ask = denoms.iter().position(|d| d == ask_denom).unwrap_or(0);
+    if offer == 0 {
+        xchg_rates[ask].0
+    } else if ask == 0 {
+        xchg_rates[offer].1
+    } else {
+        Decimal::from_ratio(
+            xchg_rates[ask].0.numerator(),
+            xchg_rates[offer].0.numerator(),
+        )
+    }
 }
 
+/// Returns the swaps that bring the rewards available in the denom of each stream to its share.
+/// The rewards are valued in the denom of the first stream, every stream but the last one
+/// is balanced against the last one. The exchange rates are the rates of the first denom
+/// to the denom of each stream and back.
 pub(crate) fn get_swap_info(
-    config: Config,
+    streams: &[RewardStreamInfo],
     stluna_total_bonded_amount: Uint128,
     bluna_total_bonded_amount: Uint128,
-    total_stluna_rewards_available: Uint128,
-    total_bluna_rewards_available: Uint128,
-    bluna_2_stluna_rewards_xchg_rate: Decimal,
-    stluna_2_bluna_rewards_xchg_rate: Decimal,
-) -> StdResult<(Coin, String)> {
-    // Total rewards in stLuna rewards currency.
-    let total_rewards_in_stluna_rewards = total_stluna_rewards_available
-        + total_bluna_rewards_available.mul(bluna_2_stluna_rewards_xchg_rate);
-
-    let stluna_share_of_total_rewards = total_rewards_in_stluna_rewards.multiply_ratio(
+    rewards_available: &[Uint128],
+    xchg_rates: &[(Decimal, Decimal)],
+) -> StdResult<Vec<(Coin, String)>> {
+    // Total rewards in the first stream denom.
+    let total_rewards = rewards_available
+        .iter()
+        .zip(xchg_rates)
+        .fold(Uint128::zero(), |total, (amount, (_, denom_2_base))| {
+            total + amount.mul(*denom_2_base)
+        });
+
+    let targets = stream_targets(
+        streams,
         stluna_total_bonded_amount,
-        stluna_total_bonded_amount + bluna_total_bonded_amount,
-    );
+        bluna_total_bonded_amount,
+        total_rewards,
+    )?;
 
-    if total_stluna_rewards_available.gt(&stluna_share_of_total_rewards) {
-        let stluna_rewards_to_sell =
-            total_stluna_rewards_available.checked_sub(stluna_share_of_total_rewards)?;
+    let last = streams.len() - 1;
+    let denom_to_xchg = streams[last].denom.clone();
+    let mut swaps: Vec<(Coin, String)> = vec![];
+    for i in 0..last {
+        let available = rewards_available[i].mul(xchg_rates[i].1);
+        if available.gt(&targets[i]) {
+            let rewards_to_sell = available.checked_sub(targets[i])?.mul(xchg_rates[i].0);
 
-        Ok((
-            Coin::new(
-                stluna_rewards_to_sell.u128(),
-                config.stluna_reward_denom.as_str(),
-            ),
-            config.bluna_reward_denom,
-        ))
-    } else {
-        let stluna_rewards_to_buy =
-            stluna_share_of_total_rewards.checked_sub(total_stluna_rewards_available)?;
-        let bluna_rewards_to_sell = stluna_rewards_to_buy.mul(stluna_2_bluna_rewards_xchg_rate);
-
-        Ok((
-            Coin::new(
-                bluna_rewards_to_sell.u128(),
-                config.bluna_reward_denom.as_str(),
-            ),
-            config.stluna_reward_denom,
-        ))
+            swaps.push((
+                Coin::new(rewards_to_sell.u128(), streams[i].denom.as_str()),
+                denom_to_xchg.clone(),
+            ));
+        } else {
+            let rewards_to_buy = targets[i].checked_sub(available)?;
+            let xchg_rewards_to_sell = rewards_to_buy.mul(xchg_rates[last].0);
+
+            swaps.push((
+                Coin::new(xchg_rewards_to_sell.u128(), denom_to_xchg.as_str()),
+                streams[i].denom.clone(),
+            ));
+        }
     }
+    // the last denom is bought with the sold rewards before the other ones are bought with it
+    swaps.sort_by_key(|(offer_coin, _)| offer_coin.denom == denom_to_xchg);
+
+    Ok(swaps)
 }
 
 pub fn execute_dispatch_rewards(
@@ -365,116 +873,631 @@ pub fn execute_dispatch_rewards(
         return Err(StdError::generic_err("unauthorized"));
     }
 
+    // the rewards are kept in the buffer till they can be swapped within max_swap_deviation
+    if DISPATCH_DEFERRED.may_load(deps.storage)?.unwrap_or(false) {
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "claim_reward"),
+            attr("dispatch_deferred", "true"),
+        ]));
+    }
+
     let bluna_reward_addr = deps.api.addr_humanize(&config.bluna_reward_contract)?;
 
+    let streams = config.reward_streams();
     let contr_addr = env.contract.address;
-    let mut stluna_rewards = deps
-        .querier
-        .query_balance(contr_addr.clone(), config.stluna_reward_denom.as_str())?;
-    let lido_stluna_fee_amount = compute_lido_fee(stluna_rewards.amount, config.lido_fee_rate)?;
-    stluna_rewards.amount = stluna_rewards.amount.checked_sub(lido_stluna_fee_amount)?;
+    let mut stream_rewards: Vec<Coin> = streams
+        .iter()
+        .map(|stream| {
+            deps.querier
+                .query_balance(contr_addr.clone(), stream.denom.as_str())
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
 
-    let mut bluna_rewards = deps
-        .querier
-        .query_balance(contr_addr, config.bluna_reward_denom.as_str())?;
-    let lido_bluna_fee_amount = compute_lido_fee(bluna_rewards.amount, config.lido_fee_rate)?;
-    bluna_rewards.amount = bluna_rewards.amount.checked_sub(lido_bluna_fee_amount)?;
+    let last_dispatch_time = read_last_dispatch_time(deps.storage)?;
+    if !is_dispatch_due(
+        &config,
+        env.block.time.seconds(),
+        last_dispatch_time,
+        &stream_rewards,
+    ) {
+        let mut res = Response::new().add_attributes(vec![
+            attr("action", "claim_reward"),
+            attr("dispatch_postponed", "true"),
+        ]);
+        for (stream, rewards) in streams.iter().zip(stream_rewards.iter()) {
+            res = res.add_attribute(
+                format!("{}_rewards", stream_label(&config, stream)),
+                rewards.to_string(),
+            );
+        }
+        return Ok(res);
+    }
 
-    let mut fees_attrs: Vec<Attribute> = vec![];
+    let mut stream_fee_shares: Vec<Vec<Uint128>> = vec![];
+    for (stream, rewards) in streams.iter().zip(stream_rewards.iter_mut()) {
+        let lido_fee_amount = compute_lido_fee(rewards.amount, stream.fee_rate)?;
+        rewards.amount = rewards.amount.checked_sub(lido_fee_amount)?;
+        stream_fee_shares.push(split_lido_fee(&config.lido_fee_recipients, lido_fee_amount));
+    }
 
+    let mut lido_fee_messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut stream_fees: Vec<Coin> = stream_rewards
+        .iter()
+        .map(|rewards| Coin::new(0, rewards.denom.as_str()))
+        .collect();
+    for (i, recipient) in config.lido_fee_recipients.iter().enumerate() {
+        let mut fees: Vec<Coin> = vec![];
+        for (j, fee_shares) in stream_fee_shares.iter().enumerate() {
+            if fee_shares[i].is_zero() {
+                continue;
+            }
+            let fee = deduct_tax(
+                &deps.querier,
+                Coin::new(fee_shares[i].u128(), stream_fees[j].denom.as_str()),
+            )?;
+            stream_fees[j].amount += fee.amount;
+            if config.lido_fee_in_stluna
+                && fee.denom == config.stluna_reward_denom
+                && !fee.amount.is_zero()
+            {
+                // the hub bonds the fee after the rewards and mints stLuna to the recipient
+                lido_fee_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: hub_addr.to_string(),
+                    msg: to_binary(&BondRewardsForStLuna {
+                        recipient: deps.api.addr_humanize(&recipient.address)?.to_string(),
+                    })?,
+                    funds: vec![fee],
+                }));
+            } else {
+                fees.push(fee);
+            }
+        }
+        fees.retain(|fee| !fee.amount.is_zero());
+        if !fees.is_empty() {
+            lido_fee_messages.push(
+                BankMsg::Send {
+                    to_address: deps.api.addr_humanize(&recipient.address)?.to_string(),
+                    amount: fees,
+                }
+                .into(),
+            )
+        }
+    }
+
+    let mut fees_attrs: Vec<Attribute> = vec![];
     let mut lido_fees: Vec<Coin> = vec![];
-    if !lido_stluna_fee_amount.is_zero() {
-        let stluna_fee = deduct_tax(
-            &deps.querier,
-            Coin {
-                amount: lido_stluna_fee_amount,
-                denom: stluna_rewards.denom.clone(),
-            },
-        )?;
-        if !stluna_fee.amount.is_zero() {
-            lido_fees.push(stluna_fee.clone());
-            fees_attrs.push(attr("lido_stluna_fee", stluna_fee.to_string()));
-        }
-    }
-    if !lido_bluna_fee_amount.is_zero() {
-        let bluna_fee = deduct_tax(
-            &deps.querier,
-            Coin {
-                amount: lido_bluna_fee_amount,
-                denom: bluna_rewards.denom.clone(),
-            },
-        )?;
-        if !bluna_fee.amount.is_zero() {
-            lido_fees.push(bluna_fee.clone());
-            fees_attrs.push(attr("lido_bluna_fee", bluna_fee.to_string()));
+    for (stream, fee) in streams.iter().zip(stream_fees) {
+        if !fee.amount.is_zero() {
+            fees_attrs.push(attr(
+                format!("lido_{}_fee", stream_label(&config, stream)),
+                fee.to_string(),
+            ));
+            lido_fees.push(fee);
         }
     }
 
+    let release_period = match config.rewards_release_period {
+        0 => None,
+        period => Some(period),
+    };
+    let mut hub_messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
     let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
-    if !stluna_rewards.amount.is_zero() {
-        stluna_rewards = deduct_tax(&deps.querier, stluna_rewards)?;
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: hub_addr.to_string(),
-            msg: to_binary(&BondRewards {}).unwrap(),
-            funds: vec![stluna_rewards.clone()],
-        }));
-    }
-    if !lido_fees.is_empty() {
-        messages.push(
-            BankMsg::Send {
-                to_address: deps
-                    .api
-                    .addr_humanize(&config.lido_fee_address)?
-                    .to_string(),
-                amount: lido_fees,
+    let mut rewards_attrs: Vec<Attribute> = vec![];
+    let mut record = DispatchRecord {
+        id: 0,
+        height: env.block.height,
+        time: env.block.time.seconds(),
+        hub_rewards: Coin::new(0, config.stluna_reward_denom.as_str()),
+        bluna_rewards: Coin::new(0, config.bluna_reward_denom.as_str()),
+        lido_fees,
+        other_rewards: vec![],
+    };
+    for (stream, mut rewards) in streams.iter().zip(stream_rewards) {
+        if !rewards.amount.is_zero() {
+            rewards = deduct_tax(&deps.querier, rewards)?;
+        }
+        let label = stream_label(&config, stream);
+        match label.as_str() {
+            "stluna" => record.hub_rewards = rewards.clone(),
+            "bluna" => record.bluna_rewards = rewards.clone(),
+            _ => {
+                if !rewards.amount.is_zero() {
+                    record.other_rewards.push(rewards.clone())
+                }
             }
-            .into(),
-        )
-    }
-    if !bluna_rewards.amount.is_zero() {
-        bluna_rewards = deduct_tax(&deps.querier, bluna_rewards)?;
-        messages.push(
-            BankMsg::Send {
-                to_address: bluna_reward_addr.to_string(),
-                amount: vec![bluna_rewards.clone()],
+        }
+        rewards_attrs.push(attr(format!("{}_rewards", label), rewards.to_string()));
+
+        match &stream.destination {
+            RewardDestinationInfo::Hub {} => {
+                if !rewards.amount.is_zero() {
+                    hub_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: hub_addr.to_string(),
+                        msg: to_binary(&BondRewards { release_period }).unwrap(),
+                        funds: vec![rewards],
+                    }));
+                }
             }
-            .into(),
-        )
+            RewardDestinationInfo::RewardContract { address } => {
+                let reward_addr = deps.api.addr_humanize(address)?;
+                if !rewards.amount.is_zero() {
+                    messages.push(
+                        BankMsg::Send {
+                            to_address: reward_addr.to_string(),
+                            amount: vec![rewards],
+                        }
+                        .into(),
+                    )
+                }
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: reward_addr.to_string(),
+                    msg: to_binary(&UpdateGlobalIndex { release_period }).unwrap(),
+                    funds: vec![],
+                }));
+            }
+            RewardDestinationInfo::Hook { contract_addr, msg } => {
+                if !rewards.amount.is_zero() {
+                    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: deps.api.addr_humanize(contract_addr)?.to_string(),
+                        msg: msg.clone(),
+                        funds: vec![rewards],
+                    }));
+                }
+            }
+        }
     }
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: bluna_reward_addr.to_string(),
-        msg: to_binary(&UpdateGlobalIndex {
-            airdrop_hooks: None,
-        })
-        .unwrap(),
-        funds: vec![],
-    }));
+    // the rewards are bonded before the fee, so the fee stLuna is minted at the new rate
+    hub_messages.append(&mut lido_fee_messages);
+    hub_messages.append(&mut messages);
+
+    let dispatch_id = store_dispatch_record(deps.storage, record.clone())?;
+    let reward_rate = reward_rate_record(deps.as_ref(), &config, dispatch_id, &record)?;
+    store_reward_rate_record(deps.storage, &reward_rate)?;
 
     Ok(Response::new()
-        .add_messages(messages)
+        .add_messages(hub_messages)
         .add_attributes(vec![
             attr("action", "claim_reward"),
+            attr("dispatch_id", dispatch_id.to_string()),
             attr("bluna_reward_addr", bluna_reward_addr),
-            attr("stluna_rewards", stluna_rewards.to_string()),
-            attr("bluna_rewards", bluna_rewards.to_string()),
         ])
+        .add_attributes(rewards_attrs)
         .add_attributes(fees_attrs))
 }
 
-fn query_config(deps: Deps) -> StdResult<Config> {
+/// The rewards are dispatched when the balance of a reward denom reaches its minimum
+/// or when max_dispatch_interval has passed since the last dispatch,
+/// each dispatch is due if neither threshold is set
+pub(crate) fn is_dispatch_due(
+    config: &Config,
+    now: u64,
+    last_dispatch_time: Option<u64>,
+    balances: &[Coin],
+) -> bool {
+    if config.min_dispatch_amounts.is_empty() && config.max_dispatch_interval == 0 {
+        return true;
+    }
+    if let Some(next_dispatch_time) = next_dispatch_time(config, last_dispatch_time) {
+        if now >= next_dispatch_time {
+            return true;
+        }
+    }
+    config.min_dispatch_amounts.iter().any(|min_amount| {
+        balances
+            .iter()
+            .any(|balance| balance.denom == min_amount.denom && balance.amount >= min_amount.amount)
+    })
+}
+
+fn next_dispatch_time(config: &Config, last_dispatch_time: Option<u64>) -> Option<u64> {
+    if config.max_dispatch_interval == 0 {
+        return None;
+    }
+    // the first dispatch isn't postponed
+    Some(last_dispatch_time.map_or(0, |time| time + config.max_dispatch_interval))
+}
+
+/// Splits the fee between the recipients according to their shares,
+/// the last recipient gets the remainder left after rounding
+pub(crate) fn split_lido_fee(recipients: &[FeeRecipientInfo], fee: Uint128) -> Vec<Uint128> {
+    let mut rest = fee;
+    let mut fee_shares: Vec<Uint128> = vec![];
+    for (i, recipient) in recipients.iter().enumerate() {
+        let fee_share = if i == recipients.len() - 1 {
+            rest
+        } else {
+            fee.mul(recipient.share).min(rest)
+        };
+        rest -= fee_share;
+        fee_shares.push(fee_share);
+    }
+    fee_shares
+}
+
+/// Returns the rewards of the token holders of the dispatch along with the amounts bonded
+/// to the tokens at the hub
+fn reward_rate_record(
+    deps: Deps,
+    config: &Config,
+    dispatch_id: u64,
+    dispatch: &DispatchRecord,
+) -> StdResult<RewardRateRecord> {
+    let hub_state: HubStateResponse = deps.querier.query_wasm_smart(
+        deps.api.addr_humanize(&config.hub_contract)?,
+        &HubQueryMsg::State {},
+    )?;
+
+    let elapsed = DISPATCH_HISTORY
+        .may_load(deps.storage, &(dispatch_id - 1).to_be_bytes())?
+        .map_or(0, |previous| dispatch.time.saturating_sub(previous.time));
+
+    let bluna_rewards_value = if dispatch.bluna_rewards.amount.is_zero() {
+        Uint128::zero()
+    } else {
+        let terra_querier = TerraQuerier::new(&deps.querier);
+        let stluna_2_bluna_rewards_xchg_rate = terra_querier
+            .query_exchange_rates(
+                config.stluna_reward_denom.clone(),
+                vec![config.bluna_reward_denom.clone()],
+            )?
+            .exchange_rates
+            .first()
+            .ok_or_else(|| {
+                StdError::generic_err(format!(
+                    "no exchange rate for {}",
+                    config.bluna_reward_denom
+                ))
+            })?
+            .exchange_rate;
+        dispatch.bluna_rewards.amount.mul(
+            stluna_2_bluna_rewards_xchg_rate
+                .inv()
+                .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?,
+        )
+    };
+
+    Ok(RewardRateRecord {
+        id: dispatch_id,
+        time: dispatch.time,
+        elapsed,
+        stluna_rewards: dispatch.hub_rewards.amount,
+        bluna_rewards: dispatch.bluna_rewards.amount,
+        bluna_rewards_value,
+        stluna_total_bonded: hub_state.total_bond_stluna_amount,
+        bluna_total_bonded: hub_state.total_bond_bluna_amount,
+    })
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let lido_fee_recipients = config
+        .lido_fee_recipients
+        .iter()
+        .map(|recipient| {
+            Ok(FeeRecipient {
+                address: deps.api.addr_humanize(&recipient.address)?.to_string(),
+                share: recipient.share,
+            })
+        })
+        .collect::<StdResult<Vec<FeeRecipient>>>()?;
+    let reward_streams = humanize_reward_streams(deps, config.reward_streams())?;
+
+    Ok(ConfigResponse {
+        owner: config.owner,
+        hub_contract: config.hub_contract,
+        bluna_reward_contract: config.bluna_reward_contract,
+        stluna_reward_denom: config.stluna_reward_denom,
+        bluna_reward_denom: config.bluna_reward_denom,
+        lido_fee_recipients,
+        stluna_fee_rate: config.stluna_fee_rate,
+        bluna_fee_rate: config.bluna_fee_rate,
+        lido_fee_in_stluna: config.lido_fee_in_stluna,
+        max_swap_deviation: config.max_swap_deviation,
+        min_dispatch_amounts: config.min_dispatch_amounts,
+        max_dispatch_interval: config.max_dispatch_interval,
+        rewards_release_period: config.rewards_release_period,
+        reward_streams,
+    })
+}
+
+fn query_buffered_rewards(deps: Deps, env: Env) -> StdResult<GetBufferedRewardsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance = deps.querier.query_all_balances(env.contract.address)?;
+
+    let mut stluna_rewards = Coin::new(0, config.stluna_reward_denom.as_str());
+    let mut bluna_rewards = Coin::new(0, config.bluna_reward_denom.as_str());
+    let mut other_balance: Vec<Coin> = vec![];
+    for coin in balance {
+        if coin.denom == config.stluna_reward_denom {
+            stluna_rewards = coin;
+        } else if coin.denom == config.bluna_reward_denom {
+            bluna_rewards = coin;
+        } else {
+            other_balance.push(coin);
+        }
+    }
+
+    let mut other_rewards: Vec<BufferedReward> = vec![];
+    if !other_balance.is_empty() {
+        let terra_querier = TerraQuerier::new(&deps.querier);
+        let denoms: Vec<String> = other_balance.iter().map(|c| c.denom.clone()).collect();
+        // the oracle doesn't return rates for the denoms that can't be swapped,
+        // they are skipped by SwapToRewardDenom as well
+        let exchange_rates = terra_querier
+            .query_exchange_rates(config.bluna_reward_denom.clone(), denoms)?
+            .exchange_rates;
+        for coin in other_balance {
+            if let Some(rate) = exchange_rates
+                .iter()
+                .find(|item| item.quote_denom == coin.denom)
+            {
+                let bluna_2_coin_xchg_rate = rate
+                    .exchange_rate
+                    .inv()
+                    .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?;
+                other_rewards.push(BufferedReward {
+                    value: Coin {
+                        amount: coin.amount.mul(bluna_2_coin_xchg_rate),
+                        denom: config.bluna_reward_denom.clone(),
+                    },
+                    amount: coin,
+                });
+            }
+        }
+    }
+
+    Ok(GetBufferedRewardsResponse {
+        stluna_rewards,
+        bluna_rewards,
+        other_rewards,
+    })
+}
+
+/// Estimates the rewards the next dispatch sends to each stream after the Lido fee.
+/// The buffer and the delegation rewards not withdrawn by the hub yet are valued
+/// at the oracle exchange rates and split between the streams as the swap splits them.
+fn query_pending_rewards(deps: Deps, env: Env) -> StdResult<PendingRewardsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let streams = config.reward_streams();
+    let target_denoms: Vec<String> = streams.iter().map(|s| s.denom.clone()).collect();
+    let last = target_denoms.len() - 1;
+
+    let mut balance = deps.querier.query_all_balances(env.contract.address)?;
+    for delegation in deps.querier.query_all_delegations(hub_addr.clone())? {
+        let full_delegation = deps
+            .querier
+            .query_delegation(hub_addr.clone(), delegation.validator)?;
+        for reward in full_delegation.map_or(vec![], |d| d.accumulated_rewards) {
+            match balance.iter_mut().find(|coin| coin.denom == reward.denom) {
+                Some(coin) => coin.amount += reward.amount,
+                None => balance.push(reward),
+            }
+        }
+    }
+
+    let mut rewards_available: Vec<Uint128> = vec![Uint128::zero(); target_denoms.len()];
+    let mut other_balance: Vec<Coin> = vec![];
+    for coin in balance {
+        match target_denoms.iter().position(|d| *d == coin.denom) {
+            Some(index) => rewards_available[index] += coin.amount,
+            None => other_balance.push(coin),
+        }
+    }
+    if !other_balance.is_empty() {
+        let terra_querier = TerraQuerier::new(&deps.querier);
+        let denoms: Vec<String> = other_balance.iter().map(|c| c.denom.clone()).collect();
+        // the denoms the oracle has no rates for are not swapped
+        let exchange_rates = terra_querier
+            .query_exchange_rates(target_denoms[last].clone(), denoms)?
+            .exchange_rates;
+        for coin in other_balance {
+            if let Some(rate) = exchange_rates
+                .iter()
+                .find(|item| item.quote_denom == coin.denom)
+            {
+                let coin_2_xchg_rate = rate
+                    .exchange_rate
+                    .inv()
+                    .ok_or_else(|| StdError::generic_err("failed to convert exchange rate"))?;
+                rewards_available[last] += coin.amount.mul(coin_2_xchg_rate);
+            }
+        }
+    }
+
+    let xchg_rates = get_exchange_rates(deps, &target_denoms)?;
+    let hub_state: HubStateResponse = deps
+        .querier
+        .query_wasm_smart(hub_addr, &HubQueryMsg::State {})?;
+    let swaps = get_swap_info(
+        &streams,
+        hub_state.total_bond_stluna_amount,
+        hub_state.total_bond_bluna_amount,
+        &rewards_available,
+        &xchg_rates,
+    )?;
+    for (offer_coin, ask_denom) in swaps {
+        let offer = target_denoms.iter().position(|d| *d == offer_coin.denom);
+        let ask = target_denoms.iter().position(|d| *d == ask_denom);
+        if let (Some(offer), Some(ask)) = (offer, ask) {
+            let offer_2_ask_xchg_rate =
+                get_swap_xchg_rate(&target_denoms, &xchg_rates, &offer_coin.denom, &ask_denom);
+            rewards_available[offer] = rewards_available[offer].saturating_sub(offer_coin.amount);
+            rewards_available[ask] += offer_coin.amount.mul(offer_2_ask_xchg_rate);
+        }
+    }
+
+    let mut res = PendingRewardsResponse {
+        stluna_rewards: Coin::new(0, config.stluna_reward_denom.as_str()),
+        bluna_rewards: Coin::new(0, config.bluna_reward_denom.as_str()),
+        other_rewards: vec![],
+    };
+    for (stream, amount) in streams.iter().zip(rewards_available) {
+        let lido_fee_amount = compute_lido_fee(amount, stream.fee_rate)?;
+        let rewards = Coin::new(
+            amount.checked_sub(lido_fee_amount)?.u128(),
+            stream.denom.as_str(),
+        );
+        match stream_label(&config, stream).as_str() {
+            "stluna" => res.stluna_rewards = rewards,
+            "bluna" => res.bluna_rewards = rewards,
+            _ => {
+                if !rewards.amount.is_zero() {
+                    res.other_rewards.push(rewards)
+                }
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+fn query_next_dispatch(deps: Deps, env: Env) -> StdResult<NextDispatchResponse> {
     let config = CONFIG.load(deps.storage)?;
-    Ok(config)
+    let stluna_rewards = deps.querier.query_balance(
+        env.contract.address.clone(),
+        config.stluna_reward_denom.as_str(),
+    )?;
+    let bluna_rewards = deps.querier.query_balance(
+        env.contract.address.clone(),
+        config.bluna_reward_denom.as_str(),
+    )?;
+    let stream_rewards = config
+        .reward_streams()
+        .iter()
+        .map(|stream| {
+            deps.querier
+                .query_balance(env.contract.address.clone(), stream.denom.as_str())
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+    let last_dispatch_time = read_last_dispatch_time(deps.storage)?;
+
+    Ok(NextDispatchResponse {
+        dispatch_due: is_dispatch_due(
+            &config,
+            env.block.time.seconds(),
+            last_dispatch_time,
+            &stream_rewards,
+        ),
+        next_dispatch_time: next_dispatch_time(&config, last_dispatch_time),
+        stluna_rewards,
+        bluna_rewards,
+        min_dispatch_amounts: config.min_dispatch_amounts,
+        last_dispatch_time,
+    })
+}
+
+fn query_dispatch_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DispatchHistoryResponse> {
+    let history = read_dispatch_history(deps.storage, start_after, limit)?;
+    Ok(DispatchHistoryResponse { history })
+}
+
+fn query_reward_rate_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RewardRateHistoryResponse> {
+    let history = read_reward_rate_history(deps.storage, start_after, limit)?;
+    Ok(RewardRateHistoryResponse { history })
+}
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// The APR of a token is its rewards over the window annualised and divided by
+/// the average amount bonded to the token, weighted by the time between the dispatches
+fn query_apr(deps: Deps, env: Env, window: u64) -> StdResult<AprResponse> {
+    let since = env.block.time.seconds().saturating_sub(window);
+    let records = read_reward_rate_records_since(deps.storage, since)?;
+
+    let mut elapsed: u64 = 0;
+    let mut stluna_rewards = Uint128::zero();
+    let mut bluna_rewards = Uint128::zero();
+    let mut stluna_bonded_time = Uint128::zero();
+    let mut bluna_bonded_time = Uint128::zero();
+    for record in records {
+        // the rewards of the first dispatch can't be attributed to any period
+        if record.elapsed == 0 {
+            continue;
+        }
+        elapsed += record.elapsed;
+        stluna_rewards += record.stluna_rewards;
+        bluna_rewards += record.bluna_rewards_value;
+        stluna_bonded_time += record
+            .stluna_total_bonded
+            .checked_mul(record.elapsed.into())?;
+        bluna_bonded_time += record
+            .bluna_total_bonded
+            .checked_mul(record.elapsed.into())?;
+    }
+
+    let annualise = |rewards: Uint128, bonded_time: Uint128| -> StdResult<Decimal> {
+        if bonded_time.is_zero() {
+            return Ok(Decimal::zero());
+        }
+        Ok(Decimal::from_ratio(
+            rewards.checked_mul(SECONDS_PER_YEAR.into())?,
+            bonded_time,
+        ))
+    };
+
+    Ok(AprResponse {
+        elapsed,
+        stluna_apr: annualise(stluna_rewards, stluna_bonded_time)?,
+        bluna_apr: annualise(bluna_rewards, bluna_bonded_time)?,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::GetBufferedRewards {} => unimplemented!(),
+        QueryMsg::GetBufferedRewards {} => to_binary(&query_buffered_rewards(deps, env)?),
+        QueryMsg::PendingRewards {} => to_binary(&query_pending_rewards(deps, env)?),
+        QueryMsg::DispatchHistory { start_after, limit } => {
+            to_binary(&query_dispatch_history(deps, start_after, limit)?)
+        }
+        QueryMsg::RewardRateHistory { start_after, limit } => {
+            to_binary(&query_reward_rate_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Apr { window } => to_binary(&query_apr(deps, env, window)?),
+        QueryMsg::NextDispatch {} => to_binary(&query_next_dispatch(deps, env)?),
+        QueryMsg::SwapRoutes { start_after, limit } => {
+            to_binary(&query_swap_routes(deps, start_after, limit)?)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    // migrate the config with the single fee recipient and fee rate
+    if CONFIG.load(deps.storage).is_err() {
+        let old_config = OLD_CONFIG.load(deps.storage)?;
+        check_fee_rate(old_config.lido_fee_rate)?;
+        let new_config = Config {
+            owner: old_config.owner,
+            hub_contract: old_config.hub_contract,
+            bluna_reward_contract: old_config.bluna_reward_contract,
+            stluna_reward_denom: old_config.stluna_reward_denom,
+            bluna_reward_denom: old_config.bluna_reward_denom,
+            lido_fee_recipients: vec![FeeRecipientInfo {
+                address: old_config.lido_fee_address,
+                share: Decimal::one(),
+            }],
+            stluna_fee_rate: old_config.lido_fee_rate,
+            bluna_fee_rate: old_config.lido_fee_rate,
+            lido_fee_in_stluna: false,
+            max_swap_deviation: None,
+            min_dispatch_amounts: vec![],
+            max_dispatch_interval: 0,
+            rewards_release_period: 0,
+            reward_streams: vec![],
+        };
+        CONFIG.save(deps.storage, &new_config)?;
+    }
+
     Ok(Response::default())
 }
@@ -15,6 +15,10 @@
 pub mod contract;
 pub mod msg;
 pub mod state;
+pub mod terraswap;
+
+mod reward_stream;
+mod swap_route;
 
 #[cfg(test)]
 mod testing;
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_std::{Decimal, Uint128};
+use crate::state::{DispatchRecord, RewardRateRecord};
+use crate::terraswap::AssetInfo;
+use cosmwasm_std::{Binary, CanonicalAddr, Coin, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -22,12 +25,62 @@ pub struct InstantiateMsg {
     pub bluna_reward_contract: String,
     pub stluna_reward_denom: String,
     pub bluna_reward_denom: String,
-    pub lido_fee_address: String,
-    pub lido_fee_rate: Decimal,
+    pub lido_fee_recipients: Vec<FeeRecipient>,
+    pub stluna_fee_rate: Decimal,
+    pub bluna_fee_rate: Decimal,
+    // the max shortfall of a market swap against the oracle rate, swaps are not checked if None
+    #[serde(default)]
+    pub max_swap_deviation: Option<Decimal>,
+}
+
+/// FeeRecipient receives the share of the Lido fee, the shares of all recipients sum up to one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeRecipient {
+    pub address: String,
+    pub share: Decimal,
+}
+
+/// RewardStream receives the part of the rewards set by its share in its own denom,
+/// the Lido fee is taken from the stream at its fee rate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStream {
+    pub denom: String,
+    pub destination: RewardDestination,
+    pub share: RewardShare,
+    pub fee_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardDestination {
+    /// The rewards are bonded by the hub for the stLuna holders
+    Hub {},
+    /// The rewards are sent to the reward contract, then its global index is updated
+    RewardContract { address: String },
+    /// The rewards are sent along with the message to the contract
+    Hook { contract_addr: String, msg: Binary },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardShare {
+    /// The share of the rewards left after the fixed shares that is equal to the share of
+    /// the token in the amount bonded to all the tokens of the bonded streams
+    Bonded { token: BondedToken },
+    /// The fixed share of all the rewards
+    Fixed { share: Decimal },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BondedToken {
+    StLuna,
+    BLuna,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     SwapToRewardDenom {
         bluna_total_bonded: Uint128,
@@ -39,10 +92,50 @@ pub enum ExecuteMsg {
         bluna_reward_contract: Option<String>,
         stluna_reward_denom: Option<String>,
         bluna_reward_denom: Option<String>,
-        lido_fee_address: Option<String>,
-        lido_fee_rate: Option<Decimal>,
+        lido_fee_recipients: Option<Vec<FeeRecipient>>,
+        stluna_fee_rate: Option<Decimal>,
+        bluna_fee_rate: Option<Decimal>,
+        lido_fee_in_stluna: Option<bool>,
+        max_swap_deviation: Option<Decimal>,
+        // removes the max swap deviation, so the swaps aren't checked against the oracle rate
+        clear_max_swap_deviation: Option<bool>,
+        min_dispatch_amounts: Option<Vec<Coin>>,
+        max_dispatch_interval: Option<u64>,
+        rewards_release_period: Option<u64>,
+        reward_streams: Option<Vec<RewardStream>>,
     },
     DispatchRewards {},
+    // MigrateRewardDenom swaps the balances of the reward denom of the token to the new denom
+    // and switches the dispatcher and the reward contract of the token to it,
+    // the migration fails while the swap deviates from the oracle rate by more than max_swap_deviation
+    MigrateRewardDenom {
+        token: BondedToken,
+        new_denom: String,
+    },
+    // SetSwapRoute sets the Terraswap pairs the asset is swapped through into a reward denom,
+    // the route replaces the market swap for the native denoms
+    SetSwapRoute {
+        offer_asset_info: AssetInfo,
+        hops: Vec<SwapHop>,
+    },
+    RemoveSwapRoute {
+        offer_asset_info: AssetInfo,
+    },
+    // Receive swaps the cw20 tokens sent to the contract through their route
+    Receive(Cw20ReceiveMsg),
+    // ExecuteSwapHops swaps the whole balance of the asset through the next hops of a route,
+    // only the contract itself is allowed to execute
+    ExecuteSwapHops {
+        offer_asset_info: AssetInfo,
+        hops: Vec<SwapHop>,
+    },
+}
+
+/// SwapHop swaps the asset received from the previous hop at the pair into the ask asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapHop {
+    pub pair_contract: String,
+    pub ask_asset_info: AssetInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -50,13 +143,116 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     // GetBufferedRewards returns the buffered amount of bLuna and stLuna rewards.
     GetBufferedRewards {},
+    // PendingRewards estimates the rewards the next dispatch sends to the tokens,
+    // including the delegation rewards not withdrawn by the hub yet
+    PendingRewards {},
     // Config returns config
     Config {},
+    // DispatchHistory returns the amounts sent by the previous DispatchRewards calls
+    DispatchHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // RewardRateHistory returns the latest rewards distributed to the holders of the tokens
+    RewardRateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // Apr returns the annualised reward rate of the tokens for the last `window` seconds
+    Apr {
+        window: u64,
+    },
+    // NextDispatch returns the buffered rewards and when they are going to be dispatched
+    NextDispatch {},
+    // SwapRoutes returns the Terraswap routes ordered by the denom or the token address
+    SwapRoutes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetBufferedRewardsResponse {}
+pub struct ConfigResponse {
+    pub owner: CanonicalAddr,
+    pub hub_contract: CanonicalAddr,
+    pub bluna_reward_contract: CanonicalAddr,
+    pub stluna_reward_denom: String,
+    pub bluna_reward_denom: String,
+    pub lido_fee_recipients: Vec<FeeRecipient>,
+    pub stluna_fee_rate: Decimal,
+    pub bluna_fee_rate: Decimal,
+    pub lido_fee_in_stluna: bool,
+    pub max_swap_deviation: Option<Decimal>,
+    pub min_dispatch_amounts: Vec<Coin>,
+    pub max_dispatch_interval: u64,
+    pub rewards_release_period: u64,
+    pub reward_streams: Vec<RewardStream>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetBufferedRewardsResponse {
+    pub stluna_rewards: Coin,
+    pub bluna_rewards: Coin,
+    // other denoms that are swapped to the bLuna reward denom before dispatching
+    pub other_rewards: Vec<BufferedReward>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsResponse {
+    pub stluna_rewards: Coin,
+    pub bluna_rewards: Coin,
+    // the rewards of the other reward streams
+    pub other_rewards: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BufferedReward {
+    pub amount: Coin,
+    // the value of the amount in the bLuna reward denom at the oracle exchange rate
+    pub value: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NextDispatchResponse {
+    pub stluna_rewards: Coin,
+    pub bluna_rewards: Coin,
+    pub min_dispatch_amounts: Vec<Coin>,
+    pub last_dispatch_time: Option<u64>,
+    // the time the rewards are dispatched at regardless of the minimums, None if there is no limit
+    pub next_dispatch_time: Option<u64>,
+    // whether the next DispatchRewards call sends the rewards
+    pub dispatch_due: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DispatchHistoryResponse {
+    pub history: Vec<DispatchRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardRateHistoryResponse {
+    pub history: Vec<RewardRateRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AprResponse {
+    // seconds covered by the dispatches within the window
+    pub elapsed: u64,
+    pub stluna_apr: Decimal,
+    pub bluna_apr: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub offer_asset_info: AssetInfo,
+    pub hops: Vec<SwapHop>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoutesResponse {
+    pub routes: Vec<SwapRoute>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MigrateMsg {}
@@ -0,0 +1,184 @@
+// Copyright 2021 Lido
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{Decimal, Deps, StdError, StdResult, Uint128};
+use std::ops::Mul;
+
+use crate::contract::check_fee_rate;
+use crate::msg::{BondedToken, RewardDestination, RewardShare, RewardStream};
+use crate::state::{Config, RewardDestinationInfo, RewardStreamInfo};
+
+/// Validates the reward streams and converts their addresses to the canonical ones
+pub(crate) fn canonicalize_reward_streams(
+    deps: Deps,
+    config: &Config,
+    streams: Vec<RewardStream>,
+) -> StdResult<Vec<RewardStreamInfo>> {
+    if streams.is_empty() {
+        return Err(StdError::generic_err("reward streams list is empty"));
+    }
+
+    let mut total_fixed_share = Decimal::zero();
+    let mut has_bonded_share = false;
+    let mut streams_raw: Vec<RewardStreamInfo> = vec![];
+    for stream in streams {
+        if streams_raw.iter().any(|s| s.denom == stream.denom) {
+            return Err(StdError::generic_err(format!(
+                "duplicate reward stream of {}",
+                stream.denom
+            )));
+        }
+        check_fee_rate(stream.fee_rate)?;
+        match &stream.share {
+            RewardShare::Bonded { .. } => has_bonded_share = true,
+            RewardShare::Fixed { share } => {
+                if share.is_zero() {
+                    return Err(StdError::generic_err(format!(
+                        "fixed share of the {} stream is zero",
+                        stream.denom
+                    )));
+                }
+                total_fixed_share = total_fixed_share + *share;
+            }
+        }
+
+        let destination = match stream.destination {
+            RewardDestination::Hub {} => {
+                // the hub bonds only the coins it delegates
+                if stream.denom != config.stluna_reward_denom {
+                    return Err(StdError::generic_err(format!(
+                        "the hub can't bond {} rewards",
+                        stream.denom
+                    )));
+                }
+                RewardDestinationInfo::Hub {}
+            }
+            RewardDestination::RewardContract { address } => {
+                RewardDestinationInfo::RewardContract {
+                    address: deps.api.addr_canonicalize(&address)?,
+                }
+            }
+            RewardDestination::Hook { contract_addr, msg } => RewardDestinationInfo::Hook {
+                contract_addr: deps.api.addr_canonicalize(&contract_addr)?,
+                msg,
+            },
+        };
+        streams_raw.push(RewardStreamInfo {
+            denom: stream.denom,
+            destination,
+            share: stream.share,
+            fee_rate: stream.fee_rate,
+        });
+    }
+
+    if total_fixed_share > Decimal::one() {
+        return Err(StdError::generic_err(
+            "fixed shares can't sum up to more than one",
+        ));
+    }
+    if !has_bonded_share && total_fixed_share != Decimal::one() {
+        return Err(StdError::generic_err(
+            "fixed shares must sum up to one without bonded shares",
+        ));
+    }
+
+    Ok(streams_raw)
+}
+
+pub(crate) fn humanize_reward_streams(
+    deps: Deps,
+    streams: Vec<RewardStreamInfo>,
+) -> StdResult<Vec<RewardStream>> {
+    streams
+        .into_iter()
+        .map(|stream| {
+            let destination = match stream.destination {
+                RewardDestinationInfo::Hub {} => RewardDestination::Hub {},
+                RewardDestinationInfo::RewardContract { address } => {
+                    RewardDestination::RewardContract {
+                        address: deps.api.addr_humanize(&address)?.to_string(),
+                    }
+                }
+                RewardDestinationInfo::Hook { contract_addr, msg } => RewardDestination::Hook {
+                    contract_addr: deps.api.addr_humanize(&contract_addr)?.to_string(),
+                    msg,
+                },
+            };
+            Ok(RewardStream {
+                denom: stream.denom,
+                destination,
+                share: stream.share,
+                fee_rate: stream.fee_rate,
+            })
+        })
+        .collect()
+}
+
+/// Splits the total rewards between the streams, the fixed shares are taken first and the rest
+/// is split between the bonded streams in proportion to the amounts bonded to their tokens
+pub(crate) fn stream_targets(
+    streams: &[RewardStreamInfo],
+    stluna_total_bonded_amount: Uint128,
+    bluna_total_bonded_amount: Uint128,
+    total_rewards: Uint128,
+) -> StdResult<Vec<Uint128>> {
+    let bonded_amount = |token: &BondedToken| match token {
+        BondedToken::StLuna => stluna_total_bonded_amount,
+        BondedToken::BLuna => bluna_total_bonded_amount,
+    };
+
+    let mut fixed_rewards = Uint128::zero();
+    let mut total_bonded_amount = Uint128::zero();
+    for stream in streams {
+        match &stream.share {
+            RewardShare::Fixed { share } => fixed_rewards += total_rewards.mul(*share),
+            RewardShare::Bonded { token } => total_bonded_amount += bonded_amount(token),
+        }
+    }
+    let bonded_rewards = total_rewards.checked_sub(fixed_rewards)?;
+    if total_bonded_amount.is_zero() && !bonded_rewards.is_zero() {
+        return Err(StdError::generic_err(
+            "nothing is bonded to the tokens of the bonded reward streams",
+        ));
+    }
+
+    Ok(streams
+        .iter()
+        .map(|stream| match &stream.share {
+            RewardShare::Fixed { share } => total_rewards.mul(*share),
+            RewardShare::Bonded { token } => {
+                if bonded_rewards.is_zero() {
+                    Uint128::zero()
+                } else {
+                    bonded_rewards.multiply_ratio(bonded_amount(token), total_bonded_amount)
+                }
+            }
+        })
+        .collect())
+}
+
+/// Returns the prefix of the attributes of the stream: "stluna" for the hub,
+/// "bluna" for the bLuna reward contract and the denom of the stream for the others,
+/// the denoms of the streams are unique so each stream gets its own attributes
+pub(crate) fn stream_label(config: &Config, stream: &RewardStreamInfo) -> String {
+    match &stream.destination {
+        RewardDestinationInfo::Hub {} => "stluna".to_string(),
+        RewardDestinationInfo::RewardContract { address }
+            if *address == config.bluna_reward_contract =>
+        {
+            "bluna".to_string()
+        }
+        _ => stream.denom.clone(),
+    }
+}
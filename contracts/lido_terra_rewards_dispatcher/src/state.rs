@@ -15,14 +15,116 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Decimal};
+use cosmwasm_std::{Binary, CanonicalAddr, Coin, Decimal, Order, StdResult, Storage, Uint128};
 
-use cw_storage_plus::Item;
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::msg::{BondedToken, RewardShare};
+use crate::terraswap::AssetInfo;
 
 pub static CONFIG: Item<Config> = Item::new("config");
+pub static OLD_CONFIG: Item<OldConfig> = Item::new("config");
+pub static DISPATCH_HISTORY: Map<&[u8], DispatchRecord> = Map::new("dispatch_history");
+pub static DISPATCH_COUNT: Item<u64> = Item::new("dispatch_count");
+// set by SwapToRewardDenom when the rewards can't be rebalanced within max_swap_deviation
+pub static DISPATCH_DEFERRED: Item<bool> = Item::new("dispatch_deferred");
+pub static REWARD_RATE_HISTORY: Map<&[u8], RewardRateRecord> = Map::new("reward_rate_history");
+pub static SWAP_ROUTES: Map<&[u8], SwapRouteInfo> = Map::new("swap_routes");
+
+/// The number of the latest reward rate records kept in the storage
+pub const REWARD_RATE_HISTORY_LENGTH: u64 = 1000;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    pub owner: CanonicalAddr,
+    pub hub_contract: CanonicalAddr,
+    pub bluna_reward_contract: CanonicalAddr,
+    pub stluna_reward_denom: String,
+    pub bluna_reward_denom: String,
+    pub lido_fee_recipients: Vec<FeeRecipientInfo>,
+    pub stluna_fee_rate: Decimal,
+    pub bluna_fee_rate: Decimal,
+    // bond the Luna fee and mint stLuna to the fee recipients instead of sending the coins
+    #[serde(default)]
+    pub lido_fee_in_stluna: bool,
+    // the max shortfall of a market swap against the oracle rate, swaps are not checked if None
+    #[serde(default)]
+    pub max_swap_deviation: Option<Decimal>,
+    // the rewards stay in the buffer till the balance of a reward denom reaches its minimum
+    #[serde(default)]
+    pub min_dispatch_amounts: Vec<Coin>,
+    // seconds after the last dispatch when the rewards are dispatched regardless of the minimums,
+    // zero disables the limit
+    #[serde(default)]
+    pub max_dispatch_interval: u64,
+    // seconds over which the dispatched rewards are released to the holders,
+    // zero releases the rewards at once
+    #[serde(default)]
+    pub rewards_release_period: u64,
+    // the streams the rewards are split between, the stLuna and bLuna streams made of
+    // the fields above are used if it's empty
+    #[serde(default)]
+    pub reward_streams: Vec<RewardStreamInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeRecipientInfo {
+    pub address: CanonicalAddr,
+    pub share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStreamInfo {
+    pub denom: String,
+    pub destination: RewardDestinationInfo,
+    pub share: RewardShare,
+    pub fee_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardDestinationInfo {
+    Hub {},
+    RewardContract {
+        address: CanonicalAddr,
+    },
+    Hook {
+        contract_addr: CanonicalAddr,
+        msg: Binary,
+    },
+}
+
+impl Config {
+    /// Returns the configured reward streams or the stLuna and bLuna streams by default
+    pub fn reward_streams(&self) -> Vec<RewardStreamInfo> {
+        if !self.reward_streams.is_empty() {
+            return self.reward_streams.clone();
+        }
+        vec![
+            RewardStreamInfo {
+                denom: self.stluna_reward_denom.clone(),
+                destination: RewardDestinationInfo::Hub {},
+                share: RewardShare::Bonded {
+                    token: BondedToken::StLuna,
+                },
+                fee_rate: self.stluna_fee_rate,
+            },
+            RewardStreamInfo {
+                denom: self.bluna_reward_denom.clone(),
+                destination: RewardDestinationInfo::RewardContract {
+                    address: self.bluna_reward_contract.clone(),
+                },
+                share: RewardShare::Bonded {
+                    token: BondedToken::BLuna,
+                },
+                fee_rate: self.bluna_fee_rate,
+            },
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OldConfig {
     pub owner: CanonicalAddr,
     pub hub_contract: CanonicalAddr,
     pub bluna_reward_contract: CanonicalAddr,
@@ -31,3 +133,162 @@ pub struct Config {
     pub lido_fee_address: CanonicalAddr,
     pub lido_fee_rate: Decimal,
 }
+
+/// DispatchRecord keeps the amounts sent by a single DispatchRewards call
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DispatchRecord {
+    pub id: u64,
+    pub height: u64,
+    pub time: u64,
+    pub hub_rewards: Coin,
+    pub bluna_rewards: Coin,
+    pub lido_fees: Vec<Coin>,
+    // sent to the streams other than the stLuna and bLuna ones
+    #[serde(default)]
+    pub other_rewards: Vec<Coin>,
+}
+
+/// Stores the record under the next id and returns the id
+pub fn store_dispatch_record(
+    storage: &mut dyn Storage,
+    mut record: DispatchRecord,
+) -> StdResult<u64> {
+    let id = DISPATCH_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    record.id = id;
+    DISPATCH_HISTORY.save(storage, &id.to_be_bytes(), &record)?;
+    DISPATCH_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Returns the time of the last DispatchRewards call
+pub fn read_last_dispatch_time(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    let last_id = match DISPATCH_COUNT.may_load(storage)? {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    Ok(DISPATCH_HISTORY
+        .may_load(storage, &last_id.to_be_bytes())?
+        .map(|record| record.time))
+}
+
+/// RewardRateRecord keeps the rewards distributed to the holders of the tokens
+/// by a single DispatchRewards call
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardRateRecord {
+    pub id: u64,
+    pub time: u64,
+    // seconds passed since the previous dispatch, zero for the first one
+    pub elapsed: u64,
+    // bonded to stLuna, in the stLuna reward denom
+    pub stluna_rewards: Uint128,
+    // sent to the bLuna reward contract, in the bLuna reward denom
+    pub bluna_rewards: Uint128,
+    // the value of bluna_rewards in the stLuna reward denom at the oracle exchange rate
+    pub bluna_rewards_value: Uint128,
+    pub stluna_total_bonded: Uint128,
+    pub bluna_total_bonded: Uint128,
+}
+
+/// Stores the record and removes the ones that don't fit into REWARD_RATE_HISTORY_LENGTH
+pub fn store_reward_rate_record(
+    storage: &mut dyn Storage,
+    record: &RewardRateRecord,
+) -> StdResult<()> {
+    REWARD_RATE_HISTORY.save(storage, &record.id.to_be_bytes(), record)?;
+    if record.id > REWARD_RATE_HISTORY_LENGTH {
+        let outdated_id = record.id - REWARD_RATE_HISTORY_LENGTH;
+        REWARD_RATE_HISTORY.remove(storage, &outdated_id.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// SwapRouteInfo keeps the Terraswap pairs an asset is swapped through into a reward denom,
+/// the route is stored under the denom or the token address of the offer asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRouteInfo {
+    pub offer_asset_info: AssetInfo,
+    pub hops: Vec<SwapHopInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapHopInfo {
+    pub pair_contract: CanonicalAddr,
+    pub ask_asset_info: AssetInfo,
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 100;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn read_dispatch_history(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<DispatchRecord>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.to_be_bytes().to_vec()));
+
+    DISPATCH_HISTORY
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+pub fn read_reward_rate_history(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<RewardRateRecord>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.to_be_bytes().to_vec()));
+
+    REWARD_RATE_HISTORY
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+/// Returns the reward rate records made after `since`, starting from the latest one
+pub fn read_reward_rate_records_since(
+    storage: &dyn Storage,
+    since: u64,
+) -> StdResult<Vec<RewardRateRecord>> {
+    let mut records: Vec<RewardRateRecord> = vec![];
+    for item in REWARD_RATE_HISTORY.range(storage, None, None, Order::Descending) {
+        let record = item?.1;
+        if record.time <= since {
+            break;
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn read_swap_routes(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<SwapRouteInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|key| Bound::exclusive(key.into_bytes()));
+
+    SWAP_ROUTES
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+/// Returns the routes of all the cw20 tokens
+pub fn read_token_swap_routes(storage: &dyn Storage) -> StdResult<Vec<SwapRouteInfo>> {
+    let mut routes: Vec<SwapRouteInfo> = vec![];
+    for item in SWAP_ROUTES.range(storage, None, None, Order::Ascending) {
+        let route = item?.1;
+        if let AssetInfo::Token { .. } = route.offer_asset_info {
+            routes.push(route);
+        }
+    }
+    Ok(routes)
+}
@@ -0,0 +1,418 @@
+// Copyright 2021 Lido
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use terra_cosmwasm::TerraMsgWrapper;
+
+use crate::msg::{ExecuteMsg, SwapHop, SwapRoute, SwapRoutesResponse};
+use crate::state::{
+    read_swap_routes, read_token_swap_routes, Config, SwapHopInfo, SwapRouteInfo, CONFIG,
+    SWAP_ROUTES,
+};
+use crate::terraswap::{
+    Asset, AssetInfo, PairCw20HookMsg, PairExecuteMsg, PairQueryMsg, SimulationResponse,
+};
+use basset::{compute_swap_deviation, deduct_tax};
+
+pub fn execute_set_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    offer_asset_info: AssetInfo,
+    hops: Vec<SwapHop>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if hops.is_empty() {
+        return Err(StdError::generic_err("swap route is empty"));
+    }
+    let offer_asset_info = validate_asset_info(deps.as_ref(), offer_asset_info)?;
+    if is_reward_denom(&config, &offer_asset_info) {
+        return Err(StdError::generic_err(
+            "can't set a swap route for a reward denom",
+        ));
+    }
+
+    let mut hops_raw: Vec<SwapHopInfo> = vec![];
+    for hop in hops {
+        hops_raw.push(SwapHopInfo {
+            pair_contract: deps.api.addr_canonicalize(&hop.pair_contract)?,
+            ask_asset_info: validate_asset_info(deps.as_ref(), hop.ask_asset_info)?,
+        });
+    }
+    if let Some((last_hop, intermediate_hops)) = hops_raw.split_last() {
+        // the hops swap the whole balance of the intermediate assets
+        if intermediate_hops
+            .iter()
+            .any(|hop| is_reward_denom(&config, &hop.ask_asset_info))
+        {
+            return Err(StdError::generic_err(
+                "swap route can't pass through a reward denom",
+            ));
+        }
+        if !is_reward_denom(&config, &last_hop.ask_asset_info) {
+            return Err(StdError::generic_err(
+                "swap route must end in a reward denom",
+            ));
+        }
+    }
+
+    let key = offer_asset_info.to_string();
+    SWAP_ROUTES.save(
+        deps.storage,
+        key.as_bytes(),
+        &SwapRouteInfo {
+            offer_asset_info,
+            hops: hops_raw,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_swap_route"),
+        attr("offer_asset", key),
+    ]))
+}
+
+pub fn execute_remove_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    offer_asset_info: AssetInfo,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let key = validate_asset_info(deps.as_ref(), offer_asset_info)?.to_string();
+    if SWAP_ROUTES
+        .may_load(deps.storage, key.as_bytes())?
+        .is_none()
+    {
+        return Err(StdError::generic_err(format!("no swap route for {}", key)));
+    }
+    SWAP_ROUTES.remove(deps.storage, key.as_bytes());
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_swap_route"),
+        attr("offer_asset", key),
+    ]))
+}
+
+/// Swaps the tokens sent to the contract through their route, the tokens without
+/// a route are rejected
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let config = CONFIG.load(deps.storage)?;
+    let route = SWAP_ROUTES
+        .may_load(deps.storage, info.sender.as_str().as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("no swap route for token {}", info.sender)))?;
+
+    let offer_asset = Asset {
+        info: route.offer_asset_info.clone(),
+        amount: cw20_msg.amount,
+    };
+    let hops = humanize_hops(deps.as_ref(), &route.hops)?;
+    let msgs = swap_route_msgs(
+        &env.contract.address,
+        &offer_asset,
+        hops,
+        config.max_swap_deviation,
+    )?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "receive"),
+        attr("from", cw20_msg.sender),
+        attr("routed_swap", offer_asset.to_string()),
+    ]))
+}
+
+/// Swaps the whole balance of the asset received from the previous hop through the rest of the route
+pub fn execute_swap_hops(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_asset_info: AssetInfo,
+    hops: Vec<SwapHop>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+    let config = CONFIG.load(deps.storage)?;
+
+    let balance = query_asset_balance(deps.as_ref(), &env.contract.address, &offer_asset_info)?;
+    let offer_asset = Asset {
+        amount: offer_amount(deps.as_ref(), &offer_asset_info, balance)?,
+        info: offer_asset_info,
+    };
+    if offer_asset.amount.is_zero() {
+        return Ok(Response::new().add_attributes(vec![attr("action", "swap_hops")]));
+    }
+    let msgs = swap_route_msgs(
+        &env.contract.address,
+        &offer_asset,
+        hops,
+        config.max_swap_deviation,
+    )?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "swap_hops"),
+        attr("routed_swap", offer_asset.to_string()),
+    ]))
+}
+
+/// RouteSimulation keeps the outcome of swapping an asset through its route
+pub(crate) struct RouteSimulation {
+    pub ask_denom: String,
+    pub return_amount: Uint128,
+    // the largest spread of the hops
+    pub spread: Decimal,
+    pub msgs: Vec<CosmosMsg<TerraMsgWrapper>>,
+}
+
+/// Simulates the swap of the amount through the route of the asset and returns
+/// the swap messages along with the amount of the reward denom received,
+/// None if the asset has no route
+pub(crate) fn simulate_route(
+    deps: Deps,
+    contract_addr: &Addr,
+    offer_asset_info: &AssetInfo,
+    amount: Uint128,
+    max_swap_deviation: Option<Decimal>,
+) -> StdResult<Option<RouteSimulation>> {
+    let route = match SWAP_ROUTES.may_load(deps.storage, offer_asset_info.to_string().as_bytes())? {
+        Some(route) => route,
+        None => return Ok(None),
+    };
+
+    let offer_asset = Asset {
+        info: route.offer_asset_info.clone(),
+        amount: offer_amount(deps, &route.offer_asset_info, amount)?,
+    };
+    let mut asset = offer_asset.clone();
+    let mut spread = Decimal::zero();
+    for (i, hop) in route.hops.iter().enumerate() {
+        if asset.amount.is_zero() {
+            asset.info = hop.ask_asset_info.clone();
+            continue;
+        }
+        let simulation: SimulationResponse = deps.querier.query_wasm_smart(
+            deps.api.addr_humanize(&hop.pair_contract)?,
+            &PairQueryMsg::Simulation {
+                offer_asset: asset.clone(),
+            },
+        )?;
+        spread = spread.max(compute_swap_deviation(
+            simulation.return_amount + simulation.spread_amount,
+            simulation.return_amount,
+        ));
+        // the reward denom received from the last hop stays on the contract
+        let return_amount = if i == route.hops.len() - 1 {
+            simulation.return_amount
+        } else {
+            offer_amount(deps, &hop.ask_asset_info, simulation.return_amount)?
+        };
+        asset = Asset {
+            info: hop.ask_asset_info.clone(),
+            amount: return_amount,
+        };
+    }
+
+    let ask_denom = match asset.info {
+        AssetInfo::NativeToken { denom } => denom,
+        AssetInfo::Token { .. } => {
+            return Err(StdError::generic_err(
+                "swap route must end in a reward denom",
+            ))
+        }
+    };
+    let msgs = if offer_asset.amount.is_zero() {
+        vec![]
+    } else {
+        let hops = humanize_hops(deps, &route.hops)?;
+        swap_route_msgs(contract_addr, &offer_asset, hops, max_swap_deviation)?
+    };
+
+    Ok(Some(RouteSimulation {
+        ask_denom,
+        return_amount: asset.amount,
+        spread,
+        msgs,
+    }))
+}
+
+/// Returns the balances of the tokens that have a route
+pub(crate) fn query_token_balances(deps: Deps, contract_addr: &Addr) -> StdResult<Vec<Asset>> {
+    let mut balances: Vec<Asset> = vec![];
+    for route in read_token_swap_routes(deps.storage)? {
+        let amount = query_asset_balance(deps, contract_addr, &route.offer_asset_info)?;
+        if !amount.is_zero() {
+            balances.push(Asset {
+                info: route.offer_asset_info,
+                amount,
+            });
+        }
+    }
+    Ok(balances)
+}
+
+pub fn query_swap_routes(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SwapRoutesResponse> {
+    let routes = read_swap_routes(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|route| {
+            Ok(SwapRoute {
+                hops: humanize_hops(deps, &route.hops)?,
+                offer_asset_info: route.offer_asset_info,
+            })
+        })
+        .collect::<StdResult<Vec<SwapRoute>>>()?;
+    Ok(SwapRoutesResponse { routes })
+}
+
+/// Returns the message swapping the asset at the first hop, the rest of the hops are executed
+/// by the contract itself after the first one
+fn swap_route_msgs(
+    contract_addr: &Addr,
+    offer_asset: &Asset,
+    mut hops: Vec<SwapHop>,
+    max_spread: Option<Decimal>,
+) -> StdResult<Vec<CosmosMsg<TerraMsgWrapper>>> {
+    let first_hop = hops.remove(0);
+    let swap_msg = match &offer_asset.info {
+        AssetInfo::NativeToken { denom } => WasmMsg::Execute {
+            contract_addr: first_hop.pair_contract,
+            msg: to_binary(&PairExecuteMsg::Swap {
+                offer_asset: offer_asset.clone(),
+                belief_price: None,
+                max_spread,
+                to: None,
+            })?,
+            funds: vec![Coin {
+                denom: denom.clone(),
+                amount: offer_asset.amount,
+            }],
+        },
+        AssetInfo::Token {
+            contract_addr: token_addr,
+        } => WasmMsg::Execute {
+            contract_addr: token_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: first_hop.pair_contract,
+                amount: offer_asset.amount,
+                msg: to_binary(&PairCw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread,
+                    to: None,
+                })?,
+            })?,
+            funds: vec![],
+        },
+    };
+
+    let mut msgs: Vec<CosmosMsg<TerraMsgWrapper>> = vec![swap_msg.into()];
+    if !hops.is_empty() {
+        msgs.push(
+            WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&ExecuteMsg::ExecuteSwapHops {
+                    offer_asset_info: first_hop.ask_asset_info,
+                    hops,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+/// Returns the amount that can be offered out of the balance, the tax of the native coins
+/// is charged on top of the amount sent to the pair
+fn offer_amount(deps: Deps, asset_info: &AssetInfo, balance: Uint128) -> StdResult<Uint128> {
+    match asset_info {
+        AssetInfo::NativeToken { denom } => {
+            Ok(deduct_tax(&deps.querier, Coin::new(balance.u128(), denom.as_str()))?.amount)
+        }
+        AssetInfo::Token { .. } => Ok(balance),
+    }
+}
+
+fn query_asset_balance(
+    deps: Deps,
+    contract_addr: &Addr,
+    asset_info: &AssetInfo,
+) -> StdResult<Uint128> {
+    match asset_info {
+        AssetInfo::NativeToken { denom } => Ok(deps
+            .querier
+            .query_balance(contract_addr, denom.as_str())?
+            .amount),
+        AssetInfo::Token {
+            contract_addr: token_addr,
+        } => {
+            let res: BalanceResponse = deps.querier.query_wasm_smart(
+                token_addr,
+                &Cw20QueryMsg::Balance {
+                    address: contract_addr.to_string(),
+                },
+            )?;
+            Ok(res.balance)
+        }
+    }
+}
+
+fn humanize_hops(deps: Deps, hops: &[SwapHopInfo]) -> StdResult<Vec<SwapHop>> {
+    hops.iter()
+        .map(|hop| {
+            Ok(SwapHop {
+                pair_contract: deps.api.addr_humanize(&hop.pair_contract)?.to_string(),
+                ask_asset_info: hop.ask_asset_info.clone(),
+            })
+        })
+        .collect()
+}
+
+fn validate_asset_info(deps: Deps, asset_info: AssetInfo) -> StdResult<AssetInfo> {
+    match asset_info {
+        AssetInfo::Token { contract_addr } => Ok(AssetInfo::Token {
+            contract_addr: deps.api.addr_validate(&contract_addr)?.to_string(),
+        }),
+        native => Ok(native),
+    }
+}
+
+fn is_reward_denom(config: &Config, asset_info: &AssetInfo) -> bool {
+    match asset_info {
+        AssetInfo::NativeToken { denom } => config
+            .reward_streams()
+            .iter()
+            .any(|stream| stream.denom == *denom),
+        AssetInfo::Token { .. } => false,
+    }
+}
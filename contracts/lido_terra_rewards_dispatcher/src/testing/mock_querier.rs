@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::terraswap::{PairQueryMsg, SimulationResponse};
+use basset::hub::{QueryMsg as HubQueryMsg, StateResponse as HubStateResponse};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier, QuerierResult,
-    QueryRequest, SystemError, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Addr, Coin, ContractResult, Decimal, FullDelegation,
+    OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
 };
+use cw20::{BalanceResponse, Cw20QueryMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use terra_cosmwasm::{
@@ -26,6 +29,11 @@ use terra_cosmwasm::{
 pub const MOCK_HUB_CONTRACT_ADDR: &str = "hub";
 pub const MOCK_BLUNA_REWARD_CONTRACT_ADDR: &str = "reward";
 pub const MOCK_LIDO_FEE_ADDRESS: &str = "lido_fee";
+pub const MOCK_STLUNA_TOTAL_BONDED: u128 = 1_000_000;
+pub const MOCK_BLUNA_TOTAL_BONDED: u128 = 2_000_000;
+pub const MOCK_REWARD_TOKEN_ADDR: &str = "reward_token";
+pub const MOCK_REWARD_TOKEN_BALANCE: u128 = 100;
+pub const MOCK_ILLIQUID_PAIR_ADDR: &str = "pair_illiquid";
 
 pub fn mock_dependencies(
     contract_balance: &[Coin],
@@ -43,6 +51,8 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
+    // the share the market swaps of uluna and uusd lose against the oracle rate
+    swap_spread: Decimal,
 }
 
 impl Querier for WasmMockQuerier {
@@ -138,12 +148,24 @@ impl WasmMockQuerier {
                                     receive: Coin::new(offer_coin.amount.u128() * 2, ask_denom), // 1uusd = 2usdr
                                 })))
                             } else if offer_coin.denom == "uluna" && ask_denom == "uusd" {
+                                let receive = Uint128::from(offer_coin.amount.u128() * 32); //1uluna = 32uusd
                                 QuerierResult::Ok(ContractResult::from(to_binary(&SwapResponse {
-                                    receive: Coin::new(offer_coin.amount.u128() * 32, ask_denom), //1uluna = 32uusd
+                                    receive: Coin {
+                                        amount: receive - receive * self.swap_spread,
+                                        denom: ask_denom.to_string(),
+                                    },
                                 })))
                             } else if offer_coin.denom == "uusd" && ask_denom == "uluna" {
+                                let receive = Uint128::from(offer_coin.amount.u128() / 32); //1uusd = 0.03125uluna
                                 QuerierResult::Ok(ContractResult::from(to_binary(&SwapResponse {
-                                    receive: Coin::new(offer_coin.amount.u128() / 32, ask_denom), //1uusd = 0.03125uluna
+                                    receive: Coin {
+                                        amount: receive - receive * self.swap_spread,
+                                        denom: ask_denom.to_string(),
+                                    },
+                                })))
+                            } else if offer_coin.denom == "uusd" && ask_denom == "ukrw" {
+                                QuerierResult::Ok(ContractResult::from(to_binary(&SwapResponse {
+                                    receive: Coin::new(offer_coin.amount.u128() / 64, ask_denom), // half of the oracle rate
                                 })))
                             } else {
                                 panic!("unknown denom")
@@ -158,6 +180,63 @@ impl WasmMockQuerier {
                     )
                 }
             }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == MOCK_HUB_CONTRACT_ADDR =>
+            {
+                match from_binary(msg).unwrap() {
+                    HubQueryMsg::State {} => {
+                        let res = HubStateResponse {
+                            bluna_exchange_rate: Decimal::one(),
+                            stluna_exchange_rate: Decimal::one(),
+                            total_bond_bluna_amount: Uint128::from(MOCK_BLUNA_TOTAL_BONDED),
+                            total_bond_stluna_amount: Uint128::from(MOCK_STLUNA_TOTAL_BONDED),
+                            last_index_modification: 0,
+                            prev_hub_balance: Uint128::zero(),
+                            last_unbonded_time: 0,
+                            last_processed_batch: 0,
+                            stluna_unreleased_rewards: Uint128::zero(),
+                            total_bond_amount: Uint128::from(MOCK_BLUNA_TOTAL_BONDED),
+                            exchange_rate: Decimal::one(),
+                        };
+                        QuerierResult::Ok(ContractResult::from(to_binary(&res)))
+                    }
+                    _ => unimplemented!(),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr.starts_with("pair") =>
+            {
+                match from_binary(msg).unwrap() {
+                    PairQueryMsg::Simulation { offer_asset } => {
+                        let return_amount = offer_asset.amount.multiply_ratio(2u128, 1u128);
+                        // the illiquid pair loses half of the amount to the spread
+                        let spread_amount = if contract_addr == MOCK_ILLIQUID_PAIR_ADDR {
+                            return_amount
+                        } else {
+                            Uint128::zero()
+                        };
+                        let res = SimulationResponse {
+                            return_amount,
+                            spread_amount,
+                            commission_amount: Uint128::zero(),
+                        };
+                        QuerierResult::Ok(ContractResult::from(to_binary(&res)))
+                    }
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == MOCK_REWARD_TOKEN_ADDR =>
+            {
+                match from_binary(msg).unwrap() {
+                    Cw20QueryMsg::Balance { address: _ } => {
+                        let res = BalanceResponse {
+                            balance: Uint128::from(MOCK_REWARD_TOKEN_BALANCE),
+                        };
+                        QuerierResult::Ok(ContractResult::from(to_binary(&res)))
+                    }
+                    _ => unimplemented!(),
+                }
+            }
             QueryRequest::Wasm(WasmQuery::Raw {
                 contract_addr: _,
                 key: _,
@@ -169,7 +248,29 @@ impl WasmMockQuerier {
 
 impl WasmMockQuerier {
     pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            swap_spread: Decimal::zero(),
+        }
+    }
+
+    pub fn with_swap_spread(&mut self, swap_spread: Decimal) {
+        self.swap_spread = swap_spread;
+    }
+
+    // the rewards accumulated by the delegation of the hub to a validator
+    pub fn with_hub_delegation_rewards(&mut self, rewards: &[Coin]) {
+        self.base.update_staking(
+            "uluna",
+            &[],
+            &[FullDelegation {
+                delegator: Addr::unchecked(MOCK_HUB_CONTRACT_ADDR),
+                validator: String::from("validator"),
+                amount: Coin::new(1000, "uluna"),
+                can_redelegate: Coin::new(0, "uluna"),
+                accumulated_rewards: rewards.to_vec(),
+            }],
+        );
     }
 }
 
@@ -31,15 +31,31 @@
 //!      });
 //! 4. Anywhere you see query(&deps, ...) you must replace it with query(deps.as_mut(), ...)
 
-use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{coins, Api, Coin, Decimal, StdError, Uint128};
+use basset::hub::ExecuteMsg::{BondRewards, BondRewardsForStLuna};
+use basset::reward::ExecuteMsg::UpdateGlobalIndex;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use terra_cosmwasm::{create_swap_msg, TerraMsgWrapper};
 
-use crate::contract::{execute, get_swap_info, instantiate};
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::state::CONFIG;
+use crate::contract::{execute, get_swap_info, instantiate, migrate, query};
+use crate::msg::{
+    AprResponse, BondedToken, BufferedReward, ConfigResponse, DispatchHistoryResponse, ExecuteMsg,
+    FeeRecipient, GetBufferedRewardsResponse, InstantiateMsg, MigrateMsg, NextDispatchResponse,
+    PendingRewardsResponse, QueryMsg, RewardDestination, RewardRateHistoryResponse, RewardShare,
+    RewardStream, SwapHop, SwapRoute, SwapRoutesResponse,
+};
+use crate::state::{
+    DispatchRecord, FeeRecipientInfo, OldConfig, RewardRateRecord, CONFIG, OLD_CONFIG,
+};
+use crate::terraswap::{Asset, AssetInfo, PairCw20HookMsg, PairExecuteMsg};
 use crate::testing::mock_querier::{
-    mock_dependencies, MOCK_BLUNA_REWARD_CONTRACT_ADDR, MOCK_HUB_CONTRACT_ADDR,
-    MOCK_LIDO_FEE_ADDRESS,
+    mock_dependencies, MOCK_BLUNA_REWARD_CONTRACT_ADDR, MOCK_BLUNA_TOTAL_BONDED,
+    MOCK_HUB_CONTRACT_ADDR, MOCK_ILLIQUID_PAIR_ADDR, MOCK_LIDO_FEE_ADDRESS, MOCK_REWARD_TOKEN_ADDR,
+    MOCK_REWARD_TOKEN_BALANCE, MOCK_STLUNA_TOTAL_BONDED,
 };
 
 fn default_init() -> InstantiateMsg {
@@ -48,8 +64,13 @@ fn default_init() -> InstantiateMsg {
         bluna_reward_contract: String::from(MOCK_BLUNA_REWARD_CONTRACT_ADDR),
         bluna_reward_denom: "uusd".to_string(),
         stluna_reward_denom: "uluna".to_string(),
-        lido_fee_address: String::from(MOCK_LIDO_FEE_ADDRESS),
-        lido_fee_rate: Decimal::from_ratio(Uint128::from(5u64), Uint128::from(100u64)),
+        lido_fee_recipients: vec![FeeRecipient {
+            address: String::from(MOCK_LIDO_FEE_ADDRESS),
+            share: Decimal::one(),
+        }],
+        stluna_fee_rate: Decimal::from_ratio(Uint128::from(5u64), Uint128::from(100u64)),
+        bluna_fee_rate: Decimal::from_ratio(Uint128::from(5u64), Uint128::from(100u64)),
+        max_swap_deviation: None,
     }
 }
 
@@ -88,8 +109,8 @@ fn test_swap_to_reward_denom() {
             ],
             stluna_total_bonded: Uint128::from(1u128),
             bluna_total_bonded: Uint128::from(2u128),
-            expected_total_luna_rewards_available: "200".to_string(),
-            expected_total_ust_rewards_available: "1300".to_string(),
+            expected_total_luna_rewards_available: "200uluna".to_string(),
+            expected_total_ust_rewards_available: "1300uusd".to_string(),
             expected_offer_coin_denom: "uluna".to_string(),
             expected_offer_coin_amount: "120".to_string(),
             expected_ask_denom: "uusd".to_string(),
@@ -103,8 +124,8 @@ fn test_swap_to_reward_denom() {
             ],
             stluna_total_bonded: Uint128::from(2u128),
             bluna_total_bonded: Uint128::from(2u128),
-            expected_total_luna_rewards_available: "200".to_string(),
-            expected_total_ust_rewards_available: "1300".to_string(),
+            expected_total_luna_rewards_available: "200uluna".to_string(),
+            expected_total_ust_rewards_available: "1300uusd".to_string(),
             expected_offer_coin_denom: "uluna".to_string(),
             expected_offer_coin_amount: "80".to_string(),
             expected_ask_denom: "uusd".to_string(),
@@ -118,8 +139,8 @@ fn test_swap_to_reward_denom() {
             ],
             stluna_total_bonded: Uint128::from(2u128),
             bluna_total_bonded: Uint128::from(1u128),
-            expected_total_luna_rewards_available: "200".to_string(),
-            expected_total_ust_rewards_available: "1300".to_string(),
+            expected_total_luna_rewards_available: "200uluna".to_string(),
+            expected_total_ust_rewards_available: "1300uusd".to_string(),
             expected_offer_coin_denom: "uluna".to_string(),
             expected_offer_coin_amount: "40".to_string(),
             expected_ask_denom: "uusd".to_string(),
@@ -133,8 +154,8 @@ fn test_swap_to_reward_denom() {
             ],
             stluna_total_bonded: Uint128::from(2u128),
             bluna_total_bonded: Uint128::from(2u128),
-            expected_total_luna_rewards_available: "0".to_string(),
-            expected_total_ust_rewards_available: "1300".to_string(),
+            expected_total_luna_rewards_available: "0uluna".to_string(),
+            expected_total_ust_rewards_available: "1300uusd".to_string(),
             expected_offer_coin_denom: "uusd".to_string(),
             expected_offer_coin_amount: "640".to_string(),
             expected_ask_denom: "uluna".to_string(),
@@ -160,10 +181,10 @@ fn test_swap_to_reward_denom() {
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         for attr in res.attributes {
-            if attr.key == *"total_luna_rewards_available" {
+            if attr.key == *"total_stluna_rewards_available" {
                 assert_eq!(attr.value, test_case.expected_total_luna_rewards_available)
             }
-            if attr.key == *"total_ust_rewards_available" {
+            if attr.key == *"total_bluna_rewards_available" {
                 assert_eq!(attr.value, test_case.expected_total_ust_rewards_available)
             }
             if attr.key == *"offer_coin_denom" {
@@ -179,6 +200,191 @@ fn test_swap_to_reward_denom() {
     }
 }
 
+fn set_max_swap_deviation(
+    deps: DepsMut,
+    max_swap_deviation: Decimal,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let owner = mock_info("creator", &[]);
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: Some(max_swap_deviation),
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
+    };
+    execute(deps, mock_env(), owner, update_config_msg)
+}
+
+#[test]
+fn max_swap_deviation_config() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("creator", &[]);
+
+    let mut msg = default_init();
+    msg.max_swap_deviation = Some(Decimal::percent(101));
+    let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("max swap deviation can't be greater than 1")
+    );
+
+    msg.max_swap_deviation = Some(Decimal::percent(10));
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_swap_deviation, Some(Decimal::percent(10)));
+
+    let mut update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: Some(Decimal::percent(5)),
+        clear_max_swap_deviation: Some(true),
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        update_config_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("max swap deviation can't be set and cleared at once")
+    );
+
+    if let ExecuteMsg::UpdateConfig {
+        max_swap_deviation, ..
+    } = &mut update_config_msg
+    {
+        *max_swap_deviation = None;
+    }
+    execute(deps.as_mut(), mock_env(), info, update_config_msg).unwrap();
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_swap_deviation, None);
+}
+
+#[test]
+fn test_swap_max_deviation() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(300, "uusd"),
+        Coin::new(500, "usdr"),
+    ]);
+
+    let msg = default_init();
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let err = set_max_swap_deviation(deps.as_mut(), Decimal::percent(101)).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("max swap deviation can't be greater than 1")
+    );
+
+    // the market swap of usdr returns 1000uusd instead of the 16000uusd at the oracle rate
+    set_max_swap_deviation(deps.as_mut(), Decimal::percent(10)).unwrap();
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_swap_deviation, Some(Decimal::percent(10)));
+
+    let hub_info = mock_info(String::from(MOCK_HUB_CONTRACT_ADDR).as_str(), &[]);
+    let swap_msg = ExecuteMsg::SwapToRewardDenom {
+        stluna_total_bonded: Uint128::from(1u128),
+        bluna_total_bonded: Uint128::from(2u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    // the usdr stays in the buffer, only the rebalancing swap is sent
+    assert_eq!(1, res.messages.len());
+    assert_eq!(
+        res.messages[0].msg,
+        create_swap_msg(Coin::new(131, "uluna"), "uusd".to_string())
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("total_bluna_rewards_available", "300uusd")));
+    assert!(res.attributes.contains(&attr("skipped_swap", "500usdr")));
+    assert!(res
+        .attributes
+        .contains(&attr("skipped_swap_deviation", "0.9375")));
+    assert!(res.attributes.contains(&attr("swap_deviation", "0")));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    // the rebalancing swap loses 20%, so the dispatch is deferred
+    deps.querier.with_swap_spread(Decimal::percent(20));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res
+        .attributes
+        .contains(&attr("swap_deviation", "0.199904580152671755")));
+    assert!(res.attributes.contains(&attr("dispatch_deferred", "true")));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.contains(&attr("dispatch_deferred", "true")));
+
+    // the rewards are dispatched as soon as the swap is back within the limit
+    deps.querier.with_swap_spread(Decimal::percent(5));
+    let res = execute(deps.as_mut(), mock_env(), hub_info.clone(), swap_msg).unwrap();
+    assert_eq!(1, res.messages.len());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+}
+
 #[test]
 fn test_dispatch_rewards() {
     let mut deps = mock_dependencies(&[
@@ -229,8 +435,13 @@ fn test_dispatch_rewards_zero_lido_fee() {
         bluna_reward_contract: String::from(MOCK_BLUNA_REWARD_CONTRACT_ADDR),
         bluna_reward_denom: "uusd".to_string(),
         stluna_reward_denom: "uluna".to_string(),
-        lido_fee_address: String::from(MOCK_LIDO_FEE_ADDRESS),
-        lido_fee_rate: Decimal::zero(),
+        lido_fee_recipients: vec![FeeRecipient {
+            address: String::from(MOCK_LIDO_FEE_ADDRESS),
+            share: Decimal::one(),
+        }],
+        stluna_fee_rate: Decimal::zero(),
+        bluna_fee_rate: Decimal::zero(),
+        max_swap_deviation: None,
     };
     let info = mock_info("creator", &[]);
 
@@ -254,6 +465,236 @@ fn test_dispatch_rewards_zero_lido_fee() {
     }
 }
 
+#[test]
+fn test_get_buffered_rewards() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(500, "usdr"),
+        Coin::new(100, "mnt"),
+    ]);
+
+    let msg = default_init();
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res: GetBufferedRewardsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetBufferedRewards {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        GetBufferedRewardsResponse {
+            stluna_rewards: Coin::new(200, "uluna"),
+            bluna_rewards: Coin::new(0, "uusd"),
+            // mnt can't be swapped, so it's not a reward
+            other_rewards: vec![BufferedReward {
+                amount: Coin::new(500, "usdr"),
+                value: Coin::new(16000, "uusd"), // 1uusd = 0.03125usdr in the mock oracle
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_pending_rewards() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(300, "uusd"),
+        Coin::new(100, "mnt"),
+    ]);
+    deps.querier
+        .with_hub_delegation_rewards(&[Coin::new(100, "uluna"), Coin::new(64, "uusd")]);
+
+    let msg = default_init();
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // total rewards = 300uluna + 364uusd = 311uluna, stluna target = 311 * 1/3 = 103uluna,
+    // the rest 197uluna are swapped to 6304uusd, the fee is 5% of each
+    let res: PendingRewardsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingRewards {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        PendingRewardsResponse {
+            stluna_rewards: Coin::new(98, "uluna"),
+            bluna_rewards: Coin::new(6335, "uusd"),
+            other_rewards: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_dispatch_history() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+
+    let msg = default_init();
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res: DispatchHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DispatchHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.history.is_empty());
+
+    let info = mock_info(String::from(MOCK_HUB_CONTRACT_ADDR).as_str(), &[]);
+    let mut env = mock_env();
+    for _ in 0..3 {
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::DispatchRewards {},
+        )
+        .unwrap();
+    }
+
+    let res: DispatchHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DispatchHistory {
+                start_after: Some(1),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.history,
+        vec![DispatchRecord {
+            id: 2,
+            height: mock_env().block.height + 2,
+            time: mock_env().block.time.plus_seconds(120).seconds(),
+            hub_rewards: Coin::new(190, "uluna"),
+            bluna_rewards: Coin::new(282, "uusd"),
+            lido_fees: vec![Coin::new(10, "uluna"), Coin::new(14, "uusd")],
+            other_rewards: vec![],
+        }]
+    );
+
+    let res: DispatchHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DispatchHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.history.iter().map(|r| r.id).collect::<Vec<u64>>(),
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+fn test_apr() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+
+    let msg = default_init();
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let day: u64 = 24 * 60 * 60;
+    let info = mock_info(String::from(MOCK_HUB_CONTRACT_ADDR).as_str(), &[]);
+    let mut env = mock_env();
+    for _ in 0..3 {
+        env.block.time = env.block.time.plus_seconds(day);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::DispatchRewards {},
+        )
+        .unwrap();
+    }
+
+    let res: RewardRateHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RewardRateHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.history.len(), 3);
+    // there is nothing to compare the first dispatch with
+    assert_eq!(res.history[0].elapsed, 0);
+    assert_eq!(
+        res.history[2],
+        RewardRateRecord {
+            id: 3,
+            time: env.block.time.seconds(),
+            elapsed: day,
+            stluna_rewards: Uint128::from(190u64),
+            bluna_rewards: Uint128::from(282u64),
+            bluna_rewards_value: Uint128::from(8u64), // 1uluna = 32uusd
+            stluna_total_bonded: Uint128::from(MOCK_STLUNA_TOTAL_BONDED),
+            bluna_total_bonded: Uint128::from(MOCK_BLUNA_TOTAL_BONDED),
+        }
+    );
+
+    // 190uluna a day for 1_000_000uluna bonded and 8uluna a day for 2_000_000uluna bonded
+    let expected = AprResponse {
+        elapsed: 2 * day,
+        stluna_apr: Decimal::from_ratio(6935u64, 100_000u64),
+        bluna_apr: Decimal::from_ratio(146u64, 100_000u64),
+    };
+    let res: AprResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Apr { window: 7 * day },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, expected);
+
+    let res: AprResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Apr { window: day }).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        AprResponse {
+            elapsed: day,
+            ..expected
+        }
+    );
+
+    // no dispatches within the window
+    env.block.time = env.block.time.plus_seconds(2 * day);
+    let res: AprResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::Apr { window: day }).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        AprResponse {
+            elapsed: 0,
+            stluna_apr: Decimal::zero(),
+            bluna_apr: Decimal::zero(),
+        }
+    );
+}
+
 #[test]
 fn test_get_swap_info() {
     let mut deps = mock_dependencies(&[]);
@@ -276,15 +717,20 @@ fn test_get_swap_info() {
     let stluna_2_bluna_rewards_xchg_rate =
         Decimal::from_ratio(Uint128::from(1u64), Uint128::from(1u64));
     let (offer_coin, _) = get_swap_info(
-        config.clone(),
+        &config.reward_streams(),
         stluna_total_bond_amount,
         bluna_total_bond_amount,
-        total_luna_rewards_available,
-        total_ust_rewards_available,
-        bluna_2_stluna_rewards_xchg_rate,
-        stluna_2_bluna_rewards_xchg_rate,
+        &[total_luna_rewards_available, total_ust_rewards_available],
+        &[
+            (Decimal::one(), Decimal::one()),
+            (
+                stluna_2_bluna_rewards_xchg_rate,
+                bluna_2_stluna_rewards_xchg_rate,
+            ),
+        ],
     )
-    .unwrap();
+    .unwrap()[0]
+        .clone();
     assert_eq!(offer_coin.denom, config.bluna_reward_denom);
     assert_eq!(offer_coin.amount, Uint128::zero());
 
@@ -297,15 +743,20 @@ fn test_get_swap_info() {
     let stluna_2_bluna_rewards_xchg_rate =
         Decimal::from_ratio(Uint128::from(10u64), Uint128::from(15u64));
     let (offer_coin, _) = get_swap_info(
-        config.clone(),
+        &config.reward_streams(),
         stluna_total_bond_amount,
         bluna_total_bond_amount,
-        total_luna_rewards_available,
-        total_ust_rewards_available,
-        bluna_2_stluna_rewards_xchg_rate,
-        stluna_2_bluna_rewards_xchg_rate,
+        &[total_luna_rewards_available, total_ust_rewards_available],
+        &[
+            (Decimal::one(), Decimal::one()),
+            (
+                stluna_2_bluna_rewards_xchg_rate,
+                bluna_2_stluna_rewards_xchg_rate,
+            ),
+        ],
     )
-    .unwrap();
+    .unwrap()[0]
+        .clone();
     assert_eq!(offer_coin.denom, config.bluna_reward_denom);
     assert_eq!(offer_coin.amount, Uint128::from(3u64));
 
@@ -318,15 +769,20 @@ fn test_get_swap_info() {
     let stluna_2_bluna_rewards_xchg_rate =
         Decimal::from_ratio(Uint128::from(100u64), Uint128::from(75u64));
     let (offer_coin, _) = get_swap_info(
-        config.clone(),
+        &config.reward_streams(),
         stluna_total_bond_amount,
         bluna_total_bond_amount,
-        total_luna_rewards_available,
-        total_ust_rewards_available,
-        bluna_2_stluna_rewards_xchg_rate,
-        stluna_2_bluna_rewards_xchg_rate,
+        &[total_luna_rewards_available, total_ust_rewards_available],
+        &[
+            (Decimal::one(), Decimal::one()),
+            (
+                stluna_2_bluna_rewards_xchg_rate,
+                bluna_2_stluna_rewards_xchg_rate,
+            ),
+        ],
     )
-    .unwrap();
+    .unwrap()[0]
+        .clone();
     assert_eq!(offer_coin.denom, config.stluna_reward_denom);
     assert_eq!(offer_coin.amount, Uint128::from(3u64));
 }
@@ -351,8 +807,16 @@ fn test_update_config() {
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&invalid_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -366,8 +830,16 @@ fn test_update_config() {
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -384,8 +856,16 @@ fn test_update_config() {
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -406,8 +886,16 @@ fn test_update_config() {
         bluna_reward_contract: Some(String::from("some_address")),
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -428,8 +916,16 @@ fn test_update_config() {
         bluna_reward_contract: None,
         stluna_reward_denom: Some(String::from("new_denom")),
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -451,8 +947,16 @@ fn test_update_config() {
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: Some(String::from("new_denom")),
-        lido_fee_address: None,
-        lido_fee_rate: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -467,15 +971,32 @@ fn test_update_config() {
     let config = CONFIG.load(&deps.storage).unwrap();
     assert_eq!(String::from("uusd"), config.bluna_reward_denom);
 
-    // change lido_fee_address
+    // change lido_fee_recipients
     let update_config_msg = ExecuteMsg::UpdateConfig {
         owner: None,
         hub_contract: None,
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: Some(String::from("some_address")),
-        lido_fee_rate: None,
+        lido_fee_recipients: Some(vec![
+            FeeRecipient {
+                address: String::from("some_address"),
+                share: Decimal::percent(70),
+            },
+            FeeRecipient {
+                address: String::from("other_address"),
+                share: Decimal::percent(30),
+            },
+        ]),
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
@@ -483,26 +1004,1310 @@ fn test_update_config() {
 
     let config = CONFIG.load(&deps.storage).unwrap();
     assert_eq!(
-        deps.api
-            .addr_canonicalize(&String::from("some_address"))
-            .unwrap(),
-        config.lido_fee_address
+        vec![
+            FeeRecipientInfo {
+                address: deps
+                    .api
+                    .addr_canonicalize(&String::from("some_address"))
+                    .unwrap(),
+                share: Decimal::percent(70),
+            },
+            FeeRecipientInfo {
+                address: deps
+                    .api
+                    .addr_canonicalize(&String::from("other_address"))
+                    .unwrap(),
+                share: Decimal::percent(30),
+            }
+        ],
+        config.lido_fee_recipients
     );
 
-    // change lido_fee_rate
+    // the shares must sum up to one
+    for (recipients, error) in [
+        (vec![], "fee recipients list is empty"),
+        (
+            vec![
+                ("some_address", Decimal::percent(70)),
+                ("other_address", Decimal::percent(20)),
+            ],
+            "fee shares must sum up to one",
+        ),
+        (
+            vec![
+                ("some_address", Decimal::one()),
+                ("other_address", Decimal::zero()),
+            ],
+            "fee share of other_address is zero",
+        ),
+        (
+            vec![
+                ("some_address", Decimal::percent(50)),
+                ("some_address", Decimal::percent(50)),
+            ],
+            "duplicate fee recipient some_address",
+        ),
+    ] {
+        let update_config_msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            hub_contract: None,
+            bluna_reward_contract: None,
+            stluna_reward_denom: None,
+            bluna_reward_denom: None,
+            lido_fee_recipients: Some(
+                recipients
+                    .into_iter()
+                    .map(|(address, share)| FeeRecipient {
+                        address: String::from(address),
+                        share,
+                    })
+                    .collect(),
+            ),
+            stluna_fee_rate: None,
+            bluna_fee_rate: None,
+            lido_fee_in_stluna: None,
+            max_swap_deviation: None,
+            clear_max_swap_deviation: None,
+            min_dispatch_amounts: None,
+            max_dispatch_interval: None,
+            rewards_release_period: None,
+            reward_streams: None,
+        };
+        let info = mock_info(&new_owner, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
+        assert_eq!(res.unwrap_err(), StdError::generic_err(error));
+    }
+
+    // change stluna_fee_rate and bluna_fee_rate
     let update_config_msg = ExecuteMsg::UpdateConfig {
         owner: None,
         hub_contract: None,
         bluna_reward_contract: None,
         stluna_reward_denom: None,
         bluna_reward_denom: None,
-        lido_fee_address: None,
-        lido_fee_rate: Some(Decimal::one()),
+        lido_fee_recipients: None,
+        stluna_fee_rate: Some(Decimal::percent(10)),
+        bluna_fee_rate: Some(Decimal::percent(20)),
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
     };
     let info = mock_info(&new_owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
     assert!(res.is_ok());
 
     let config = CONFIG.load(&deps.storage).unwrap();
-    assert_eq!(Decimal::one(), config.lido_fee_rate);
+    assert_eq!(Decimal::percent(10), config.stluna_fee_rate);
+    assert_eq!(Decimal::percent(20), config.bluna_fee_rate);
+
+    // the fee rate can't exceed the maximum
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: Some(Decimal::one()),
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
+    };
+    let info = mock_info(&new_owner, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, update_config_msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("fee rate can't be greater than 0.2")
+    );
+}
+
+#[test]
+fn test_dispatch_rewards_multiple_fee_recipients() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(3000, "uusd")]);
+
+    let mut msg = default_init();
+    msg.lido_fee_recipients = vec![
+        FeeRecipient {
+            address: String::from("treasury"),
+            share: Decimal::percent(50),
+        },
+        FeeRecipient {
+            address: String::from("operators"),
+            share: Decimal::percent(30),
+        },
+        FeeRecipient {
+            address: String::from("insurance"),
+            share: Decimal::percent(20),
+        },
+    ];
+    msg.stluna_fee_rate = Decimal::percent(10);
+    msg.bluna_fee_rate = Decimal::percent(2);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info(String::from(MOCK_HUB_CONTRACT_ADDR).as_str(), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(6, res.messages.len());
+
+    // 20uluna and 60uusd of the fee, the tax is 1% for uusd
+    let fee_messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![
+        BankMsg::Send {
+            to_address: String::from("treasury"),
+            amount: vec![Coin::new(10, "uluna"), Coin::new(29, "uusd")],
+        }
+        .into(),
+        BankMsg::Send {
+            to_address: String::from("operators"),
+            amount: vec![Coin::new(6, "uluna"), Coin::new(17, "uusd")],
+        }
+        .into(),
+        BankMsg::Send {
+            to_address: String::from("insurance"),
+            amount: vec![Coin::new(4, "uluna"), Coin::new(11, "uusd")],
+        }
+        .into(),
+    ];
+    assert_eq!(
+        res.messages[1..4]
+            .iter()
+            .map(|m| m.msg.clone())
+            .collect::<Vec<CosmosMsg<TerraMsgWrapper>>>(),
+        fee_messages
+    );
+
+    for attr in res.attributes {
+        if attr.key == "lido_stluna_fee" {
+            assert_eq!("20uluna", attr.value)
+        }
+        if attr.key == "lido_bluna_fee" {
+            assert_eq!("57uusd", attr.value)
+        }
+    }
+}
+
+#[test]
+fn test_dispatch_rewards_fee_in_stluna() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+
+    let owner = String::from("creator");
+    let msg = default_init();
+    let info = mock_info(&owner, &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: Some(true),
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: None,
+    };
+    let info = mock_info(&owner, &[]);
+    execute(deps.as_mut(), mock_env(), info, update_config_msg).unwrap();
+
+    let info = mock_info(String::from(MOCK_HUB_CONTRACT_ADDR).as_str(), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(5, res.messages.len());
+
+    // the Luna fee is bonded right after the rewards, the UST fee is sent as usual
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from(MOCK_HUB_CONTRACT_ADDR),
+            msg: to_binary(&BondRewardsForStLuna {
+                recipient: String::from(MOCK_LIDO_FEE_ADDRESS),
+            })
+            .unwrap(),
+            funds: vec![Coin::new(10, "uluna")],
+        })
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        BankMsg::Send {
+            to_address: String::from(MOCK_LIDO_FEE_ADDRESS),
+            amount: vec![Coin::new(14, "uusd")],
+        }
+        .into()
+    );
+}
+
+#[test]
+fn test_migrate_fee_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let old_config = OldConfig {
+        owner: deps.api.addr_canonicalize("owner").unwrap(),
+        hub_contract: deps.api.addr_canonicalize(MOCK_HUB_CONTRACT_ADDR).unwrap(),
+        bluna_reward_contract: deps
+            .api
+            .addr_canonicalize(MOCK_BLUNA_REWARD_CONTRACT_ADDR)
+            .unwrap(),
+        stluna_reward_denom: "uluna".to_string(),
+        bluna_reward_denom: "uusd".to_string(),
+        lido_fee_address: deps.api.addr_canonicalize(MOCK_LIDO_FEE_ADDRESS).unwrap(),
+        lido_fee_rate: Decimal::percent(5),
+    };
+    OLD_CONFIG.save(&mut deps.storage, &old_config).unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.lido_fee_recipients,
+        vec![FeeRecipient {
+            address: String::from(MOCK_LIDO_FEE_ADDRESS),
+            share: Decimal::one(),
+        }]
+    );
+    assert_eq!(config.stluna_fee_rate, Decimal::percent(5));
+    assert_eq!(config.bluna_fee_rate, Decimal::percent(5));
+
+    // the second migration keeps the config
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+}
+
+fn native_asset_info(denom: &str) -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    }
+}
+
+fn set_swap_route(
+    deps: DepsMut,
+    sender: &str,
+    offer_asset_info: AssetInfo,
+    hops: Vec<(&str, AssetInfo)>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let msg = ExecuteMsg::SetSwapRoute {
+        offer_asset_info,
+        hops: hops
+            .into_iter()
+            .map(|(pair_contract, ask_asset_info)| SwapHop {
+                pair_contract: pair_contract.to_string(),
+                ask_asset_info,
+            })
+            .collect(),
+    };
+    execute(deps, mock_env(), mock_info(sender, &[]), msg)
+}
+
+#[test]
+fn test_set_swap_route() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    let reward_token = AssetInfo::Token {
+        contract_addr: MOCK_REWARD_TOKEN_ADDR.to_string(),
+    };
+    let err = set_swap_route(
+        deps.as_mut(),
+        "stranger",
+        native_asset_info("mnt"),
+        vec![("pair_mnt_uusd", native_asset_info("uusd"))],
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+
+    let err =
+        set_swap_route(deps.as_mut(), "creator", native_asset_info("mnt"), vec![]).unwrap_err();
+    assert_eq!(err, StdError::generic_err("swap route is empty"));
+
+    let err = set_swap_route(
+        deps.as_mut(),
+        "creator",
+        native_asset_info("uluna"),
+        vec![("pair_uluna_uusd", native_asset_info("uusd"))],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("can't set a swap route for a reward denom")
+    );
+
+    let err = set_swap_route(
+        deps.as_mut(),
+        "creator",
+        reward_token.clone(),
+        vec![("pair_token_mnt", native_asset_info("mnt"))],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("swap route must end in a reward denom")
+    );
+
+    let err = set_swap_route(
+        deps.as_mut(),
+        "creator",
+        reward_token.clone(),
+        vec![
+            ("pair_token_uusd", native_asset_info("uusd")),
+            ("pair_uusd_uluna", native_asset_info("uluna")),
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("swap route can't pass through a reward denom")
+    );
+
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        native_asset_info("mnt"),
+        vec![("pair_mnt_uusd", native_asset_info("uusd"))],
+    )
+    .unwrap();
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        reward_token.clone(),
+        vec![
+            ("pair_token_mnt", native_asset_info("mnt")),
+            ("pair_mnt_uusd", native_asset_info("uusd")),
+        ],
+    )
+    .unwrap();
+
+    let routes: SwapRoutesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SwapRoutes {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        routes.routes,
+        vec![
+            SwapRoute {
+                offer_asset_info: native_asset_info("mnt"),
+                hops: vec![SwapHop {
+                    pair_contract: "pair_mnt_uusd".to_string(),
+                    ask_asset_info: native_asset_info("uusd"),
+                }],
+            },
+            SwapRoute {
+                offer_asset_info: reward_token.clone(),
+                hops: vec![
+                    SwapHop {
+                        pair_contract: "pair_token_mnt".to_string(),
+                        ask_asset_info: native_asset_info("mnt"),
+                    },
+                    SwapHop {
+                        pair_contract: "pair_mnt_uusd".to_string(),
+                        ask_asset_info: native_asset_info("uusd"),
+                    },
+                ],
+            },
+        ]
+    );
+
+    let remove_msg = ExecuteMsg::RemoveSwapRoute {
+        offer_asset_info: native_asset_info("mnt"),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        remove_msg.clone(),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        remove_msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("no swap route for mnt"));
+
+    let routes: SwapRoutesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SwapRoutes {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(routes.routes.len(), 1);
+    assert_eq!(routes.routes[0].offer_asset_info, reward_token);
+}
+
+#[test]
+fn test_swap_to_reward_denom_with_routes() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(300, "uusd"),
+        Coin::new(100, "mnt"),
+    ]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    let reward_token = AssetInfo::Token {
+        contract_addr: MOCK_REWARD_TOKEN_ADDR.to_string(),
+    };
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        native_asset_info("mnt"),
+        vec![("pair_mnt_uusd", native_asset_info("uusd"))],
+    )
+    .unwrap();
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        reward_token,
+        vec![
+            ("pair_token_mnt", native_asset_info("mnt")),
+            ("pair_mnt_uusd", native_asset_info("uusd")),
+        ],
+    )
+    .unwrap();
+
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let swap_msg = ExecuteMsg::SwapToRewardDenom {
+        stluna_total_bonded: Uint128::from(1u128),
+        bluna_total_bonded: Uint128::from(2u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+    // the tax of 1mnt is paid on top of the amount sent to the pair
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "pair_mnt_uusd".to_string(),
+            msg: to_binary(&PairExecuteMsg::Swap {
+                offer_asset: Asset {
+                    info: native_asset_info("mnt"),
+                    amount: Uint128::from(99u128),
+                },
+                belief_price: None,
+                max_spread: None,
+                to: None,
+            })
+            .unwrap(),
+            funds: vec![Coin::new(99, "mnt")],
+        })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_REWARD_TOKEN_ADDR.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: "pair_token_mnt".to_string(),
+                amount: Uint128::from(MOCK_REWARD_TOKEN_BALANCE),
+                msg: to_binary(&PairCw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                })
+                .unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&ExecuteMsg::ExecuteSwapHops {
+                offer_asset_info: native_asset_info("mnt"),
+                hops: vec![SwapHop {
+                    pair_contract: "pair_mnt_uusd".to_string(),
+                    ask_asset_info: native_asset_info("uusd"),
+                }],
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    // 300uusd + 198uusd for 99mnt + 396uusd for 198mnt received for 100 tokens
+    assert!(res
+        .attributes
+        .contains(&attr("total_bluna_rewards_available", "894uusd")));
+
+    // the spread of the illiquid pair exceeds the limit, the mnt stays in the buffer
+    set_max_swap_deviation(deps.as_mut(), Decimal::percent(10)).unwrap();
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        native_asset_info("mnt"),
+        vec![(MOCK_ILLIQUID_PAIR_ADDR, native_asset_info("uusd"))],
+    )
+    .unwrap();
+    let res = execute(deps.as_mut(), mock_env(), hub_info, swap_msg).unwrap();
+    assert_eq!(3, res.messages.len());
+    assert!(res.attributes.contains(&attr("skipped_swap", "100mnt")));
+    assert!(res
+        .attributes
+        .contains(&attr("skipped_swap_deviation", "0.5")));
+    assert!(res
+        .attributes
+        .contains(&attr("total_bluna_rewards_available", "696uusd")));
+}
+
+#[test]
+fn test_receive_and_swap_hops() {
+    let mut deps = mock_dependencies(&[Coin::new(100, "mnt")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        AssetInfo::Token {
+            contract_addr: MOCK_REWARD_TOKEN_ADDR.to_string(),
+        },
+        vec![
+            ("pair_token_mnt", native_asset_info("mnt")),
+            ("pair_mnt_uusd", native_asset_info("uusd")),
+        ],
+    )
+    .unwrap();
+
+    let receive_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "integration".to_string(),
+        amount: Uint128::from(50u128),
+        msg: to_binary(&{}).unwrap(),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stray_token", &[]),
+        receive_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("no swap route for token stray_token")
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_REWARD_TOKEN_ADDR, &[]),
+        receive_msg,
+    )
+    .unwrap();
+    assert_eq!(2, res.messages.len());
+    assert!(res
+        .attributes
+        .contains(&attr("routed_swap", "50reward_token")));
+
+    let swap_hops_msg = ExecuteMsg::ExecuteSwapHops {
+        offer_asset_info: native_asset_info("mnt"),
+        hops: vec![SwapHop {
+            pair_contract: "pair_mnt_uusd".to_string(),
+            ask_asset_info: native_asset_info("uusd"),
+        }],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        swap_hops_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+
+    // the whole mnt balance is swapped at the next hop
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        swap_hops_msg,
+    )
+    .unwrap();
+    assert_eq!(1, res.messages.len());
+    assert!(res.attributes.contains(&attr("routed_swap", "99mnt")));
+}
+
+fn set_dispatch_thresholds(
+    deps: DepsMut,
+    min_dispatch_amounts: Vec<Coin>,
+    max_dispatch_interval: u64,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let owner = mock_info("creator", &[]);
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: Some(min_dispatch_amounts),
+        max_dispatch_interval: Some(max_dispatch_interval),
+        rewards_release_period: None,
+        reward_streams: None,
+    };
+    execute(deps, mock_env(), owner, update_config_msg)
+}
+
+#[test]
+fn test_dispatch_thresholds() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    let err =
+        set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(1000, "mnt")], 3600).unwrap_err();
+    assert_eq!(err, StdError::generic_err("mnt is not a reward denom"));
+    let err = set_dispatch_thresholds(
+        deps.as_mut(),
+        vec![Coin::new(1000, "uluna"), Coin::new(2000, "uluna")],
+        3600,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("duplicate minimum dispatch amount of uluna")
+    );
+
+    set_dispatch_thresholds(
+        deps.as_mut(),
+        vec![Coin::new(1000, "uluna"), Coin::new(1000, "uusd")],
+        3600,
+    )
+    .unwrap();
+
+    // the first dispatch isn't postponed
+    let mut env = mock_env();
+    let dispatch_time = env.block.time.seconds();
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    // the balances are below the minimums till max_dispatch_interval passes
+    env.block.time = env.block.time.plus_seconds(10);
+    let next_dispatch: NextDispatchResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::NextDispatch {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        next_dispatch,
+        NextDispatchResponse {
+            stluna_rewards: Coin::new(200, "uluna"),
+            bluna_rewards: Coin::new(300, "uusd"),
+            min_dispatch_amounts: vec![Coin::new(1000, "uluna"), Coin::new(1000, "uusd")],
+            last_dispatch_time: Some(dispatch_time),
+            next_dispatch_time: Some(dispatch_time + 3600),
+            dispatch_due: false,
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.contains(&attr("dispatch_postponed", "true")));
+
+    env.block.time = env.block.time.plus_seconds(3590);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    // the stLuna rewards reach their minimum
+    set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(100, "uluna")], 0).unwrap();
+    env.block.time = env.block.time.plus_seconds(10);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    let next_dispatch: NextDispatchResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::NextDispatch {}).unwrap()).unwrap();
+    assert_eq!(next_dispatch.next_dispatch_time, None);
+    assert!(next_dispatch.dispatch_due);
+}
+
+#[test]
+fn test_dispatch_interval_only() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(300, "uusd"),
+        Coin::new(500, "usdr"),
+    ]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    set_dispatch_thresholds(deps.as_mut(), vec![], 3600).unwrap();
+
+    let mut env = mock_env();
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let swap_msg = ExecuteMsg::SwapToRewardDenom {
+        stluna_total_bonded: Uint128::from(1u128),
+        bluna_total_bonded: Uint128::from(2u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(2, res.messages.len());
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    // neither the swaps nor the dispatch happen till max_dispatch_interval passes
+    env.block.time = env.block.time.plus_seconds(10);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.contains(&attr("dispatch_postponed", "true")));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        hub_info.clone(),
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.contains(&attr("dispatch_postponed", "true")));
+
+    env.block.time = env.block.time.plus_seconds(3590);
+    let res = execute(deps.as_mut(), env.clone(), hub_info.clone(), swap_msg).unwrap();
+    assert_eq!(2, res.messages.len());
+    let res = execute(deps.as_mut(), env, hub_info, ExecuteMsg::DispatchRewards {}).unwrap();
+    assert_eq!(4, res.messages.len());
+}
+
+#[test]
+fn test_swap_dispatch_thresholds() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(200, "uluna"),
+        Coin::new(300, "uusd"),
+        Coin::new(500, "usdr"),
+    ]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let swap_msg = ExecuteMsg::SwapToRewardDenom {
+        stluna_total_bonded: Uint128::from(1u128),
+        bluna_total_bonded: Uint128::from(2u128),
+    };
+
+    // the usdr converted to 1000uusd counts towards the minimum
+    set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(2000, "uusd")], 0).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.contains(&attr("dispatch_postponed", "true")));
+
+    set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(1300, "uusd")], 0).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), hub_info, swap_msg).unwrap();
+    assert_eq!(2, res.messages.len());
+    assert!(!res.attributes.contains(&attr("dispatch_postponed", "true")));
+}
+
+#[test]
+fn test_dispatch_rewards_release_period() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), default_init()).unwrap();
+
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: Some(86400),
+        reward_streams: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, update_config_msg).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.rewards_release_period, 86400);
+
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+
+    // both the hub and the bLuna reward contract release the rewards over the period
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&BondRewards {
+                release_period: Some(86400),
+            })
+            .unwrap(),
+            funds: vec![Coin::new(190, "uluna")],
+        })
+    );
+    assert_eq!(
+        res.messages[3].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_BLUNA_REWARD_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&UpdateGlobalIndex {
+                release_period: Some(86400),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+fn set_reward_streams(
+    deps: DepsMut,
+    reward_streams: Vec<RewardStream>,
+) -> StdResult<Response<TerraMsgWrapper>> {
+    let owner = mock_info("creator", &[]);
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        hub_contract: None,
+        bluna_reward_contract: None,
+        stluna_reward_denom: None,
+        bluna_reward_denom: None,
+        lido_fee_recipients: None,
+        stluna_fee_rate: None,
+        bluna_fee_rate: None,
+        lido_fee_in_stluna: None,
+        max_swap_deviation: None,
+        clear_max_swap_deviation: None,
+        min_dispatch_amounts: None,
+        max_dispatch_interval: None,
+        rewards_release_period: None,
+        reward_streams: Some(reward_streams),
+    };
+    execute(deps, mock_env(), owner, update_config_msg)
+}
+
+fn basket_reward_streams() -> Vec<RewardStream> {
+    vec![
+        RewardStream {
+            denom: "uluna".to_string(),
+            destination: RewardDestination::Hub {},
+            share: RewardShare::Bonded {
+                token: BondedToken::StLuna,
+            },
+            fee_rate: Decimal::percent(10),
+        },
+        RewardStream {
+            denom: "ukrw".to_string(),
+            destination: RewardDestination::Hook {
+                contract_addr: "wrapper".to_string(),
+                msg: to_binary("distribute").unwrap(),
+            },
+            share: RewardShare::Fixed {
+                share: Decimal::percent(10),
+            },
+            fee_rate: Decimal::zero(),
+        },
+        RewardStream {
+            denom: "uusd".to_string(),
+            destination: RewardDestination::RewardContract {
+                address: MOCK_BLUNA_REWARD_CONTRACT_ADDR.to_string(),
+            },
+            share: RewardShare::Bonded {
+                token: BondedToken::BLuna,
+            },
+            fee_rate: Decimal::percent(10),
+        },
+    ]
+}
+
+#[test]
+fn test_set_reward_streams() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+    // the stLuna and bLuna streams are used by default
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.reward_streams,
+        vec![
+            RewardStream {
+                denom: "uluna".to_string(),
+                destination: RewardDestination::Hub {},
+                share: RewardShare::Bonded {
+                    token: BondedToken::StLuna,
+                },
+                fee_rate: Decimal::percent(5),
+            },
+            RewardStream {
+                denom: "uusd".to_string(),
+                destination: RewardDestination::RewardContract {
+                    address: MOCK_BLUNA_REWARD_CONTRACT_ADDR.to_string(),
+                },
+                share: RewardShare::Bonded {
+                    token: BondedToken::BLuna,
+                },
+                fee_rate: Decimal::percent(5),
+            },
+        ]
+    );
+
+    let err = set_reward_streams(deps.as_mut(), vec![]).unwrap_err();
+    assert_eq!(err, StdError::generic_err("reward streams list is empty"));
+
+    let mut streams = basket_reward_streams();
+    streams[1].denom = "uusd".to_string();
+    let err = set_reward_streams(deps.as_mut(), streams).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("duplicate reward stream of uusd")
+    );
+
+    let mut streams = basket_reward_streams();
+    streams[0].denom = "ukrw".to_string();
+    streams[1].denom = "uluna".to_string();
+    let err = set_reward_streams(deps.as_mut(), streams).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("the hub can't bond ukrw rewards")
+    );
+
+    let mut streams = basket_reward_streams();
+    streams[1].share = RewardShare::Fixed {
+        share: Decimal::percent(110),
+    };
+    let err = set_reward_streams(deps.as_mut(), streams).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("fixed shares can't sum up to more than one")
+    );
+
+    let streams = vec![basket_reward_streams()[1].clone()];
+    let err = set_reward_streams(deps.as_mut(), streams).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("fixed shares must sum up to one without bonded shares")
+    );
+
+    let mut streams = basket_reward_streams();
+    streams[2].fee_rate = Decimal::percent(30);
+    let err = set_reward_streams(deps.as_mut(), streams).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("fee rate can't be greater than 0.2")
+    );
+
+    set_reward_streams(deps.as_mut(), basket_reward_streams()).unwrap();
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.reward_streams, basket_reward_streams());
+
+    // the minimums and the swap routes accept the denoms of the streams
+    set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(1000, "ukrw")], 0).unwrap();
+    set_swap_route(
+        deps.as_mut(),
+        "creator",
+        native_asset_info("mnt"),
+        vec![("pair0000", native_asset_info("ukrw"))],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_swap_to_reward_streams() {
+    let mut deps = mock_dependencies(&[Coin::new(200, "uluna"), Coin::new(300, "uusd")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+    set_reward_streams(deps.as_mut(), basket_reward_streams()).unwrap();
+
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::SwapToRewardDenom {
+        stluna_total_bonded: Uint128::from(1u128),
+        bluna_total_bonded: Uint128::from(1u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), hub_info, msg).unwrap();
+
+    // total rewards = 200uluna + 300uusd / 32 = 209uluna, ukrw gets 10% of it = 20uluna,
+    // the rest is split in half: 94uluna for stLuna and the rest in uusd for bLuna
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(Coin::new(106, "uluna"), "uusd".to_string())),
+            SubMsg::new(create_swap_msg(Coin::new(640, "uusd"), "ukrw".to_string())),
+        ]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("total_ukrw_rewards_available", "0ukrw")));
+    assert!(res
+        .attributes
+        .contains(&attr("total_stluna_rewards_available", "200uluna")));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "stluna_2_ukrw_rewards_xchg_rate"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "stluna_2_bluna_rewards_xchg_rate"));
+}
+
+#[test]
+fn test_dispatch_rewards_to_streams() {
+    let mut deps = mock_dependencies(&[Coin::new(100, "uluna"), Coin::new(100, "ukrw")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+    set_reward_streams(deps.as_mut(), basket_reward_streams()).unwrap();
+
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info,
+        ExecuteMsg::DispatchRewards {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&BondRewards {
+                    release_period: None
+                })
+                .unwrap(),
+                funds: vec![Coin::new(90, "uluna")],
+            })),
+            SubMsg::new(BankMsg::Send {
+                to_address: MOCK_LIDO_FEE_ADDRESS.to_string(),
+                amount: vec![Coin::new(10, "uluna")],
+            }),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "wrapper".to_string(),
+                msg: to_binary("distribute").unwrap(),
+                funds: vec![Coin::new(99, "ukrw")],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_BLUNA_REWARD_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&UpdateGlobalIndex {
+                    release_period: None
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("ukrw_rewards", "99ukrw")));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::DispatchHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: DispatchHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.history[0].hub_rewards, Coin::new(90, "uluna"));
+    assert_eq!(history.history[0].bluna_rewards, Coin::new(0, "uusd"));
+    assert_eq!(
+        history.history[0].other_rewards,
+        vec![Coin::new(99, "ukrw")]
+    );
+}
+
+#[test]
+fn test_migrate_reward_denom() {
+    let mut deps = mock_dependencies(&[Coin::new(100, "uluna"), Coin::new(300, "uusd")]);
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+    set_dispatch_thresholds(deps.as_mut(), vec![Coin::new(1000, "uusd")], 0).unwrap();
+
+    let migrate_msg = |token: BondedToken, new_denom: &str| ExecuteMsg::MigrateRewardDenom {
+        token,
+        new_denom: new_denom.to_string(),
+    };
+    let owner = mock_info("creator", &[]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        migrate_msg(BondedToken::BLuna, "ukrw"),
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        migrate_msg(BondedToken::StLuna, "ukrw"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("migrating stluna reward denom is forbidden")
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        migrate_msg(BondedToken::BLuna, "uusd"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("bluna rewards are already paid in uusd")
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        migrate_msg(BondedToken::BLuna, "uluna"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("uluna is already a reward denom")
+    );
+
+    // the market swap returns 4ukrw for the 300uusd worth 9ukrw at the oracle rate
+    set_max_swap_deviation(deps.as_mut(), Decimal::percent(50)).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        migrate_msg(BondedToken::BLuna, "ukrw"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("swap deviation 0.555555555555555555 exceeds max swap deviation 0.5")
+    );
+
+    set_max_swap_deviation(deps.as_mut(), Decimal::percent(60)).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner,
+        migrate_msg(BondedToken::BLuna, "ukrw"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(Coin::new(300, "uusd"), "ukrw".to_string())),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_BLUNA_REWARD_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&basset::reward::ExecuteMsg::MigrateRewardDenom {
+                    new_denom: "ukrw".to_string()
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.bluna_reward_denom, "ukrw");
+    assert_eq!(config.reward_streams[1].denom, "ukrw");
+    assert_eq!(config.min_dispatch_amounts, vec![]);
 }
@@ -0,0 +1,526 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+
+use crate::math::decimal_division;
+use crate::msg::{
+    AssetsResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PendingClaim,
+    PendingClaimsResponse, QueryMsg, SharesResponse,
+};
+use crate::querier::{
+    query_current_batch_id, query_hub_parameters, query_hub_state, query_stluna_balance,
+    query_unbond_history, query_withdrawable_unbonded,
+};
+use crate::state::{add_claim, read_claims, remove_claim, Config, CONFIG};
+use basset::hub::{Cw20HookMsg as HubCw20HookMsg, ExecuteMsg as HubExecuteMsg};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    let config = Config {
+        hub_contract: deps.api.addr_canonicalize(&msg.hub_contract)?,
+        stluna_token_contract: deps.api.addr_canonicalize(&msg.stluna_token_contract)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::Deposit { receiver } => execute_deposit(deps, env, info, receiver),
+        ExecuteMsg::Mint { shares, receiver } => execute_mint(deps, env, info, shares, receiver),
+        ExecuteMsg::Withdraw { assets, receiver } => {
+            execute_withdraw(deps, env, info, assets, receiver)
+        }
+        ExecuteMsg::Redeem { shares, receiver } => {
+            execute_redeem(deps, env, info, shares, receiver)
+        }
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::DepositHook {
+            receiver,
+            prev_balance,
+            min_shares,
+        } => execute_deposit_hook(deps, env, info, receiver, prev_balance, min_shares),
+        ExecuteMsg::ClaimHook { receiver } => execute_claim_hook(deps, env, info, receiver),
+    }
+}
+
+fn read_contracts(deps: Deps) -> StdResult<(Addr, Addr)> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok((
+        deps.api.addr_humanize(&config.hub_contract)?,
+        deps.api.addr_humanize(&config.stluna_token_contract)?,
+    ))
+}
+
+fn query_exchange_rate(deps: Deps, hub_address: &Addr) -> StdResult<Decimal> {
+    let exchange_rate = query_hub_state(deps, hub_address)?.stluna_exchange_rate;
+    if exchange_rate.is_zero() {
+        return Err(StdError::generic_err("the stLuna exchange rate is zero"));
+    }
+    Ok(exchange_rate)
+}
+
+fn convert_to_shares(assets: Uint128, exchange_rate: Decimal) -> Uint128 {
+    decimal_division(assets, exchange_rate)
+}
+
+fn convert_to_assets(shares: Uint128, exchange_rate: Decimal) -> Uint128 {
+    shares * exchange_rate
+}
+
+/// Converts the shares to the assets rounding up,
+/// so the hub mints at least the shares for the assets
+fn preview_mint(shares: Uint128, exchange_rate: Decimal) -> Uint128 {
+    let assets = convert_to_assets(shares, exchange_rate);
+    if convert_to_shares(assets, exchange_rate) < shares {
+        assets + Uint128::new(1)
+    } else {
+        assets
+    }
+}
+
+/// Converts the assets to the shares rounding up,
+/// so the shares unbonded are worth at least the assets
+fn preview_withdraw(assets: Uint128, exchange_rate: Decimal) -> Uint128 {
+    let shares = convert_to_shares(assets, exchange_rate);
+    if convert_to_assets(shares, exchange_rate) < assets {
+        shares + Uint128::new(1)
+    } else {
+        shares
+    }
+}
+
+/// Bonds the Luna sent with the message, the minted stLuna is sent to the receiver by the hook
+fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: Option<String>,
+) -> StdResult<Response> {
+    let (hub_address, _) = read_contracts(deps.as_ref())?;
+    let coin_denom = query_hub_parameters(deps.as_ref(), &hub_address)?.underlying_coin_denom;
+    let assets = read_payment(&info, &coin_denom)?;
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+
+    let messages = bond_messages(deps.as_ref(), &env, assets, &coin_denom, &receiver, None)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "deposit"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("assets", assets),
+    ]))
+}
+
+/// Bonds the Luna needed to mint the shares and refunds the rest of the sent Luna
+fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+    receiver: Option<String>,
+) -> StdResult<Response> {
+    if shares.is_zero() {
+        return Err(StdError::generic_err("Invalid zero amount"));
+    }
+    let (hub_address, _) = read_contracts(deps.as_ref())?;
+    let coin_denom = query_hub_parameters(deps.as_ref(), &hub_address)?.underlying_coin_denom;
+    let payment = read_payment(&info, &coin_denom)?;
+    let assets = preview_mint(shares, query_exchange_rate(deps.as_ref(), &hub_address)?);
+    let refund = payment.checked_sub(assets).map_err(|_| {
+        StdError::generic_err(format!(
+            "{}{} is required to mint the shares",
+            assets, coin_denom
+        ))
+    })?;
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+
+    let mut messages = bond_messages(
+        deps.as_ref(),
+        &env,
+        assets,
+        &coin_denom,
+        &receiver,
+        Some(shares),
+    )?;
+    if !refund.is_zero() {
+        messages.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(refund.u128(), &*coin_denom),
+            }
+            .into(),
+        );
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "mint"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("assets", assets),
+        attr("shares", shares),
+    ]))
+}
+
+fn read_payment(info: &MessageInfo, coin_denom: &str) -> StdResult<Uint128> {
+    if info.funds.len() > 1usize {
+        return Err(StdError::generic_err(
+            "More than one coin is sent; only one asset is supported",
+        ));
+    }
+    info.funds
+        .iter()
+        .find(|x| x.denom == coin_denom && x.amount > Uint128::zero())
+        .map(|x| x.amount)
+        .ok_or_else(|| {
+            StdError::generic_err(format!("No {} assets are provided to bond", coin_denom))
+        })
+}
+
+/// Returns the messages bonding the assets for stLuna and sending the minted stLuna
+/// to the receiver
+fn bond_messages(
+    deps: Deps,
+    env: &Env,
+    assets: Uint128,
+    coin_denom: &str,
+    receiver: &str,
+    min_shares: Option<Uint128>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let (hub_address, stluna_token_address) = read_contracts(deps)?;
+    deps.api.addr_validate(receiver)?;
+    let prev_balance = query_stluna_balance(deps, &stluna_token_address, &env.contract.address)?;
+
+    Ok(vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&HubExecuteMsg::BondForStLuna {})?,
+            funds: coins(assets.u128(), coin_denom),
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::DepositHook {
+                receiver: receiver.to_string(),
+                prev_balance,
+                min_shares: min_shares.unwrap_or_default(),
+            })?,
+            funds: vec![],
+        }),
+    ])
+}
+
+/// Sends the stLuna minted by the hub since the deposit to the receiver
+/// Only the contract itself is allowed to execute
+fn execute_deposit_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: String,
+    prev_balance: Uint128,
+    min_shares: Uint128,
+) -> StdResult<Response> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let (_, stluna_token_address) = read_contracts(deps.as_ref())?;
+    let balance =
+        query_stluna_balance(deps.as_ref(), &stluna_token_address, &env.contract.address)?;
+    let shares = balance.checked_sub(prev_balance)?;
+    if shares < min_shares {
+        return Err(StdError::generic_err(format!(
+            "Minted {} shares, less than the requested {}",
+            shares, min_shares
+        )));
+    }
+
+    let message = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: stluna_token_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: receiver.clone(),
+            amount: shares,
+        })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        attr("action", "deposit_hook"),
+        attr("receiver", receiver),
+        attr("shares", shares),
+    ]))
+}
+
+fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Uint128,
+    receiver: Option<String>,
+) -> StdResult<Response> {
+    let (hub_address, _) = read_contracts(deps.as_ref())?;
+    let shares = preview_withdraw(assets, query_exchange_rate(deps.as_ref(), &hub_address)?);
+    unbond_shares(deps, env, info, shares, receiver, "withdraw")
+}
+
+fn execute_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+    receiver: Option<String>,
+) -> StdResult<Response> {
+    unbond_shares(deps, env, info, shares, receiver, "redeem")
+}
+
+/// Takes the shares of the sender and unbonds them in the current batch of the hub,
+/// the claim of the receiver is recorded by the batch id
+fn unbond_shares(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+    receiver: Option<String>,
+    action: &str,
+) -> StdResult<Response> {
+    if shares.is_zero() {
+        return Err(StdError::generic_err("Invalid zero amount"));
+    }
+    let (hub_address, stluna_token_address) = read_contracts(deps.as_ref())?;
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    let receiver_raw = deps.api.addr_canonicalize(&receiver)?;
+
+    let batch_id = query_current_batch_id(deps.as_ref(), &hub_address)?;
+    add_claim(deps.storage, &receiver_raw, batch_id, shares)?;
+
+    let messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: stluna_token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: info.sender.to_string(),
+                recipient: env.contract.address.to_string(),
+                amount: shares,
+            })?,
+            funds: vec![],
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: stluna_token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: hub_address.to_string(),
+                amount: shares,
+                msg: to_binary(&HubCw20HookMsg::Unbond {})?,
+            })?,
+            funds: vec![],
+        }),
+    ];
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", action),
+        attr("owner", info.sender),
+        attr("receiver", receiver),
+        attr("shares", shares),
+        attr("batch_id", batch_id.to_string()),
+    ]))
+}
+
+/// Withdraws the unbonded Luna of the contract from the hub if there is any,
+/// the released claims of the sender are paid by the hook
+fn execute_claim(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if read_claims(deps.storage, &sender_raw)?.is_empty() {
+        return Err(StdError::generic_err("No pending claims"));
+    }
+
+    let (hub_address, _) = read_contracts(deps.as_ref())?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !query_withdrawable_unbonded(deps.as_ref(), &hub_address, &env.contract.address)?.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&HubExecuteMsg::WithdrawUnbonded {})?,
+            funds: vec![],
+        }));
+    }
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::ClaimHook {
+            receiver: info.sender.to_string(),
+        })?,
+        funds: vec![],
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![attr("action", "claim"), attr("sender", info.sender)]))
+}
+
+/// Sends the Luna of the claims of the receiver in the batches released by the hub
+/// Only the contract itself is allowed to execute
+fn execute_claim_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: String,
+) -> StdResult<Response> {
+    if info.sender != env.contract.address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let (hub_address, _) = read_contracts(deps.as_ref())?;
+    let coin_denom = query_hub_parameters(deps.as_ref(), &hub_address)?.underlying_coin_denom;
+    let receiver_raw = deps.api.addr_canonicalize(&receiver)?;
+
+    let mut assets = Uint128::zero();
+    for (batch_id, shares) in read_claims(deps.storage, &receiver_raw)? {
+        match query_unbond_history(deps.as_ref(), &hub_address, batch_id)? {
+            Some(history) if history.released => {
+                assets += shares * history.stluna_withdraw_rate;
+                remove_claim(deps.storage, &receiver_raw, batch_id);
+            }
+            _ => {}
+        }
+    }
+    if assets.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No withdrawable {} assets are available yet",
+            coin_denom
+        )));
+    }
+
+    let message = BankMsg::Send {
+        to_address: receiver.clone(),
+        amount: coins(assets.u128(), &*coin_denom),
+    };
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        attr("action", "claim_hook"),
+        attr("receiver", receiver),
+        attr("assets", assets),
+    ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::TotalAssets {} => to_binary(&query_total_assets(deps)?),
+        QueryMsg::ConvertToShares { assets } | QueryMsg::PreviewDeposit { assets } => {
+            to_binary(&query_shares(deps, assets, convert_to_shares)?)
+        }
+        QueryMsg::ConvertToAssets { shares } | QueryMsg::PreviewRedeem { shares } => {
+            to_binary(&query_assets(deps, shares, convert_to_assets)?)
+        }
+        QueryMsg::PreviewMint { shares } => to_binary(&query_assets(deps, shares, preview_mint)?),
+        QueryMsg::PreviewWithdraw { assets } => {
+            to_binary(&query_shares(deps, assets, preview_withdraw)?)
+        }
+        QueryMsg::MaxDeposit { receiver: _ } => to_binary(&query_max_deposit(deps)?),
+        QueryMsg::PendingClaims { address } => to_binary(&query_pending_claims(deps, address)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let (hub_address, stluna_token_address) = read_contracts(deps)?;
+    Ok(ConfigResponse {
+        hub_contract: hub_address.to_string(),
+        stluna_token_contract: stluna_token_address.to_string(),
+    })
+}
+
+fn query_total_assets(deps: Deps) -> StdResult<AssetsResponse> {
+    let (hub_address, _) = read_contracts(deps)?;
+    Ok(AssetsResponse {
+        assets: query_hub_state(deps, &hub_address)?.total_bond_stluna_amount,
+    })
+}
+
+fn query_shares(
+    deps: Deps,
+    assets: Uint128,
+    convert: fn(Uint128, Decimal) -> Uint128,
+) -> StdResult<SharesResponse> {
+    let (hub_address, _) = read_contracts(deps)?;
+    Ok(SharesResponse {
+        shares: convert(assets, query_exchange_rate(deps, &hub_address)?),
+    })
+}
+
+fn query_assets(
+    deps: Deps,
+    shares: Uint128,
+    convert: fn(Uint128, Decimal) -> Uint128,
+) -> StdResult<AssetsResponse> {
+    let (hub_address, _) = read_contracts(deps)?;
+    Ok(AssetsResponse {
+        assets: convert(shares, query_exchange_rate(deps, &hub_address)?),
+    })
+}
+
+fn query_max_deposit(deps: Deps) -> StdResult<AssetsResponse> {
+    let (hub_address, _) = read_contracts(deps)?;
+    let paused = query_hub_parameters(deps, &hub_address)?
+        .paused
+        .unwrap_or_default();
+    Ok(AssetsResponse {
+        assets: if paused {
+            Uint128::zero()
+        } else {
+            Uint128::new(u128::MAX)
+        },
+    })
+}
+
+fn query_pending_claims(deps: Deps, address: String) -> StdResult<PendingClaimsResponse> {
+    let (hub_address, _) = read_contracts(deps)?;
+    let exchange_rate = query_exchange_rate(deps, &hub_address)?;
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+
+    let mut claims: Vec<PendingClaim> = vec![];
+    let mut claimable = Uint128::zero();
+    for (batch_id, shares) in read_claims(deps.storage, &address_raw)? {
+        let (assets, released) = match query_unbond_history(deps, &hub_address, batch_id)? {
+            Some(history) if history.released => (shares * history.stluna_withdraw_rate, true),
+            Some(history) => (shares * history.stluna_applied_exchange_rate, false),
+            None => (convert_to_assets(shares, exchange_rate), false),
+        };
+        if released {
+            claimable += assets;
+        }
+        claims.push(PendingClaim {
+            batch_id,
+            shares,
+            assets,
+            released,
+        });
+    }
+
+    Ok(PendingClaimsResponse { claims, claimable })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
@@ -0,0 +1,132 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::Uint128;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub hub_contract: String,
+    pub stluna_token_contract: String,
+}
+
+/// The assets are Luna and the shares are stLuna,
+/// the receivers default to the sender
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Bonds the sent Luna and sends the minted stLuna to the receiver
+    Deposit { receiver: Option<String> },
+    /// Bonds the Luna needed to mint the shares and refunds the rest of the sent Luna
+    Mint {
+        shares: Uint128,
+        receiver: Option<String>,
+    },
+    /// Unbonds the shares of the sender worth the assets for the receiver
+    Withdraw {
+        assets: Uint128,
+        receiver: Option<String>,
+    },
+    /// Unbonds the shares of the sender for the receiver
+    Redeem {
+        shares: Uint128,
+        receiver: Option<String>,
+    },
+    /// Sends the Luna of the released claims to the sender
+    Claim {},
+
+    /// Sends the stLuna minted by the hub to the receiver.
+    /// Only the contract itself is allowed to execute
+    DepositHook {
+        receiver: String,
+        prev_balance: Uint128,
+        min_shares: Uint128,
+    },
+    /// Sends the Luna of the released claims to the receiver.
+    /// Only the contract itself is allowed to execute
+    ClaimHook { receiver: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    /// The Luna bonded for stLuna by the hub
+    TotalAssets {},
+    ConvertToShares {
+        assets: Uint128,
+    },
+    ConvertToAssets {
+        shares: Uint128,
+    },
+    /// The shares minted by `Deposit`
+    PreviewDeposit {
+        assets: Uint128,
+    },
+    /// The assets `Mint` takes
+    PreviewMint {
+        shares: Uint128,
+    },
+    /// The shares `Withdraw` unbonds
+    PreviewWithdraw {
+        assets: Uint128,
+    },
+    /// The assets `Redeem` unbonds at the current exchange rate,
+    /// the claim is paid at the withdraw rate of its batch
+    PreviewRedeem {
+        shares: Uint128,
+    },
+    MaxDeposit {
+        receiver: String,
+    },
+    PendingClaims {
+        address: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub hub_contract: String,
+    pub stluna_token_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetsResponse {
+    pub assets: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharesResponse {
+    pub shares: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingClaim {
+    pub batch_id: u64,
+    pub shares: Uint128,
+    /// The Luna paid for the claim if it's released, an estimate otherwise
+    pub assets: Uint128,
+    pub released: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingClaimsResponse {
+    pub claims: Vec<PendingClaim>,
+    /// The Luna of the released claims
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
@@ -0,0 +1,93 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+
+use basset::hub::{
+    AllHistoryResponse, CurrentBatchResponse, Parameters, QueryMsg as HubQueryMsg, StateResponse,
+    UnbondHistoryResponse, WithdrawableUnbondedResponse,
+};
+
+pub fn query_hub_state(deps: Deps, hub_address: &Addr) -> StdResult<StateResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::State {})?,
+    }))
+}
+
+pub fn query_hub_parameters(deps: Deps, hub_address: &Addr) -> StdResult<Parameters> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::Parameters {})?,
+    }))
+}
+
+pub fn query_current_batch_id(deps: Deps, hub_address: &Addr) -> StdResult<u64> {
+    let current_batch: CurrentBatchResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&HubQueryMsg::CurrentBatch {})?,
+        }))?;
+    Ok(current_batch.id)
+}
+
+/// Returns the unbond history of the batch, none if the batch hasn't been undelegated yet
+pub fn query_unbond_history(
+    deps: Deps,
+    hub_address: &Addr,
+    batch_id: u64,
+) -> StdResult<Option<UnbondHistoryResponse>> {
+    let history: AllHistoryResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&HubQueryMsg::AllHistory {
+                start_from: batch_id.checked_sub(1),
+                limit: Some(1),
+            })?,
+        }))?;
+    Ok(history
+        .history
+        .into_iter()
+        .find(|history| history.batch_id == batch_id))
+}
+
+pub fn query_withdrawable_unbonded(
+    deps: Deps,
+    hub_address: &Addr,
+    address: &Addr,
+) -> StdResult<Uint128> {
+    let withdrawable: WithdrawableUnbondedResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&HubQueryMsg::WithdrawableUnbonded {
+                address: address.to_string(),
+            })?,
+        }))?;
+    Ok(withdrawable.withdrawable)
+}
+
+pub fn query_stluna_balance(
+    deps: Deps,
+    stluna_token_address: &Addr,
+    address: &Addr,
+) -> StdResult<Uint128> {
+    let balance: BalanceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: stluna_token_address.to_string(),
+        msg: to_binary(&Cw20QueryMsg::Balance {
+            address: address.to_string(),
+        })?,
+    }))?;
+    Ok(balance.balance)
+}
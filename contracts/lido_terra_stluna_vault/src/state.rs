@@ -0,0 +1,64 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
+
+pub const CONFIG: Item<Config> = Item::new("config");
+// the stLuna unbonded for the receivers by the batch ids of the hub
+pub const CLAIMS: Map<(&[u8], &[u8]), Uint128> = Map::new("claims");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub hub_contract: CanonicalAddr,
+    pub stluna_token_contract: CanonicalAddr,
+}
+
+pub fn add_claim(
+    storage: &mut dyn Storage,
+    receiver: &CanonicalAddr,
+    batch_id: u64,
+    shares: Uint128,
+) -> StdResult<()> {
+    CLAIMS.update(
+        storage,
+        (receiver.as_slice(), &batch_id.to_be_bytes()),
+        |claim| -> StdResult<_> { Ok(claim.unwrap_or_default() + shares) },
+    )?;
+    Ok(())
+}
+
+pub fn remove_claim(storage: &mut dyn Storage, receiver: &CanonicalAddr, batch_id: u64) {
+    CLAIMS.remove(storage, (receiver.as_slice(), &batch_id.to_be_bytes()))
+}
+
+/// Returns the batch ids and the stLuna amounts of the claims of the receiver
+pub fn read_claims(
+    storage: &dyn Storage,
+    receiver: &CanonicalAddr,
+) -> StdResult<Vec<(u64, Uint128)>> {
+    CLAIMS
+        .prefix(receiver.as_slice())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (k, shares) = item?;
+            let mut batch_id = [0u8; 8];
+            batch_id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(batch_id), shares))
+        })
+        .collect()
+}
@@ -0,0 +1,357 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use basset::hub::{
+    Cw20HookMsg as HubCw20HookMsg, ExecuteMsg as HubExecuteMsg, UnbondHistoryResponse,
+};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, BankMsg, CosmosMsg, Decimal, OwnedDeps, StdError, SubMsg,
+    Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{
+    AssetsResponse, ExecuteMsg, InstantiateMsg, PendingClaim, PendingClaimsResponse, QueryMsg,
+    SharesResponse,
+};
+use crate::testing::mock_querier::{
+    mock_dependencies, WasmMockQuerier, MOCK_CURRENT_BATCH_ID, MOCK_HUB_CONTRACT_ADDR,
+    MOCK_STLUNA_TOKEN_CONTRACT_ADDR,
+};
+
+fn do_init(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+    let msg = InstantiateMsg {
+        hub_contract: MOCK_HUB_CONTRACT_ADDR.to_string(),
+        stluna_token_contract: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+    };
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(11u128, 10u128));
+}
+
+fn unbond_history(batch_id: u64, released: bool) -> UnbondHistoryResponse {
+    UnbondHistoryResponse {
+        batch_id,
+        time: 0,
+        bluna_amount: Uint128::zero(),
+        bluna_applied_exchange_rate: Decimal::one(),
+        bluna_withdraw_rate: Decimal::one(),
+        stluna_amount: Uint128::from(500u128),
+        stluna_applied_exchange_rate: Decimal::from_ratio(11u128, 10u128),
+        stluna_withdraw_rate: Decimal::from_ratio(105u128, 100u128),
+        released,
+        amount: Uint128::zero(),
+        applied_exchange_rate: Decimal::one(),
+        withdraw_rate: Decimal::one(),
+    }
+}
+
+#[test]
+fn deposit_and_mint() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier.with_stluna_balance(Uint128::from(7u128));
+
+    let info = mock_info("addr0000", &coins(1100, "uluna"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { receiver: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&HubExecuteMsg::BondForStLuna {}).unwrap(),
+                funds: coins(1100, "uluna"),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::DepositHook {
+                    receiver: "addr0000".to_string(),
+                    prev_balance: Uint128::from(7u128),
+                    min_shares: Uint128::zero(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+
+    // the hook is only executed by the contract itself
+    let hook = ExecuteMsg::DepositHook {
+        receiver: "addr0000".to_string(),
+        prev_balance: Uint128::from(7u128),
+        min_shares: Uint128::from(1000u128),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, hook.clone()).unwrap_err();
+    assert_eq!(res, StdError::generic_err("unauthorized"));
+
+    // the hub minted less than requested
+    deps.querier.with_stluna_balance(Uint128::from(1006u128));
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), hook.clone()).unwrap_err();
+
+    deps.querier.with_stluna_balance(Uint128::from(1007u128));
+    let res = execute(deps.as_mut(), mock_env(), info, hook).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    // mint takes the assets for the shares and refunds the rest
+    let info = mock_info("addr0000", &coins(1000, "uluna"));
+    let msg = ExecuteMsg::Mint {
+        shares: Uint128::from(1000u128),
+        receiver: Some("addr0001".to_string()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("1100uluna is required to mint the shares")
+    );
+
+    let info = mock_info("addr0000", &coins(1200, "uluna"));
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&HubExecuteMsg::BondForStLuna {}).unwrap(),
+            funds: coins(1100, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: coins(100, "uluna"),
+        }))
+    );
+}
+
+#[test]
+fn previews() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+    deps.querier
+        .with_stluna_exchange_rate(Decimal::from_ratio(3u128, 2u128));
+
+    let query_shares = |msg: QueryMsg| -> Uint128 {
+        let res: SharesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.shares
+    };
+    let assets = Uint128::from(100u128);
+    assert_eq!(
+        query_shares(QueryMsg::ConvertToShares { assets }),
+        Uint128::from(66u128)
+    );
+    assert_eq!(
+        query_shares(QueryMsg::PreviewDeposit { assets }),
+        Uint128::from(66u128)
+    );
+    assert_eq!(
+        query_shares(QueryMsg::PreviewWithdraw { assets }),
+        Uint128::from(67u128)
+    );
+
+    let query_assets = |msg: QueryMsg| -> Uint128 {
+        let res: AssetsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.assets
+    };
+    let shares = Uint128::from(67u128);
+    assert_eq!(
+        query_assets(QueryMsg::ConvertToAssets { shares }),
+        Uint128::from(100u128)
+    );
+    assert_eq!(
+        query_assets(QueryMsg::PreviewRedeem { shares }),
+        Uint128::from(100u128)
+    );
+    assert_eq!(
+        query_assets(QueryMsg::PreviewMint { shares }),
+        Uint128::from(101u128)
+    );
+    assert_eq!(
+        query_assets(QueryMsg::TotalAssets {}),
+        Uint128::from(1_000_000u128)
+    );
+    assert_eq!(
+        query_assets(QueryMsg::MaxDeposit {
+            receiver: "addr0000".to_string()
+        }),
+        Uint128::new(u128::MAX)
+    );
+}
+
+#[test]
+fn withdraw_and_claim() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(&mut deps);
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::Withdraw {
+        assets: Uint128::from(550u128),
+        receiver: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: "addr0000".to_string(),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(500u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_STLUNA_TOKEN_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: MOCK_HUB_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(500u128),
+                    msg: to_binary(&HubCw20HookMsg::Unbond {}).unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+
+    let query_claims = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>| {
+        let res: PendingClaimsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingClaims {
+                    address: "addr0000".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res
+    };
+    assert_eq!(
+        query_claims(&deps),
+        PendingClaimsResponse {
+            claims: vec![PendingClaim {
+                batch_id: MOCK_CURRENT_BATCH_ID,
+                shares: Uint128::from(500u128),
+                assets: Uint128::from(550u128),
+                released: false,
+            }],
+            claimable: Uint128::zero(),
+        }
+    );
+
+    // nothing is released yet
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    let hook = ExecuteMsg::ClaimHook {
+        receiver: "addr0000".to_string(),
+    };
+    let contract_info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        contract_info.clone(),
+        hook.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("No withdrawable uluna assets are available yet")
+    );
+
+    deps.querier.with_unbond_history(
+        vec![
+            unbond_history(MOCK_CURRENT_BATCH_ID - 1, true),
+            unbond_history(MOCK_CURRENT_BATCH_ID, true),
+        ],
+        Uint128::from(525u128),
+    );
+    assert_eq!(query_claims(&deps).claimable, Uint128::from(525u128));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&HubExecuteMsg::WithdrawUnbonded {}).unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&hook).unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), contract_info, hook).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: coins(525, "uluna"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_hook"),
+            attr("receiver", "addr0000"),
+            attr("assets", "525"),
+        ]
+    );
+    assert_eq!(query_claims(&deps).claims, vec![]);
+
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Claim {}).unwrap_err();
+    assert_eq!(res, StdError::generic_err("No pending claims"));
+}
@@ -21,8 +21,10 @@ use cw20::{
     AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse,
     TokenInfoResponse,
 };
-use cw20_legacy::msg::{ExecuteMsg, QueryMsg};
-use lido_terra_token::msg::TokenInitMsg;
+use lido_terra_token::msg::{
+    BalanceInUnderlyingResponse, ExecuteMsg, PermitNonceResponse, QueryMsg,
+    TokenInfoExtendedResponse, TokenInitMsg, TotalSupplyResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -38,4 +40,8 @@ fn main() {
     export_schema(&schema_for!(TokenInfoResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
     export_schema(&schema_for!(AllAccountsResponse), &out_dir);
+    export_schema(&schema_for!(PermitNonceResponse), &out_dir);
+    export_schema(&schema_for!(TotalSupplyResponse), &out_dir);
+    export_schema(&schema_for!(BalanceInUnderlyingResponse), &out_dir);
+    export_schema(&schema_for!(TokenInfoExtendedResponse), &out_dir);
 }
@@ -15,17 +15,26 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
 
 use cw20_legacy::allowances::{execute_decrease_allowance, execute_increase_allowance};
 use cw20_legacy::contract::instantiate as cw20_init;
-use cw20_legacy::contract::query as cw20_query;
-use cw20_legacy::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw20_legacy::contract::{query as cw20_query, query_balance, query_token_info};
+use cw20_legacy::msg::{InstantiateMsg, QueryMsg as Cw20QueryMsg};
 
 use crate::handler::*;
-use crate::msg::{MigrateMsg, TokenInitMsg};
-use crate::state::store_hub_contract;
-use cw20::MinterResponse;
+use crate::msg::{
+    BalanceInUnderlyingResponse, ExecuteMsg, MigrateMsg, PermitNonceResponse, QueryMsg,
+    TokenInfoExtendedResponse, TokenInitMsg, TotalSupplyResponse,
+};
+use crate::querier::query_hub_state;
+use crate::state::{
+    read_balance_snapshot, read_permit_nonce, read_snapshot_id, read_total_supply_snapshot,
+    store_hub_contract,
+};
+use cw20::{BalanceResponse, MinterResponse};
 use cw20_legacy::ContractError;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -99,12 +108,115 @@ pub fn execute(
             amount,
             msg,
         } => execute_send_from(deps, env, info, owner, contract, amount, msg),
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        } => execute_permit(
+            deps, env, owner, spender, amount, expires, nonce, signature, pubkey,
+        ),
+        ExecuteMsg::Snapshot {} => execute_snapshot(deps, env, info),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    cw20_query(deps, _env, msg)
+    let cw20_msg = match msg {
+        QueryMsg::PermitNonce { owner } => return to_binary(&query_permit_nonce(deps, owner)?),
+        QueryMsg::BalanceAt {
+            address,
+            snapshot_id,
+        } => return to_binary(&query_balance_at(deps, address, snapshot_id)?),
+        QueryMsg::TotalSupplyAt { snapshot_id } => {
+            return to_binary(&query_total_supply_at(deps, snapshot_id)?)
+        }
+        QueryMsg::BalanceInUnderlying { address } => {
+            return to_binary(&query_balance_in_underlying(deps, address)?)
+        }
+        QueryMsg::TokenInfoExtended {} => return to_binary(&query_token_info_extended(deps)?),
+        QueryMsg::Balance { address } => Cw20QueryMsg::Balance { address },
+        QueryMsg::TokenInfo {} => Cw20QueryMsg::TokenInfo {},
+        QueryMsg::Minter {} => Cw20QueryMsg::Minter {},
+        QueryMsg::Allowance { owner, spender } => Cw20QueryMsg::Allowance { owner, spender },
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => Cw20QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        },
+        QueryMsg::AllAccounts { start_after, limit } => {
+            Cw20QueryMsg::AllAccounts { start_after, limit }
+        }
+    };
+    cw20_query(deps, _env, cw20_msg)
+}
+
+fn query_permit_nonce(deps: Deps, owner: String) -> StdResult<PermitNonceResponse> {
+    let owner_raw = deps.api.addr_canonicalize(&owner)?;
+    Ok(PermitNonceResponse {
+        nonce: read_permit_nonce(deps.storage, &owner_raw)?,
+    })
+}
+
+fn check_snapshot_id(deps: Deps, snapshot_id: u64) -> StdResult<()> {
+    if snapshot_id == 0 || snapshot_id > read_snapshot_id(deps.storage)? {
+        return Err(StdError::generic_err(format!(
+            "snapshot {} doesn't exist",
+            snapshot_id
+        )));
+    }
+    Ok(())
+}
+
+fn query_balance_at(deps: Deps, address: String, snapshot_id: u64) -> StdResult<BalanceResponse> {
+    check_snapshot_id(deps, snapshot_id)?;
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    match read_balance_snapshot(deps.storage, &address_raw, snapshot_id)? {
+        Some(balance) => Ok(BalanceResponse { balance }),
+        None => query_balance(deps, address),
+    }
+}
+
+fn query_total_supply_at(deps: Deps, snapshot_id: u64) -> StdResult<TotalSupplyResponse> {
+    check_snapshot_id(deps, snapshot_id)?;
+    let total_supply = match read_total_supply_snapshot(deps.storage, snapshot_id)? {
+        Some(total_supply) => total_supply,
+        None => query_token_info(deps)?.total_supply,
+    };
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+fn query_balance_in_underlying(
+    deps: Deps,
+    address: String,
+) -> StdResult<BalanceInUnderlyingResponse> {
+    let balance = query_balance(deps, address)?.balance;
+    let exchange_rate = query_hub_state(deps)?.bluna_exchange_rate;
+    Ok(BalanceInUnderlyingResponse {
+        balance,
+        exchange_rate,
+        underlying_amount: balance * exchange_rate,
+    })
+}
+
+fn query_token_info_extended(deps: Deps) -> StdResult<TokenInfoExtendedResponse> {
+    let token_info = query_token_info(deps)?;
+    let state = query_hub_state(deps)?;
+    Ok(TokenInfoExtendedResponse {
+        name: token_info.name,
+        symbol: token_info.symbol,
+        decimals: token_info.decimals,
+        total_supply: token_info.total_supply,
+        exchange_rate: state.bluna_exchange_rate,
+        total_backing: state.total_bond_bluna_amount,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
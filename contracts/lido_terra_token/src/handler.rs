@@ -13,13 +13,19 @@
 // limitations under the License.
 
 use cosmwasm_std::{
-    to_binary, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128, WasmMsg,
+    attr, to_binary, Api, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
-use crate::querier::query_reward_contract;
-use crate::state::read_hub_contract;
+use crate::querier::{query_hub_owner, query_reward_contract};
+use crate::state::{
+    checkpoint_balance, checkpoint_total_supply, read_hub_contract, read_permit_nonce,
+    read_snapshot_id, store_permit_nonce, store_snapshot_id,
+};
 use basset::hub::ExecuteMsg::CheckSlashing;
 use basset::reward::ExecuteMsg::{DecreaseBalance, IncreaseBalance};
+use basset::{verify_permit, PermitPayload};
+use cw20::{AllowanceResponse, Expiration};
 use cw20_legacy::allowances::{
     execute_burn_from as cw20_burn_from, execute_send_from as cw20_send_from,
     execute_transfer_from as cw20_transfer_from,
@@ -28,6 +34,7 @@ use cw20_legacy::contract::{
     execute_burn as cw20_burn, execute_mint as cw20_mint, execute_send as cw20_send,
     execute_transfer as cw20_transfer,
 };
+use cw20_legacy::state::{ALLOWANCES, BALANCES, TOKEN_INFO};
 use cw20_legacy::ContractError;
 
 pub fn execute_transfer(
@@ -42,6 +49,12 @@ pub fn execute_transfer(
 
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
 
+    checkpoint_snapshots(
+        deps.storage,
+        deps.api,
+        &[sender.as_str(), &recipient],
+        false,
+    )?;
     let res: Response = cw20_transfer(deps, env, info, recipient, amount)?;
     let messages = vec![
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -76,6 +89,7 @@ pub fn execute_burn(
     let reward_contract = query_reward_contract(&deps)?;
     let hub_contract = deps.api.addr_humanize(&read_hub_contract(deps.storage)?)?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[sender.as_str()], true)?;
     let res: Response = cw20_burn(deps, env, info, amount)?;
     let mut messages = vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: reward_contract.to_string(),
@@ -106,6 +120,7 @@ pub fn execute_mint(
 ) -> Result<Response, ContractError> {
     let reward_contract = query_reward_contract(&deps)?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[&recipient], true)?;
     let res: Response = cw20_mint(deps, env, info, recipient.clone(), amount)?;
     Ok(Response::new()
         .add_submessages(vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -130,6 +145,7 @@ pub fn execute_send(
     let sender = info.sender.clone();
     let reward_contract = query_reward_contract(&deps)?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[sender.as_str(), &contract], false)?;
     let res: Response = cw20_send(deps, env, info, contract.clone(), amount, msg)?;
     let messages = vec![
         vec![
@@ -171,6 +187,7 @@ pub fn execute_transfer_from(
 
     let valid_owner = deps.api.addr_validate(owner.as_str())?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner, &recipient], false)?;
     let res: Response = cw20_transfer_from(deps, env, info, owner, recipient.clone(), amount)?;
     let messages = vec![
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -207,6 +224,7 @@ pub fn execute_burn_from(
 
     let valid_owner = deps.api.addr_validate(owner.as_str())?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner], true)?;
     let res: Response = cw20_burn_from(deps, env, info, owner, amount)?;
     let messages = vec![
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -241,6 +259,7 @@ pub fn execute_send_from(
 
     let valid_owner = deps.api.addr_validate(owner.as_str())?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner, &contract], false)?;
     let res: Response = cw20_send_from(deps, env, info, owner, contract.clone(), amount, msg)?;
     let messages = vec![
         vec![
@@ -269,3 +288,115 @@ pub fn execute_send_from(
         .add_submessages(messages)
         .add_attributes(res.attributes))
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    nonce: u64,
+    signature: Binary,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if let Some(expires) = expires {
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+    }
+
+    let owner_raw = deps.api.addr_canonicalize(owner_addr.as_str())?;
+    let spender_raw = deps.api.addr_canonicalize(spender_addr.as_str())?;
+    let expected_nonce = read_permit_nonce(deps.storage, &owner_raw)?;
+    if nonce != expected_nonce {
+        return Err(StdError::generic_err(format!(
+            "invalid permit nonce, expected {}",
+            expected_nonce
+        ))
+        .into());
+    }
+
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id,
+        contract: env.contract.address.to_string(),
+        owner: owner_addr.to_string(),
+        spender: spender_addr.to_string(),
+        amount,
+        expires,
+        nonce,
+    };
+    verify_permit(deps.api, &payload, &signature, &pubkey)?;
+
+    store_permit_nonce(deps.storage, &owner_raw, nonce + 1)?;
+    ALLOWANCES.save(
+        deps.storage,
+        (owner_raw.as_slice(), spender_raw.as_slice()),
+        &AllowanceResponse {
+            allowance: amount,
+            expires: expires.unwrap_or_default(),
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]))
+}
+
+/// Takes a snapshot of the balances and the total supply, the snapshot id is returned
+/// in the attributes
+pub fn execute_snapshot(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let hub_contract = deps.api.addr_humanize(&read_hub_contract(deps.storage)?)?;
+    if info.sender != hub_contract && info.sender != query_hub_owner(deps.as_ref(), &hub_contract)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let snapshot_id = read_snapshot_id(deps.storage)? + 1;
+    store_snapshot_id(deps.storage, snapshot_id)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "snapshot"),
+        attr("snapshot_id", snapshot_id.to_string()),
+        attr("height", env.block.height.to_string()),
+    ]))
+}
+
+/// Records the balances of the accounts and the total supply if it's going to change
+/// at the last snapshot before the cw20 handlers change them
+fn checkpoint_snapshots(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    accounts: &[&str],
+    total_supply: bool,
+) -> StdResult<()> {
+    if read_snapshot_id(storage)? == 0 {
+        return Ok(());
+    }
+    for account in accounts {
+        let account_raw = api.addr_canonicalize(account)?;
+        let balance = BALANCES
+            .may_load(storage, account_raw.as_slice())?
+            .unwrap_or_default();
+        checkpoint_balance(storage, &account_raw, balance)?;
+    }
+    if total_supply {
+        let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+        checkpoint_total_supply(storage, total_supply)?;
+    }
+    Ok(())
+}
@@ -12,12 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_std::{to_binary, Addr, DepsMut, QueryRequest, StdError, StdResult, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Deps, DepsMut, QueryRequest, StdError, StdResult, WasmQuery};
 
 use crate::state::read_hub_contract;
-use basset::hub::{ConfigResponse, QueryMsg as HubQueryMsg};
-use lido_terra_rewards_dispatcher::msg::QueryMsg as RewardsDispatcherQueryMsg;
-use lido_terra_rewards_dispatcher::state::Config as RewardsDispatcherConfig;
+use basset::hub::{ConfigResponse, QueryMsg as HubQueryMsg, StateResponse};
+use lido_terra_rewards_dispatcher::msg::{
+    ConfigResponse as RewardsDispatcherConfig, QueryMsg as RewardsDispatcherQueryMsg,
+};
 
 pub fn query_reward_contract(deps: &DepsMut) -> StdResult<Addr> {
     let hub_address = deps.api.addr_humanize(&read_hub_contract(deps.storage)?)?;
@@ -52,3 +53,21 @@ pub fn query_reward_contract(deps: &DepsMut) -> StdResult<Addr> {
 
     Ok(bluna_reward_address)
 }
+
+pub fn query_hub_owner(deps: Deps, hub_address: &Addr) -> StdResult<Addr> {
+    let config: ConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::Config {})?,
+    }))?;
+
+    deps.api.addr_validate(config.owner.as_str())
+}
+
+pub fn query_hub_state(deps: Deps) -> StdResult<StateResponse> {
+    let hub_address = deps.api.addr_humanize(&read_hub_contract(deps.storage)?)?;
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::State {})?,
+    }))
+}
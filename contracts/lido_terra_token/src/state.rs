@@ -12,11 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage, Uint128};
 //use cosmwasm_storage::{singleton, singleton_read};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Bound, Item, Map};
 
 pub const HUB_CONTRACT_KEY: Item<CanonicalAddr> = Item::new("\u{0}\u{c}hub_contract");
+// the nonce the next permit of the owner must be signed with
+pub const PERMIT_NONCES: Map<&[u8], u64> = Map::new("permit_nonces");
+// the id of the last snapshot, zero if no snapshot was taken
+pub const SNAPSHOT_ID: Item<u64> = Item::new("snapshot_id");
+// the balances of the accounts at the snapshots, a balance is recorded under the last snapshot
+// before it changes for the first time after the snapshot
+pub const BALANCE_SNAPSHOTS: Map<(&[u8], &[u8]), Uint128> = Map::new("balance_snapshots");
+pub const TOTAL_SUPPLY_SNAPSHOTS: Map<&[u8], Uint128> = Map::new("total_supply_snapshots");
 
 // meta is the token definition as well as the total_supply
 pub fn read_hub_contract(storage: &dyn Storage) -> StdResult<CanonicalAddr> {
@@ -30,6 +38,90 @@ pub fn store_hub_contract(
     HUB_CONTRACT_KEY.save(storage, hub_contract)
 }
 
+pub fn read_permit_nonce(storage: &dyn Storage, owner: &CanonicalAddr) -> StdResult<u64> {
+    Ok(PERMIT_NONCES
+        .may_load(storage, owner.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn store_permit_nonce(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    nonce: u64,
+) -> StdResult<()> {
+    PERMIT_NONCES.save(storage, owner.as_slice(), &nonce)
+}
+
+pub fn read_snapshot_id(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(SNAPSHOT_ID.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn store_snapshot_id(storage: &mut dyn Storage, snapshot_id: u64) -> StdResult<()> {
+    SNAPSHOT_ID.save(storage, &snapshot_id)
+}
+
+/// Records the balance of the account at the last snapshot unless it has already been changed
+/// since the snapshot. Must be called before the balance changes.
+pub fn checkpoint_balance(
+    storage: &mut dyn Storage,
+    account: &CanonicalAddr,
+    balance: Uint128,
+) -> StdResult<()> {
+    let snapshot_id = read_snapshot_id(storage)?;
+    if snapshot_id == 0 {
+        return Ok(());
+    }
+    let key = (account.as_slice(), &snapshot_id.to_be_bytes()[..]);
+    if !BALANCE_SNAPSHOTS.has(storage, key) {
+        BALANCE_SNAPSHOTS.save(storage, key, &balance)?;
+    }
+    Ok(())
+}
+
+/// Records the total supply at the last snapshot unless it has already been changed
+/// since the snapshot. Must be called before the total supply changes.
+pub fn checkpoint_total_supply(storage: &mut dyn Storage, total_supply: Uint128) -> StdResult<()> {
+    let snapshot_id = read_snapshot_id(storage)?;
+    if snapshot_id == 0 {
+        return Ok(());
+    }
+    let key = snapshot_id.to_be_bytes();
+    if !TOTAL_SUPPLY_SNAPSHOTS.has(storage, &key) {
+        TOTAL_SUPPLY_SNAPSHOTS.save(storage, &key, &total_supply)?;
+    }
+    Ok(())
+}
+
+/// Returns the balance of the account at the snapshot,
+/// none if the balance hasn't changed since the snapshot
+pub fn read_balance_snapshot(
+    storage: &dyn Storage,
+    account: &CanonicalAddr,
+    snapshot_id: u64,
+) -> StdResult<Option<Uint128>> {
+    let start = Bound::inclusive(snapshot_id.to_be_bytes().to_vec());
+    BALANCE_SNAPSHOTS
+        .prefix(account.as_slice())
+        .range(storage, Some(start), None, Order::Ascending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
+
+/// Returns the total supply at the snapshot,
+/// none if the total supply hasn't changed since the snapshot
+pub fn read_total_supply_snapshot(
+    storage: &dyn Storage,
+    snapshot_id: u64,
+) -> StdResult<Option<Uint128>> {
+    let start = Bound::inclusive(snapshot_id.to_be_bytes().to_vec());
+    TOTAL_SUPPLY_SNAPSHOTS
+        .range(storage, Some(start), None, Order::Ascending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
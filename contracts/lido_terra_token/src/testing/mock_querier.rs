@@ -12,13 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use basset::hub::ConfigResponse;
+use basset::hub::{ConfigResponse, QueryMsg as HubQueryMsg, StateResponse};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_slice, to_binary, Api, Coin, ContractResult, Decimal, Empty, OwnedDeps, Querier,
-    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+    from_binary, from_slice, to_binary, Api, Coin, ContractResult, Decimal, Empty, OwnedDeps,
+    Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
-use lido_terra_rewards_dispatcher::state::Config as RewardsDispatcherConfig;
+use lido_terra_rewards_dispatcher::msg::{ConfigResponse as RewardsDispatcherConfig, FeeRecipient};
 
 pub const MOCK_OWNER_ADDR: &str = "owner";
 pub const MOCK_HUB_CONTRACT_ADDR: &str = "hub";
@@ -66,11 +66,25 @@ impl Querier for WasmMockQuerier {
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
-            QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr,
-                msg: _,
-            }) => {
-                if *contract_addr == MOCK_HUB_CONTRACT_ADDR {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if *contract_addr == MOCK_HUB_CONTRACT_ADDR
+                    && from_binary(msg) == Ok(HubQueryMsg::State {})
+                {
+                    let state = StateResponse {
+                        bluna_exchange_rate: Decimal::from_ratio(9u128, 10u128),
+                        stluna_exchange_rate: Decimal::from_ratio(11u128, 10u128),
+                        total_bond_bluna_amount: Uint128::from(900u128),
+                        total_bond_stluna_amount: Uint128::from(1100u128),
+                        last_index_modification: 0,
+                        prev_hub_balance: Uint128::zero(),
+                        last_unbonded_time: 0,
+                        last_processed_batch: 0,
+                        stluna_unreleased_rewards: Uint128::zero(),
+                        total_bond_amount: Uint128::from(900u128),
+                        exchange_rate: Decimal::from_ratio(9u128, 10u128),
+                    };
+                    SystemResult::Ok(ContractResult::from(to_binary(&state)))
+                } else if *contract_addr == MOCK_HUB_CONTRACT_ADDR {
                     let config = ConfigResponse {
                         owner: String::from("owner1"),
                         reward_dispatcher_contract: Some(String::from(
@@ -101,10 +115,18 @@ impl WasmMockQuerier {
                             .unwrap(),
                         stluna_reward_denom: "uluna".to_string(),
                         bluna_reward_denom: "uusd".to_string(),
-                        lido_fee_address: api
-                            .addr_canonicalize(&String::from(MOCK_LIDO_FEE_ADDRESS))
-                            .unwrap(),
-                        lido_fee_rate: Decimal::from_ratio(5u128, 100u128),
+                        lido_fee_recipients: vec![FeeRecipient {
+                            address: String::from(MOCK_LIDO_FEE_ADDRESS),
+                            share: Decimal::one(),
+                        }],
+                        stluna_fee_rate: Decimal::from_ratio(5u128, 100u128),
+                        bluna_fee_rate: Decimal::from_ratio(5u128, 100u128),
+                        lido_fee_in_stluna: false,
+                        max_swap_deviation: None,
+                        min_dispatch_amounts: vec![],
+                        max_dispatch_interval: 0,
+                        rewards_release_period: 0,
+                        reward_streams: vec![],
                     };
                     SystemResult::Ok(ContractResult::from(to_binary(&config)))
                 } else {
@@ -14,17 +14,26 @@
 
 use basset::hub::ExecuteMsg::CheckSlashing;
 use basset::reward::ExecuteMsg::{DecreaseBalance, IncreaseBalance};
+use basset::{permit_sign_doc, pubkey_to_address, PermitPayload};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use cosmwasm_std::{
-    coins, to_binary, Api, CosmosMsg, DepsMut, OwnedDeps, Querier, Storage, SubMsg, Uint128,
-    WasmMsg,
+    attr, coins, from_binary, to_binary, Api, Binary, CosmosMsg, Decimal, DepsMut, OwnedDeps,
+    Querier, StdError, Storage, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{
+    AllowanceResponse, BalanceResponse, Cw20ReceiveMsg, Expiration, MinterResponse,
+    TokenInfoResponse,
 };
-use cw20::{Cw20ReceiveMsg, MinterResponse, TokenInfoResponse};
 use cw20_legacy::contract::{query_minter, query_token_info};
-use cw20_legacy::msg::ExecuteMsg;
-
-use crate::contract::{execute, instantiate};
-use crate::msg::TokenInitMsg;
+use cw20_legacy::ContractError;
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{
+    BalanceInUnderlyingResponse, ExecuteMsg, PermitNonceResponse, QueryMsg,
+    TokenInfoExtendedResponse, TokenInitMsg, TotalSupplyResponse,
+};
 use crate::state::read_hub_contract;
 use crate::testing::mock_querier::{
     mock_dependencies, MOCK_HUB_CONTRACT_ADDR, MOCK_REWARD_CONTRACT_ADDR,
@@ -487,3 +496,282 @@ fn send_from() {
         .unwrap()
     );
 }
+
+fn sign_permit(key: &SigningKey, payload: &PermitPayload) -> ExecuteMsg {
+    let signature: Signature = key.sign(&permit_sign_doc(payload).unwrap());
+    ExecuteMsg::Permit {
+        owner: payload.owner.clone(),
+        spender: payload.spender.clone(),
+        amount: payload.amount,
+        expires: payload.expires,
+        nonce: payload.nonce,
+        signature: Binary::from(signature.as_ref()),
+        pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+    }
+}
+
+#[test]
+fn permit() {
+    let mut deps = mock_dependencies(&[]);
+    do_init_with_minter(
+        deps.borrow_mut(),
+        &String::from(MOCK_HUB_CONTRACT_ADDR),
+        None,
+    );
+
+    let owner_key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+    let owner = pubkey_to_address(&owner_key.verifying_key().to_bytes()).unwrap();
+    let spender = String::from("addr0001");
+    do_mint(deps.as_mut(), owner.clone(), Uint128::new(1000u128));
+
+    let env = mock_env();
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner: owner.clone(),
+        spender: spender.clone(),
+        amount: Uint128::new(300u128),
+        expires: Some(Expiration::AtHeight(env.block.height + 100)),
+        nonce: 0,
+    };
+    let relayer_info = mock_info("relayer", &[]);
+
+    // the signature must be made over the submitted permit
+    let signed_msg = sign_permit(&owner_key, &payload);
+    let msg = match signed_msg.clone() {
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+            ..
+        } => ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount: Uint128::new(1000u128),
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        },
+        _ => unreachable!(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit signature"))
+    );
+
+    // the key of another account can't sign the permits of the owner
+    let other_key = SigningKey::from_bytes(&[2u8; 32]).unwrap();
+    let msg = sign_permit(&other_key, &payload);
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err(
+            "the public key doesn't belong to the owner"
+        ))
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        relayer_info.clone(),
+        signed_msg.clone(),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Allowance {
+            owner: owner.clone(),
+            spender: spender.clone(),
+        },
+    )
+    .unwrap();
+    let allowance: AllowanceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        allowance,
+        AllowanceResponse {
+            allowance: Uint128::new(300u128),
+            expires: Expiration::AtHeight(env.block.height + 100),
+        }
+    );
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PermitNonce {
+            owner: owner.clone(),
+        },
+    )
+    .unwrap();
+    let nonce: PermitNonceResponse = from_binary(&res).unwrap();
+    assert_eq!(nonce, PermitNonceResponse { nonce: 1 });
+
+    // the permit can't be replayed
+    let res = execute(deps.as_mut(), env.clone(), relayer_info, signed_msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit nonce, expected 1"))
+    );
+
+    // the spender uses the allowance granted by the permit
+    let msg = ExecuteMsg::TransferFrom {
+        owner,
+        recipient: String::from("addr0002"),
+        amount: Uint128::new(300u128),
+    };
+    execute(deps.as_mut(), env, mock_info(&spender, &[]), msg).unwrap();
+}
+
+#[test]
+fn snapshots() {
+    let mut deps = mock_dependencies(&[]);
+    do_init_with_minter(
+        deps.borrow_mut(),
+        &String::from(MOCK_HUB_CONTRACT_ADDR),
+        None,
+    );
+    let addr1 = String::from("addr0001");
+    let addr2 = String::from("addr0002");
+    do_mint(deps.as_mut(), addr1.clone(), Uint128::new(1000u128));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&addr1, &[]),
+        ExecuteMsg::Snapshot {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // the first snapshot is taken by the hub
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_HUB_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Snapshot {},
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: addr2.clone(),
+        amount: Uint128::new(300u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+    let msg = ExecuteMsg::Transfer {
+        recipient: addr2.clone(),
+        amount: Uint128::new(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+
+    // the second snapshot is taken by the owner of the hub
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner1", &[]),
+        ExecuteMsg::Snapshot {},
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("snapshot_id", "2")));
+
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(200u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+
+    let balance_at = |address: &str, snapshot_id: u64| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BalanceAt {
+                address: String::from(address),
+                snapshot_id,
+            },
+        )
+        .unwrap();
+        let balance: BalanceResponse = from_binary(&res).unwrap();
+        balance.balance.u128()
+    };
+    assert_eq!(balance_at(&addr1, 1), 1000);
+    assert_eq!(balance_at(&addr2, 1), 0);
+    assert_eq!(balance_at(&addr1, 2), 600);
+    assert_eq!(balance_at(&addr2, 2), 400);
+
+    let total_supply_at = |snapshot_id: u64| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TotalSupplyAt { snapshot_id },
+        )
+        .unwrap();
+        let total_supply: TotalSupplyResponse = from_binary(&res).unwrap();
+        total_supply.total_supply.u128()
+    };
+    assert_eq!(total_supply_at(1), 1000);
+    assert_eq!(total_supply_at(2), 1000);
+    assert_eq!(
+        query_token_info(deps.as_ref()).unwrap().total_supply,
+        Uint128::new(800u128)
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TotalSupplyAt { snapshot_id: 3 },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("snapshot 3 doesn't exist")
+    );
+}
+
+#[test]
+fn balance_in_underlying() {
+    let mut deps = mock_dependencies(&[]);
+    do_init_with_minter(
+        deps.borrow_mut(),
+        &String::from(MOCK_HUB_CONTRACT_ADDR),
+        None,
+    );
+    let addr = String::from("addr0000");
+    let amount = Uint128::from(1000u128);
+    do_mint(deps.as_mut(), addr.clone(), amount);
+
+    let res: BalanceInUnderlyingResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BalanceInUnderlying {
+                address: addr.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BalanceInUnderlyingResponse {
+            balance: amount,
+            exchange_rate: Decimal::from_ratio(9u128, 10u128),
+            underlying_amount: Uint128::from(900u128),
+        }
+    );
+
+    let res: TokenInfoExtendedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TokenInfoExtended {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        TokenInfoExtendedResponse {
+            name: "bluna".to_string(),
+            symbol: "BLUNA".to_string(),
+            decimals: 6,
+            total_supply: amount,
+            exchange_rate: Decimal::from_ratio(9u128, 10u128),
+            total_backing: Uint128::from(900u128),
+        }
+    );
+}
@@ -15,17 +15,26 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
 
 use cw20_base::allowances::{execute_decrease_allowance, execute_increase_allowance};
 use cw20_base::contract::instantiate as cw20_init;
-use cw20_base::contract::query as cw20_query;
-use cw20_base::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw20_base::contract::{query as cw20_query, query_balance, query_token_info};
+use cw20_base::msg::{InstantiateMsg, QueryMsg as Cw20QueryMsg};
 
 use crate::handler::*;
-use crate::msg::TokenInitMsg;
-use crate::state::HUB_CONTRACT;
-use cw20::MinterResponse;
+use crate::msg::{
+    BalanceInUnderlyingResponse, ExecuteMsg, PermitNonceResponse, QueryMsg,
+    TokenInfoExtendedResponse, TokenInitMsg, TotalSupplyResponse,
+};
+use crate::querier::query_hub_state;
+use crate::state::{
+    read_balance_snapshot, read_snapshot_id, read_total_supply_snapshot, HUB_CONTRACT,
+    PERMIT_NONCES,
+};
+use cw20::{BalanceResponse, MinterResponse};
 use cw20_base::ContractError;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -108,10 +117,117 @@ pub fn execute(
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        } => execute_permit(
+            deps, env, owner, spender, amount, expires, nonce, signature, pubkey,
+        ),
+        ExecuteMsg::Snapshot {} => execute_snapshot(deps, env, info),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    cw20_query(deps, env, msg)
+    let cw20_msg = match msg {
+        QueryMsg::PermitNonce { owner } => return to_binary(&query_permit_nonce(deps, owner)?),
+        QueryMsg::BalanceAt {
+            address,
+            snapshot_id,
+        } => return to_binary(&query_balance_at(deps, address, snapshot_id)?),
+        QueryMsg::TotalSupplyAt { snapshot_id } => {
+            return to_binary(&query_total_supply_at(deps, snapshot_id)?)
+        }
+        QueryMsg::BalanceInUnderlying { address } => {
+            return to_binary(&query_balance_in_underlying(deps, address)?)
+        }
+        QueryMsg::TokenInfoExtended {} => return to_binary(&query_token_info_extended(deps)?),
+        QueryMsg::Balance { address } => Cw20QueryMsg::Balance { address },
+        QueryMsg::TokenInfo {} => Cw20QueryMsg::TokenInfo {},
+        QueryMsg::Minter {} => Cw20QueryMsg::Minter {},
+        QueryMsg::Allowance { owner, spender } => Cw20QueryMsg::Allowance { owner, spender },
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => Cw20QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        },
+        QueryMsg::AllAccounts { start_after, limit } => {
+            Cw20QueryMsg::AllAccounts { start_after, limit }
+        }
+        QueryMsg::MarketingInfo {} => Cw20QueryMsg::MarketingInfo {},
+        QueryMsg::DownloadLogo {} => Cw20QueryMsg::DownloadLogo {},
+    };
+    cw20_query(deps, env, cw20_msg)
+}
+
+fn query_permit_nonce(deps: Deps, owner: String) -> StdResult<PermitNonceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    Ok(PermitNonceResponse {
+        nonce: PERMIT_NONCES
+            .may_load(deps.storage, &owner_addr)?
+            .unwrap_or_default(),
+    })
+}
+
+fn check_snapshot_id(deps: Deps, snapshot_id: u64) -> StdResult<()> {
+    if snapshot_id == 0 || snapshot_id > read_snapshot_id(deps.storage)? {
+        return Err(StdError::generic_err(format!(
+            "snapshot {} doesn't exist",
+            snapshot_id
+        )));
+    }
+    Ok(())
+}
+
+fn query_balance_at(deps: Deps, address: String, snapshot_id: u64) -> StdResult<BalanceResponse> {
+    check_snapshot_id(deps, snapshot_id)?;
+    let address_addr = deps.api.addr_validate(&address)?;
+    match read_balance_snapshot(deps.storage, &address_addr, snapshot_id)? {
+        Some(balance) => Ok(BalanceResponse { balance }),
+        None => query_balance(deps, address),
+    }
+}
+
+fn query_total_supply_at(deps: Deps, snapshot_id: u64) -> StdResult<TotalSupplyResponse> {
+    check_snapshot_id(deps, snapshot_id)?;
+    let total_supply = match read_total_supply_snapshot(deps.storage, snapshot_id)? {
+        Some(total_supply) => total_supply,
+        None => query_token_info(deps)?.total_supply,
+    };
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+fn query_balance_in_underlying(
+    deps: Deps,
+    address: String,
+) -> StdResult<BalanceInUnderlyingResponse> {
+    let balance = query_balance(deps, address)?.balance;
+    let exchange_rate = query_hub_state(deps)?.stluna_exchange_rate;
+    Ok(BalanceInUnderlyingResponse {
+        balance,
+        exchange_rate,
+        underlying_amount: balance * exchange_rate,
+    })
+}
+
+fn query_token_info_extended(deps: Deps) -> StdResult<TokenInfoExtendedResponse> {
+    let token_info = query_token_info(deps)?;
+    let state = query_hub_state(deps)?;
+    Ok(TokenInfoExtendedResponse {
+        name: token_info.name,
+        symbol: token_info.symbol,
+        decimals: token_info.decimals,
+        total_supply: token_info.total_supply,
+        exchange_rate: state.stluna_exchange_rate,
+        total_backing: state.total_bond_stluna_amount,
+    })
 }
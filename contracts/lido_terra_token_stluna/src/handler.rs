@@ -13,10 +13,12 @@
 // limitations under the License.
 
 use basset::hub::ExecuteMsg::CheckSlashing;
+use basset::{verify_permit, PermitPayload};
 use cosmwasm_std::{
-    to_binary, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128, WasmMsg,
+    attr, to_binary, Api, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
-use cw20::Logo;
+use cw20::{AllowanceResponse, Expiration, Logo};
 use cw20_base::allowances::{
     execute_burn_from as cw20_burn_from, execute_send_from as cw20_send_from,
     execute_transfer_from as cw20_transfer_from,
@@ -26,9 +28,14 @@ use cw20_base::contract::{
     execute_transfer as cw20_transfer, execute_update_marketing as cw20_update_marketing,
     execute_upload_logo as cw20_upload_logo,
 };
+use cw20_base::state::{ALLOWANCES, BALANCES, TOKEN_INFO};
 use cw20_base::ContractError;
 
-use crate::state::HUB_CONTRACT;
+use crate::querier::query_hub_owner;
+use crate::state::{
+    checkpoint_balance, checkpoint_total_supply, read_snapshot_id, HUB_CONTRACT, PERMIT_NONCES,
+    SNAPSHOT_ID,
+};
 
 pub fn execute_transfer(
     deps: DepsMut,
@@ -37,6 +44,12 @@ pub fn execute_transfer(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    checkpoint_snapshots(
+        deps.storage,
+        deps.api,
+        &[info.sender.as_str(), &recipient],
+        false,
+    )?;
     cw20_transfer(deps, env, info, recipient, amount)
 }
 
@@ -60,6 +73,7 @@ pub fn execute_burn(
             funds: vec![],
         })))
     }
+    checkpoint_snapshots(deps.storage, deps.api, &[info.sender.as_str()], true)?;
     let res = cw20_burn(deps, env, info, amount)?;
 
     Ok(Response::new()
@@ -74,6 +88,7 @@ pub fn execute_mint(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    checkpoint_snapshots(deps.storage, deps.api, &[&recipient], true)?;
     cw20_mint(deps, env, info, recipient, amount)
 }
 
@@ -85,6 +100,12 @@ pub fn execute_send(
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
+    checkpoint_snapshots(
+        deps.storage,
+        deps.api,
+        &[info.sender.as_str(), &contract],
+        false,
+    )?;
     cw20_send(deps, env, info, contract, amount, msg)
 }
 
@@ -96,6 +117,7 @@ pub fn execute_transfer_from(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner, &recipient], false)?;
     cw20_transfer_from(deps, env, info, owner, recipient, amount)
 }
 
@@ -108,6 +130,7 @@ pub fn execute_burn_from(
 ) -> Result<Response, ContractError> {
     let hub_contract = deps.api.addr_humanize(&HUB_CONTRACT.load(deps.storage)?)?;
 
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner], true)?;
     let res = cw20_burn_from(deps, env, info, owner, amount)?;
     let messages = vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: hub_contract.to_string(),
@@ -128,6 +151,7 @@ pub fn execute_send_from(
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
+    checkpoint_snapshots(deps.storage, deps.api, &[&owner, &contract], false)?;
     cw20_send_from(deps, env, info, owner, contract, amount, msg)
 }
 
@@ -150,3 +174,115 @@ pub fn execute_upload_logo(
 ) -> Result<Response, ContractError> {
     cw20_upload_logo(deps, env, info, logo)
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    nonce: u64,
+    signature: Binary,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if let Some(expires) = expires {
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+    }
+
+    let expected_nonce = PERMIT_NONCES
+        .may_load(deps.storage, &owner_addr)?
+        .unwrap_or_default();
+    if nonce != expected_nonce {
+        return Err(StdError::generic_err(format!(
+            "invalid permit nonce, expected {}",
+            expected_nonce
+        ))
+        .into());
+    }
+
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id,
+        contract: env.contract.address.to_string(),
+        owner: owner_addr.to_string(),
+        spender: spender_addr.to_string(),
+        amount,
+        expires,
+        nonce,
+    };
+    verify_permit(deps.api, &payload, &signature, &pubkey)?;
+
+    PERMIT_NONCES.save(deps.storage, &owner_addr, &(nonce + 1))?;
+    ALLOWANCES.save(
+        deps.storage,
+        (&owner_addr, &spender_addr),
+        &AllowanceResponse {
+            allowance: amount,
+            expires: expires.unwrap_or_default(),
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]))
+}
+
+/// Takes a snapshot of the balances and the total supply, the snapshot id is returned
+/// in the attributes
+pub fn execute_snapshot(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let hub_contract = deps.api.addr_humanize(&HUB_CONTRACT.load(deps.storage)?)?;
+    if info.sender != hub_contract && info.sender != query_hub_owner(deps.as_ref(), &hub_contract)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let snapshot_id = read_snapshot_id(deps.storage)? + 1;
+    SNAPSHOT_ID.save(deps.storage, &snapshot_id)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "snapshot"),
+        attr("snapshot_id", snapshot_id.to_string()),
+        attr("height", env.block.height.to_string()),
+    ]))
+}
+
+/// Records the balances of the accounts and the total supply if it's going to change
+/// at the last snapshot before the cw20 handlers change them
+fn checkpoint_snapshots(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    accounts: &[&str],
+    total_supply: bool,
+) -> StdResult<()> {
+    if read_snapshot_id(storage)? == 0 {
+        return Ok(());
+    }
+    for account in accounts {
+        let account_addr = api.addr_validate(account)?;
+        let balance = BALANCES
+            .may_load(storage, &account_addr)?
+            .unwrap_or_default();
+        checkpoint_balance(storage, &account_addr, balance)?;
+    }
+    if total_supply {
+        let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+        checkpoint_total_supply(storage, total_supply)?;
+    }
+    Ok(())
+}
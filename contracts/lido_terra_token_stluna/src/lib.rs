@@ -17,3 +17,7 @@ pub mod msg;
 pub mod state;
 
 mod handler;
+mod querier;
+
+#[cfg(test)]
+mod testing;
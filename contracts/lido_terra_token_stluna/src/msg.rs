@@ -12,7 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cw20::Cw20Coin;
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20::{Cw20Coin, Expiration, Logo};
 use cw20_base::msg::InstantiateMarketingInfo;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -26,3 +27,144 @@ pub struct TokenInitMsg {
     pub hub_contract: String,
     pub marketing: Option<InstantiateMarketingInfo>,
 }
+
+/// The cw20 messages of `cw20_base` extended with the permits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Burn {
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    BurnFrom {
+        owner: String,
+        amount: Uint128,
+    },
+    Mint {
+        recipient: String,
+        amount: Uint128,
+    },
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<String>,
+    },
+    UploadLogo(Logo),
+    /// Sets the allowance of the spender to the amount on behalf of the owner
+    /// who signed the ADR-036 sign doc of the `PermitPayload` off-chain.
+    /// Anyone can submit the permit, the nonce must be the next permit nonce of the owner.
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        nonce: u64,
+        signature: Binary,
+        pubkey: Binary,
+    },
+    /// Takes a snapshot of the balances and the total supply.
+    /// Only the hub or its owner can take the snapshots.
+    Snapshot {},
+}
+
+/// The cw20 queries of `cw20_base` extended with the permit nonces
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Balance {
+        address: String,
+    },
+    TokenInfo {},
+    Minter {},
+    Allowance {
+        owner: String,
+        spender: String,
+    },
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    MarketingInfo {},
+    DownloadLogo {},
+    /// The nonce the next permit of the owner must be signed with
+    PermitNonce {
+        owner: String,
+    },
+    /// The balance of the address at the snapshot.
+    /// Return type: cw20::BalanceResponse.
+    BalanceAt {
+        address: String,
+        snapshot_id: u64,
+    },
+    /// The total supply at the snapshot
+    TotalSupplyAt {
+        snapshot_id: u64,
+    },
+    /// The balance of the address and its value in Luna at the current stLuna exchange rate
+    BalanceInUnderlying {
+        address: String,
+    },
+    /// The token info extended with the stLuna exchange rate and the Luna backing the supply
+    TokenInfoExtended {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalanceInUnderlyingResponse {
+    pub balance: Uint128,
+    pub exchange_rate: Decimal,
+    pub underlying_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfoExtendedResponse {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Uint128,
+    pub exchange_rate: Decimal,
+    pub total_backing: Uint128,
+}
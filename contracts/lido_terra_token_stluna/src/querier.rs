@@ -0,0 +1,37 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
+
+use basset::hub::{ConfigResponse, QueryMsg as HubQueryMsg, StateResponse};
+
+use crate::state::HUB_CONTRACT;
+
+pub fn query_hub_owner(deps: Deps, hub_address: &Addr) -> StdResult<Addr> {
+    let config: ConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::Config {})?,
+    }))?;
+
+    deps.api.addr_validate(config.owner.as_str())
+}
+
+pub fn query_hub_state(deps: Deps) -> StdResult<StateResponse> {
+    let hub_address = deps.api.addr_humanize(&HUB_CONTRACT.load(deps.storage)?)?;
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&HubQueryMsg::State {})?,
+    }))
+}
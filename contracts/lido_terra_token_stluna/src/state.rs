@@ -12,7 +12,81 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_std::CanonicalAddr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, CanonicalAddr, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Item, Map};
 
 pub const HUB_CONTRACT: Item<CanonicalAddr> = Item::new("hub_contract");
+// the nonce the next permit of the owner must be signed with
+pub const PERMIT_NONCES: Map<&Addr, u64> = Map::new("permit_nonces");
+// the id of the last snapshot, zero if no snapshot was taken
+pub const SNAPSHOT_ID: Item<u64> = Item::new("snapshot_id");
+// the balances of the accounts at the snapshots, a balance is recorded under the last snapshot
+// before it changes for the first time after the snapshot
+pub const BALANCE_SNAPSHOTS: Map<(&Addr, &[u8]), Uint128> = Map::new("balance_snapshots");
+pub const TOTAL_SUPPLY_SNAPSHOTS: Map<&[u8], Uint128> = Map::new("total_supply_snapshots");
+
+pub fn read_snapshot_id(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(SNAPSHOT_ID.may_load(storage)?.unwrap_or_default())
+}
+
+/// Records the balance of the account at the last snapshot unless it has already been changed
+/// since the snapshot. Must be called before the balance changes.
+pub fn checkpoint_balance(
+    storage: &mut dyn Storage,
+    account: &Addr,
+    balance: Uint128,
+) -> StdResult<()> {
+    let snapshot_id = read_snapshot_id(storage)?;
+    if snapshot_id == 0 {
+        return Ok(());
+    }
+    let key = (account, &snapshot_id.to_be_bytes()[..]);
+    if !BALANCE_SNAPSHOTS.has(storage, key) {
+        BALANCE_SNAPSHOTS.save(storage, key, &balance)?;
+    }
+    Ok(())
+}
+
+/// Records the total supply at the last snapshot unless it has already been changed
+/// since the snapshot. Must be called before the total supply changes.
+pub fn checkpoint_total_supply(storage: &mut dyn Storage, total_supply: Uint128) -> StdResult<()> {
+    let snapshot_id = read_snapshot_id(storage)?;
+    if snapshot_id == 0 {
+        return Ok(());
+    }
+    let key = snapshot_id.to_be_bytes();
+    if !TOTAL_SUPPLY_SNAPSHOTS.has(storage, &key) {
+        TOTAL_SUPPLY_SNAPSHOTS.save(storage, &key, &total_supply)?;
+    }
+    Ok(())
+}
+
+/// Returns the balance of the account at the snapshot,
+/// none if the balance hasn't changed since the snapshot
+pub fn read_balance_snapshot(
+    storage: &dyn Storage,
+    account: &Addr,
+    snapshot_id: u64,
+) -> StdResult<Option<Uint128>> {
+    let start = Bound::inclusive(snapshot_id.to_be_bytes().to_vec());
+    BALANCE_SNAPSHOTS
+        .prefix(account)
+        .range(storage, Some(start), None, Order::Ascending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
+
+/// Returns the total supply at the snapshot,
+/// none if the total supply hasn't changed since the snapshot
+pub fn read_total_supply_snapshot(
+    storage: &dyn Storage,
+    snapshot_id: u64,
+) -> StdResult<Option<Uint128>> {
+    let start = Bound::inclusive(snapshot_id.to_be_bytes().to_vec());
+    TOTAL_SUPPLY_SNAPSHOTS
+        .range(storage, Some(start), None, Order::Ascending)
+        .next()
+        .map(|item| Ok(item?.1))
+        .transpose()
+}
@@ -0,0 +1,441 @@
+// Copyright 2021 Lido
+//
+// Licensedicensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use basset::{permit_sign_doc, pubkey_to_address, PermitPayload};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{attr, from_binary, Binary, Decimal, Deps, DepsMut, StdError, Uint128};
+use cw20::{AllowanceResponse, BalanceResponse, Expiration, MinterResponse, TokenInfoResponse};
+use cw20_base::contract::{query_minter, query_token_info};
+use cw20_base::ContractError;
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{
+    BalanceInUnderlyingResponse, ExecuteMsg, PermitNonceResponse, QueryMsg,
+    TokenInfoExtendedResponse, TokenInitMsg, TotalSupplyResponse,
+};
+use crate::testing::mock_querier::{
+    mock_dependencies, MOCK_HUB_CONTRACT_ADDR, MOCK_HUB_OWNER_ADDR,
+};
+
+// this will set up the init for other tests
+fn do_init(deps: DepsMut) {
+    let init_msg = TokenInitMsg {
+        name: "stluna".to_string(),
+        symbol: "STLUNA".to_string(),
+        decimals: 6,
+        initial_balances: vec![],
+        hub_contract: String::from(MOCK_HUB_CONTRACT_ADDR),
+        marketing: None,
+    };
+
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = instantiate(deps, mock_env(), info, init_msg).unwrap();
+    assert_eq!(0, res.messages.len());
+}
+
+fn do_mint(deps: DepsMut, addr: String, amount: Uint128) {
+    let msg = ExecuteMsg::Mint {
+        recipient: addr,
+        amount,
+    };
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(deps, mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+
+    assert_eq!(
+        query_token_info(deps.as_ref()).unwrap(),
+        TokenInfoResponse {
+            name: "stluna".to_string(),
+            symbol: "STLUNA".to_string(),
+            decimals: 6,
+            total_supply: Uint128::zero(),
+        }
+    );
+    assert_eq!(
+        query_minter(deps.as_ref()).unwrap(),
+        Some(MinterResponse {
+            minter: String::from(MOCK_HUB_CONTRACT_ADDR),
+            cap: None,
+        })
+    );
+}
+
+fn sign_permit(key: &SigningKey, payload: &PermitPayload) -> ExecuteMsg {
+    let signature: Signature = key.sign(&permit_sign_doc(payload).unwrap());
+    ExecuteMsg::Permit {
+        owner: payload.owner.clone(),
+        spender: payload.spender.clone(),
+        amount: payload.amount,
+        expires: payload.expires,
+        nonce: payload.nonce,
+        signature: Binary::from(signature.as_ref()),
+        pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+    }
+}
+
+#[test]
+fn permit() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+
+    let owner_key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+    let owner = pubkey_to_address(&owner_key.verifying_key().to_bytes()).unwrap();
+    let spender = String::from("addr0001");
+    do_mint(deps.as_mut(), owner.clone(), Uint128::new(1000u128));
+
+    let env = mock_env();
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner: owner.clone(),
+        spender: spender.clone(),
+        amount: Uint128::new(300u128),
+        expires: Some(Expiration::AtHeight(env.block.height + 100)),
+        nonce: 0,
+    };
+    let relayer_info = mock_info("relayer", &[]);
+
+    // the signature must be made over the submitted permit
+    let signed_msg = sign_permit(&owner_key, &payload);
+    let msg = match signed_msg.clone() {
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+            ..
+        } => ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount: Uint128::new(1000u128),
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        },
+        _ => unreachable!(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit signature"))
+    );
+
+    // the key of another account can't sign the permits of the owner
+    let other_key = SigningKey::from_bytes(&[2u8; 32]).unwrap();
+    let msg = sign_permit(&other_key, &payload);
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err(
+            "the public key doesn't belong to the owner"
+        ))
+    );
+
+    // the permits signed for another token or another chain are rejected
+    let msg = sign_permit(
+        &owner_key,
+        &PermitPayload {
+            contract: String::from("bluna_token"),
+            ..payload.clone()
+        },
+    );
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit signature"))
+    );
+    let msg = sign_permit(
+        &owner_key,
+        &PermitPayload {
+            chain_id: String::from("bombay-12"),
+            ..payload.clone()
+        },
+    );
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit signature"))
+    );
+
+    // the expired permits are rejected
+    let msg = sign_permit(
+        &owner_key,
+        &PermitPayload {
+            expires: Some(Expiration::AtHeight(env.block.height)),
+            ..payload.clone()
+        },
+    );
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Expired {});
+
+    // the nonce must be the next permit nonce of the owner
+    let msg = sign_permit(
+        &owner_key,
+        &PermitPayload {
+            nonce: 1,
+            ..payload
+        },
+    );
+    let res = execute(deps.as_mut(), env.clone(), relayer_info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit nonce, expected 0"))
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        relayer_info.clone(),
+        signed_msg.clone(),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Allowance {
+            owner: owner.clone(),
+            spender: spender.clone(),
+        },
+    )
+    .unwrap();
+    let allowance: AllowanceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        allowance,
+        AllowanceResponse {
+            allowance: Uint128::new(300u128),
+            expires: Expiration::AtHeight(env.block.height + 100),
+        }
+    );
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PermitNonce {
+            owner: owner.clone(),
+        },
+    )
+    .unwrap();
+    let nonce: PermitNonceResponse = from_binary(&res).unwrap();
+    assert_eq!(nonce, PermitNonceResponse { nonce: 1 });
+
+    // the permit can't be replayed
+    let res = execute(deps.as_mut(), env.clone(), relayer_info, signed_msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Std(StdError::generic_err("invalid permit nonce, expected 1"))
+    );
+
+    // the spender uses the allowance granted by the permit
+    let msg = ExecuteMsg::TransferFrom {
+        owner,
+        recipient: String::from("addr0002"),
+        amount: Uint128::new(300u128),
+    };
+    execute(deps.as_mut(), env, mock_info(&spender, &[]), msg).unwrap();
+}
+
+#[test]
+fn snapshots() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+    let addr1 = String::from("addr0001");
+    let addr2 = String::from("addr0002");
+    let addr3 = String::from("addr0003");
+    let contract = String::from("contract0000");
+    do_mint(deps.as_mut(), addr1.clone(), Uint128::new(1000u128));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&addr1, &[]),
+        ExecuteMsg::Snapshot {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // the first snapshot is taken by the hub
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_HUB_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Snapshot {},
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: addr2.clone(),
+        amount: Uint128::new(300u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+    let msg = ExecuteMsg::Send {
+        contract: contract.clone(),
+        amount: Uint128::new(100u128),
+        msg: Binary::from(b"{}"),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: addr2.clone(),
+        amount: Uint128::new(150u128),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+
+    // the second snapshot is taken by the owner of the hub
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_HUB_OWNER_ADDR, &[]),
+        ExecuteMsg::Snapshot {},
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("snapshot_id", "2")));
+
+    let msg = ExecuteMsg::TransferFrom {
+        owner: addr1.clone(),
+        recipient: addr3.clone(),
+        amount: Uint128::new(50u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SendFrom {
+        owner: addr1.clone(),
+        contract: contract.clone(),
+        amount: Uint128::new(50u128),
+        msg: Binary::from(b"{}"),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+    let msg = ExecuteMsg::BurnFrom {
+        owner: addr1.clone(),
+        amount: Uint128::new(50u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+    do_mint(deps.as_mut(), addr3.clone(), Uint128::new(100u128));
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(200u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+
+    let balance_at = |deps: Deps, address: &str, snapshot_id: u64| {
+        let res = query(
+            deps,
+            mock_env(),
+            QueryMsg::BalanceAt {
+                address: String::from(address),
+                snapshot_id,
+            },
+        )
+        .unwrap();
+        let balance: BalanceResponse = from_binary(&res).unwrap();
+        balance.balance.u128()
+    };
+    assert_eq!(balance_at(deps.as_ref(), &addr1, 1), 1000);
+    assert_eq!(balance_at(deps.as_ref(), &addr2, 1), 0);
+    assert_eq!(balance_at(deps.as_ref(), &contract, 1), 0);
+    assert_eq!(balance_at(deps.as_ref(), &addr3, 1), 0);
+    assert_eq!(balance_at(deps.as_ref(), &addr1, 2), 600);
+    assert_eq!(balance_at(deps.as_ref(), &addr2, 2), 300);
+    assert_eq!(balance_at(deps.as_ref(), &contract, 2), 100);
+    assert_eq!(balance_at(deps.as_ref(), &addr3, 2), 0);
+
+    let total_supply_at = |deps: Deps, snapshot_id: u64| {
+        let res = query(deps, mock_env(), QueryMsg::TotalSupplyAt { snapshot_id }).unwrap();
+        let total_supply: TotalSupplyResponse = from_binary(&res).unwrap();
+        total_supply.total_supply.u128()
+    };
+    assert_eq!(total_supply_at(deps.as_ref(), 1), 1000);
+    assert_eq!(total_supply_at(deps.as_ref(), 2), 1000);
+    assert_eq!(
+        query_token_info(deps.as_ref()).unwrap().total_supply,
+        Uint128::new(850u128)
+    );
+
+    // the balances unchanged since the last snapshot are the current ones
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_HUB_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Snapshot {},
+    )
+    .unwrap();
+    assert_eq!(balance_at(deps.as_ref(), &addr1, 3), 450);
+    assert_eq!(balance_at(deps.as_ref(), &addr2, 3), 100);
+    assert_eq!(balance_at(deps.as_ref(), &contract, 3), 150);
+    assert_eq!(balance_at(deps.as_ref(), &addr3, 3), 150);
+    assert_eq!(total_supply_at(deps.as_ref(), 3), 850);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TotalSupplyAt { snapshot_id: 4 },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("snapshot 4 doesn't exist")
+    );
+}
+
+#[test]
+fn balance_in_underlying() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+    let addr = String::from("addr0000");
+    let amount = Uint128::from(1000u128);
+    do_mint(deps.as_mut(), addr.clone(), amount);
+
+    // the hub reports the different rates and backings of bLuna and stLuna,
+    // the stLuna ones must be used
+    let res: BalanceInUnderlyingResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BalanceInUnderlying {
+                address: addr.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BalanceInUnderlyingResponse {
+            balance: amount,
+            exchange_rate: Decimal::from_ratio(11u128, 10u128),
+            underlying_amount: Uint128::from(1100u128),
+        }
+    );
+
+    let res: TokenInfoExtendedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TokenInfoExtended {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        TokenInfoExtendedResponse {
+            name: "stluna".to_string(),
+            symbol: "STLUNA".to_string(),
+            decimals: 6,
+            total_supply: amount,
+            exchange_rate: Decimal::from_ratio(11u128, 10u128),
+            total_backing: Uint128::from(1100u128),
+        }
+    );
+}
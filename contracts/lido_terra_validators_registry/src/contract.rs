@@ -18,13 +18,15 @@ use std::collections::HashMap;
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    to_binary, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Uint128, WasmMsg,
+    to_binary, Addr, Binary, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Order, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 
 use crate::common::calculate_delegations;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::registry::{Config, Validator, ValidatorResponse, CONFIG, REGISTRY};
+use crate::registry::{
+    Config, Hub, HubResponse, Validator, ValidatorResponse, CONFIG, HUBS, REGISTRY,
+};
 use basset::hub::ExecuteMsg::{RedelegateProxy, UpdateGlobalIndex};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -34,13 +36,15 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    let hub_raw = deps.api.addr_canonicalize(msg.hub_contract.as_str())?;
     CONFIG.save(
         deps.storage,
         &Config {
             owner: deps.api.addr_canonicalize(info.sender.as_str())?,
-            hub_contract: deps.api.addr_canonicalize(msg.hub_contract.as_str())?,
+            hub_contract: hub_raw.clone(),
         },
     )?;
+    HUBS.save(deps.storage, hub_raw.as_slice(), &Hub::default())?;
 
     for v in msg.registry {
         REGISTRY.save(deps.storage, v.address.as_str().as_bytes(), &v)?;
@@ -58,10 +62,17 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             owner,
             hub_contract,
         } => execute_update_config(deps, env, info, owner, hub_contract),
+        ExecuteMsg::AddHub { hub_contract } => add_hub(deps, env, info, hub_contract),
+        ExecuteMsg::RemoveHub { hub_contract } => remove_hub(deps, env, info, hub_contract),
+        ExecuteMsg::UpdateExcludedValidators {
+            hub_contract,
+            excluded_validators,
+        } => update_excluded_validators(deps, env, info, hub_contract, excluded_validators),
     }
 }
 
 /// Update the config. Update the owner and hub contract address.
+/// The previous main hub is no longer served by the registry after the update.
 /// Only creator/owner is allowed to execute
 pub fn execute_update_config(
     deps: DepsMut,
@@ -89,6 +100,15 @@ pub fn execute_update_config(
     if let Some(hub) = hub_contract {
         let hub_raw = deps.api.addr_canonicalize(&hub)?;
 
+        // the new main hub takes over the settings of the old one unless it's already registered
+        let old_hub = HUBS
+            .may_load(deps.storage, config.hub_contract.as_slice())?
+            .unwrap_or_default();
+        HUBS.remove(deps.storage, config.hub_contract.as_slice());
+        if HUBS.may_load(deps.storage, hub_raw.as_slice())?.is_none() {
+            HUBS.save(deps.storage, hub_raw.as_slice(), &old_hub)?;
+        }
+
         CONFIG.update(deps.storage, |mut last_config| -> StdResult<_> {
             last_config.hub_contract = hub_raw;
             Ok(last_config)
@@ -98,6 +118,115 @@ pub fn execute_update_config(
     Ok(Response::default())
 }
 
+/// Register one more hub to be served by the registry.
+/// Only creator/owner is allowed to execute
+pub fn add_hub(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    hub_contract: String,
+) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner_address = deps.api.addr_humanize(&config.owner)?;
+    if info.sender != owner_address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let hub_raw = deps.api.addr_canonicalize(&hub_contract)?;
+    if HUBS.may_load(deps.storage, hub_raw.as_slice())?.is_some() {
+        return Err(StdError::generic_err("the hub is already registered"));
+    }
+    HUBS.save(deps.storage, hub_raw.as_slice(), &Hub::default())?;
+
+    Ok(Response::default())
+}
+
+/// Stop serving the hub. The main hub can only be replaced via UpdateConfig.
+/// Only creator/owner is allowed to execute
+pub fn remove_hub(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    hub_contract: String,
+) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner_address = deps.api.addr_humanize(&config.owner)?;
+    if info.sender != owner_address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let hub_raw = deps.api.addr_canonicalize(&hub_contract)?;
+    if hub_raw == config.hub_contract {
+        return Err(StdError::generic_err("cannot remove the main hub"));
+    }
+    load_hub(deps.as_ref(), &hub_raw)?;
+    HUBS.remove(deps.storage, hub_raw.as_slice());
+
+    Ok(Response::default())
+}
+
+/// Replace the list of validators the hub must not delegate to.
+/// The hub's delegations to newly excluded validators are redelegated to the rest of the registry.
+/// Only creator/owner is allowed to execute
+pub fn update_excluded_validators(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    hub_contract: String,
+    mut excluded_validators: Vec<String>,
+) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner_address = deps.api.addr_humanize(&config.owner)?;
+    if info.sender != owner_address {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let hub_raw = deps.api.addr_canonicalize(&hub_contract)?;
+    let mut hub = load_hub(deps.as_ref(), &hub_raw)?;
+
+    excluded_validators.sort();
+    excluded_validators.dedup();
+    for validator_address in excluded_validators.iter() {
+        if !REGISTRY.has(deps.storage, validator_address.as_bytes()) {
+            return Err(StdError::generic_err(format!(
+                "Validator {} is not in the registry",
+                validator_address
+            )));
+        }
+    }
+    let newly_excluded: Vec<String> = excluded_validators
+        .iter()
+        .filter(|v| !hub.excluded_validators.contains(v))
+        .cloned()
+        .collect();
+
+    hub.excluded_validators = excluded_validators;
+    HUBS.save(deps.storage, hub_raw.as_slice(), &hub)?;
+
+    let hub_address = deps.api.addr_humanize(&hub_raw)?;
+    let mut validators = query_validators(deps.as_ref(), &hub_address)?;
+    if validators.is_empty() {
+        return Err(StdError::generic_err(
+            "Cannot exclude all the validators in the registry",
+        ));
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for validator_address in newly_excluded {
+        messages.append(&mut redelegate_messages(
+            deps.as_ref(),
+            &hub_address,
+            validator_address,
+            &mut validators,
+        )?);
+    }
+    if !messages.is_empty() {
+        messages.push(update_global_index_message(&hub_address)?);
+    }
+
+    Ok(Response::new().add_messages(messages))
+}
+
 pub fn add_validator(
     deps: DepsMut,
     _env: Env,
@@ -106,8 +235,12 @@ pub fn add_validator(
 ) -> StdResult<Response> {
     let config = CONFIG.load(deps.storage)?;
     let owner_address = deps.api.addr_humanize(&config.owner)?;
-    let hub_address = deps.api.addr_humanize(&config.hub_contract)?;
-    if info.sender != owner_address && info.sender != hub_address {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if info.sender != owner_address
+        && HUBS
+            .may_load(deps.storage, sender_raw.as_slice())?
+            .is_none()
+    {
         return Err(StdError::generic_err("unauthorized"));
     }
 
@@ -133,81 +266,160 @@ pub fn remove_validator(
 
     REGISTRY.remove(deps.storage, validator_address.as_str().as_bytes());
 
-    let mut validators = query_validators(deps.as_ref())?;
-    if validators.is_empty() {
+    if REGISTRY
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_none()
+    {
         return Err(StdError::generic_err(
             "Cannot remove the last validator in the registry",
         ));
     }
-    validators.sort_by(|v1, v2| v1.total_delegated.cmp(&v2.total_delegated));
 
-    let hub_address = deps.api.addr_humanize(&config.hub_contract)?;
+    let hubs = HUBS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| Ok(CanonicalAddr::from(item?.0)))
+        .collect::<StdResult<Vec<CanonicalAddr>>>()?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for hub_raw in hubs {
+        // the removed validator doesn't need to be excluded anymore
+        let mut hub = load_hub(deps.as_ref(), &hub_raw)?;
+        if hub.excluded_validators.contains(&validator_address) {
+            hub.excluded_validators.retain(|v| *v != validator_address);
+            HUBS.save(deps.storage, hub_raw.as_slice(), &hub)?;
+        }
+
+        let hub_address = deps.api.addr_humanize(&hub_raw)?;
+        let mut validators = query_validators(deps.as_ref(), &hub_address)?;
+
+        let mut redelegate_msgs = if validators.is_empty() {
+            // nowhere to move the delegation: the hub has excluded the rest of the registry,
+            // so the removal is only allowed if the hub has nothing delegated to the validator
+            if has_delegation(deps.as_ref(), &hub_address, &validator_address) {
+                return Err(StdError::generic_err(format!(
+                    "Cannot remove the last validator available for the hub {}",
+                    hub_address
+                )));
+            }
+            vec![]
+        } else {
+            redelegate_messages(
+                deps.as_ref(),
+                &hub_address,
+                validator_address.clone(),
+                &mut validators,
+            )?
+        };
+        if !redelegate_msgs.is_empty() {
+            messages.append(&mut redelegate_msgs);
+            messages.push(update_global_index_message(&hub_address)?);
+        }
+    }
+
+    let res = Response::new().add_messages(messages);
+    Ok(res)
+}
 
+/// Returns the RedelegateProxy message that moves the hub's delegation from `src_validator`
+/// to `validators`, if there is anything to move.
+/// `validators` are updated with the redelegated amounts, so the function can be called
+/// several times in a row for different source validators.
+fn redelegate_messages(
+    deps: Deps,
+    hub_address: &Addr,
+    src_validator: String,
+    validators: &mut [ValidatorResponse],
+) -> StdResult<Vec<CosmosMsg>> {
     let query = deps
         .querier
-        .query_delegation(hub_address.clone(), validator_address.clone());
+        .query_delegation(hub_address.clone(), src_validator.clone());
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    if let Ok(q) = query {
-        let delegated_amount = q;
+    if let Ok(Some(delegation)) = query {
+        // Terra core returns zero if there is another active redelegation
+        // That means we cannot start a new redelegation, so we only remove a validator from
+        // the registry.
+        // We'll do a redelegation manually later by sending RedelegateProxy to the hub
+        if delegation.can_redelegate.amount < delegation.amount.amount {
+            return Ok(messages);
+        }
+
+        validators.sort_by(|v1, v2| v1.total_delegated.cmp(&v2.total_delegated));
+        let (_, delegations) = calculate_delegations(delegation.amount.amount, validators)?;
 
         let mut redelegations: Vec<(String, Coin)> = vec![];
-        if let Some(delegation) = delegated_amount {
-            // Terra core returns zero if there is another active redelegation
-            // That means we cannot start a new redelegation, so we only remove a validator from
-            // the registry.
-            // We'll do a redelegation manually later by sending RedelegateProxy to the hub
-            if delegation.can_redelegate.amount < delegation.amount.amount {
-                return StdResult::Ok(Response::new());
+        for i in 0..delegations.len() {
+            if delegations[i].is_zero() {
+                continue;
             }
+            validators[i].total_delegated += delegations[i];
+            redelegations.push((
+                validators[i].address.clone(),
+                Coin::new(delegations[i].u128(), delegation.amount.denom.as_str()),
+            ));
+        }
 
-            let (_, delegations) =
-                calculate_delegations(delegation.amount.amount, validators.as_slice())?;
+        let regelegate_msg = RedelegateProxy {
+            src_validator,
+            redelegations,
+        };
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub_address.to_string(),
+            msg: to_binary(&regelegate_msg)?,
+            funds: vec![],
+        }));
+    }
 
-            for i in 0..delegations.len() {
-                if delegations[i].is_zero() {
-                    continue;
-                }
-                redelegations.push((
-                    validators[i].address.clone(),
-                    Coin::new(delegations[i].u128(), delegation.amount.denom.as_str()),
-                ));
-            }
+    Ok(messages)
+}
 
-            let regelegate_msg = RedelegateProxy {
-                src_validator: validator_address,
-                redelegations,
-            };
-            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: hub_address.clone().into_string(),
-                msg: to_binary(&regelegate_msg)?,
-                funds: vec![],
-            }));
-
-            let msg = UpdateGlobalIndex {
-                airdrop_hooks: None,
-            };
-            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: hub_address.into_string(),
-                msg: to_binary(&msg)?,
-                funds: vec![],
-            }));
-        }
+fn has_delegation(deps: Deps, hub_address: &Addr, validator_address: &str) -> bool {
+    // see the comment in query_validators() on why we don't use query_delegation() here
+    match deps.querier.query_all_delegations(hub_address) {
+        Ok(delegations) => delegations
+            .iter()
+            .any(|d| d.validator == validator_address && !d.amount.amount.is_zero()),
+        Err(_) => false,
     }
+}
 
-    let res = Response::new().add_messages(messages);
-    Ok(res)
+fn update_global_index_message(hub_address: &Addr) -> StdResult<CosmosMsg> {
+    let msg = UpdateGlobalIndex {
+        airdrop_hooks: None,
+    };
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: hub_address.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    }))
+}
+
+fn load_hub(deps: Deps, hub_raw: &CanonicalAddr) -> StdResult<Hub> {
+    HUBS.may_load(deps.storage, hub_raw.as_slice())?
+        .ok_or_else(|| StdError::generic_err("the hub is not registered"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetValidatorsForDelegation {} => {
-            let mut validators = query_validators(deps)?;
+        QueryMsg::GetValidatorsForDelegation { hub } => {
+            let hub_address = match hub {
+                Some(h) => {
+                    let hub_raw = deps.api.addr_canonicalize(&h)?;
+                    load_hub(deps, &hub_raw)?;
+                    deps.api.addr_humanize(&hub_raw)?
+                }
+                None => deps
+                    .api
+                    .addr_humanize(&CONFIG.load(deps.storage)?.hub_contract)?,
+            };
+            let mut validators = query_validators(deps, &hub_address)?;
             validators.sort_by(|v1, v2| v1.total_delegated.cmp(&v2.total_delegated));
             to_binary(&validators)
         }
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Hubs {} => to_binary(&query_hubs(deps)?),
     }
 }
 
@@ -216,20 +428,40 @@ fn query_config(deps: Deps) -> StdResult<Config> {
     Ok(config)
 }
 
-fn query_validators(deps: Deps) -> StdResult<Vec<ValidatorResponse>> {
-    let config = CONFIG.load(deps.storage)?;
-    let hub_address = deps.api.addr_humanize(&config.hub_contract)?;
+fn query_hubs(deps: Deps) -> StdResult<Vec<HubResponse>> {
+    HUBS.range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (k, hub) = item?;
+            Ok(HubResponse {
+                address: deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string(),
+                excluded_validators: hub.excluded_validators,
+            })
+        })
+        .collect()
+}
+
+/// Returns the registry validators available to the hub along with the hub's delegations to them
+fn query_validators(deps: Deps, hub_address: &Addr) -> StdResult<Vec<ValidatorResponse>> {
+    let hub_raw = deps.api.addr_canonicalize(hub_address.as_str())?;
+    let excluded_validators = HUBS
+        .may_load(deps.storage, hub_raw.as_slice())?
+        .unwrap_or_default()
+        .excluded_validators;
 
     let mut delegations = HashMap::new();
-    for delegation in deps.querier.query_all_delegations(&hub_address)? {
+    for delegation in deps.querier.query_all_delegations(hub_address)? {
         delegations.insert(delegation.validator, delegation.amount.amount);
     }
 
     let mut validators: Vec<ValidatorResponse> = vec![];
-    for item in REGISTRY.range(deps.storage, None, None, cosmwasm_std::Order::Ascending) {
+    for item in REGISTRY.range(deps.storage, None, None, Order::Ascending) {
+        let address = item?.1.address;
+        if excluded_validators.contains(&address) {
+            continue;
+        }
         let mut validator = ValidatorResponse {
             total_delegated: Default::default(),
-            address: item?.1.address,
+            address,
         };
         // There is a bug in terra/core.
         // The bug happens when we do query_delegation() but there are no delegation pair (delegator-validator)
@@ -247,6 +479,18 @@ fn query_validators(deps: Deps) -> StdResult<Vec<ValidatorResponse>> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    // registries deployed before multiple hubs support only know about the main hub
+    let config = CONFIG.load(deps.storage)?;
+    if HUBS
+        .may_load(deps.storage, config.hub_contract.as_slice())?
+        .is_none()
+    {
+        HUBS.save(
+            deps.storage,
+            config.hub_contract.as_slice(),
+            &Hub::default(),
+        )?;
+    }
     Ok(Response::default())
 }
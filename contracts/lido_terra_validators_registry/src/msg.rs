@@ -36,14 +36,30 @@ pub enum ExecuteMsg {
         owner: Option<String>,
         hub_contract: Option<String>,
     },
+
+    /// Registers an additional hub that shares the validators set
+    AddHub { hub_contract: String },
+
+    /// Removes a hub from the registry
+    RemoveHub { hub_contract: String },
+
+    /// Sets the validators the hub must not delegate to.
+    /// Delegations of the hub to newly excluded validators are redelegated
+    UpdateExcludedValidators {
+        hub_contract: String,
+        excluded_validators: Vec<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     // GetValidatorsForDelegation returns validators sorted by available amount for delegation (delegation_limit - total_delegated)
-    GetValidatorsForDelegation {},
+    // for the given hub (the main hub from config if none), skipping validators excluded for that hub
+    GetValidatorsForDelegation { hub: Option<String> },
     Config {},
+    // Hubs returns all the hubs served by the registry
+    Hubs {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
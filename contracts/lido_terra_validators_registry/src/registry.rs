@@ -23,12 +23,27 @@ pub static CONFIG: Item<Config> = Item::new("config");
 
 pub static REGISTRY: Map<&[u8], Validator> = Map::new("validators_registry");
 
+pub static HUBS: Map<&[u8], Hub> = Map::new("hubs");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
     pub hub_contract: CanonicalAddr,
 }
 
+/// Per-hub settings of a hub served by the registry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Hub {
+    /// Validators from the registry the hub must not delegate to
+    pub excluded_validators: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HubResponse {
+    pub address: String,
+    pub excluded_validators: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Validator {
     pub address: String,
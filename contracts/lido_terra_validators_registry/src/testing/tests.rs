@@ -11,15 +11,15 @@
 // limitations under the License.
 
 use crate::common::{calculate_delegations, calculate_undelegations};
-use crate::contract::{execute, instantiate};
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::registry::{Validator, ValidatorResponse, CONFIG, REGISTRY};
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::registry::{HubResponse, Validator, ValidatorResponse, CONFIG, REGISTRY};
 use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
 use basset::hub::ExecuteMsg::{RedelegateProxy, UpdateGlobalIndex};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use cosmwasm_std::{
-    coin, coins, to_binary, Addr, Api, Coin, CosmosMsg, FullDelegation, StdError, Uint128,
-    Validator as CosmosValidator, WasmMsg,
+    coin, coins, from_binary, to_binary, Addr, Api, Coin, CosmosMsg, Deps, FullDelegation,
+    StdError, Uint128, Validator as CosmosValidator, WasmMsg,
 };
 
 #[test]
@@ -110,6 +110,25 @@ fn ownership_tests() {
         hub_contract: None,
         owner: None,
     };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(res.err().unwrap(), StdError::generic_err("unauthorized"));
+
+    let msg = ExecuteMsg::AddHub {
+        hub_contract: String::from("villain_hub"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(res.err().unwrap(), StdError::generic_err("unauthorized"));
+
+    let msg = ExecuteMsg::RemoveHub {
+        hub_contract: String::from("hub_contract_address"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(res.err().unwrap(), StdError::generic_err("unauthorized"));
+
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: String::from("hub_contract_address"),
+        excluded_validators: vec![],
+    };
     let res = execute(deps.as_mut(), mock_env(), info, msg);
     assert_eq!(res.err().unwrap(), StdError::generic_err("unauthorized"));
 }
@@ -119,12 +138,25 @@ fn update_config() {
     let mut deps = mock_dependencies(&coins(2, "token"));
 
     let msg = InstantiateMsg {
-        registry: vec![],
+        registry: vec![
+            Validator {
+                address: String::from("validator1"),
+            },
+            Validator {
+                address: String::from("validator2"),
+            },
+        ],
         hub_contract: String::from("hub_contract_address"),
     };
     let info = mock_info("creator", &coins(2, "token"));
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: String::from("hub_contract_address"),
+        excluded_validators: vec![String::from("validator2")],
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
     let new_hub_address = String::from("new_hub_contract");
     let msg = ExecuteMsg::UpdateConfig {
         hub_contract: Some(new_hub_address.clone()),
@@ -138,6 +170,17 @@ fn update_config() {
         config.hub_contract
     );
 
+    // the new hub keeps the exclusions of the old one
+    let hubs: Vec<HubResponse> =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Hubs {}).unwrap()).unwrap();
+    assert_eq!(
+        hubs,
+        vec![HubResponse {
+            address: new_hub_address.clone(),
+            excluded_validators: vec![String::from("validator2")],
+        }]
+    );
+
     let new_owner = String::from("new_owner");
     let msg = ExecuteMsg::UpdateConfig {
         owner: Some(new_owner.clone()),
@@ -597,6 +640,272 @@ fn remove_validator() {
     assert_eq!(res.messages.len(), 0);
 }
 
+fn query_hub_validators(deps: Deps, hub: Option<String>) -> Vec<ValidatorResponse> {
+    from_binary(
+        &query(
+            deps,
+            mock_env(),
+            QueryMsg::GetValidatorsForDelegation { hub },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn multiple_hubs() {
+    let mut deps = mock_dependencies(&coins(2, "token"));
+    let hub1 = Addr::unchecked("hub_contract_address");
+    let hub2 = Addr::unchecked("second_hub");
+    let validator1 = String::from("validator1");
+    let validator2 = String::from("validator2");
+    let validator3 = String::from("validator3");
+
+    let validators: Vec<CosmosValidator> = [&validator1, &validator2, &validator3]
+        .iter()
+        .map(|address| CosmosValidator {
+            address: address.to_string(),
+            commission: Default::default(),
+            max_commission: Default::default(),
+            max_change_rate: Default::default(),
+        })
+        .collect();
+    set_delegation_query(
+        &mut deps.querier,
+        &[
+            sample_delegation(hub1.clone(), validator1.clone(), coin(10, "uluna")),
+            sample_delegation(hub1.clone(), validator2.clone(), coin(20, "uluna")),
+            sample_delegation(hub1.clone(), validator3.clone(), coin(30, "uluna")),
+            sample_delegation(hub2.clone(), validator1.clone(), coin(5, "uluna")),
+            sample_delegation(hub2.clone(), validator2.clone(), coin(8, "uluna")),
+            sample_delegation(hub2.clone(), validator3.clone(), coin(50, "uluna")),
+        ],
+        &validators,
+    );
+
+    let msg = InstantiateMsg {
+        registry: vec![
+            Validator {
+                address: validator1.clone(),
+            },
+            Validator {
+                address: validator2.clone(),
+            },
+            Validator {
+                address: validator3.clone(),
+            },
+        ],
+        hub_contract: hub1.to_string(),
+    };
+    let info = mock_info("creator", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddHub {
+        hub_contract: hub2.to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the hub is already registered")
+    );
+
+    // the registered hub is allowed to add validators
+    let msg = ExecuteMsg::AddValidator {
+        validator: Validator {
+            address: validator3.clone(),
+        },
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(hub2.as_str(), &[]),
+        msg,
+    )
+    .unwrap();
+
+    // every hub gets validators with its own delegations
+    let hub1_validators = query_hub_validators(deps.as_ref(), None);
+    assert_eq!(
+        hub1_validators,
+        query_hub_validators(deps.as_ref(), Some(hub1.to_string()))
+    );
+    assert_eq!(
+        hub1_validators
+            .iter()
+            .map(|v| v.total_delegated.u128())
+            .collect::<Vec<u128>>(),
+        vec![10, 20, 30]
+    );
+    let hub2_validators = query_hub_validators(deps.as_ref(), Some(hub2.to_string()));
+    assert_eq!(
+        hub2_validators
+            .iter()
+            .map(|v| v.total_delegated.u128())
+            .collect::<Vec<u128>>(),
+        vec![5, 8, 50]
+    );
+
+    // the second hub excludes validator3 and moves its delegation away
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: hub2.to_string(),
+        excluded_validators: vec![validator3.clone()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub2.to_string(),
+            msg: to_binary(&RedelegateProxy {
+                src_validator: validator3.clone(),
+                redelegations: vec![
+                    (validator1.clone(), coin(27, "uluna")),
+                    (validator2.clone(), coin(23, "uluna")),
+                ],
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub2.to_string(),
+            msg: to_binary(&UpdateGlobalIndex {
+                airdrop_hooks: None
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    let hub2_validators = query_hub_validators(deps.as_ref(), Some(hub2.to_string()));
+    assert_eq!(
+        hub2_validators
+            .iter()
+            .map(|v| v.address.clone())
+            .collect::<Vec<String>>(),
+        vec![validator1.clone(), validator2.clone()]
+    );
+    assert_eq!(query_hub_validators(deps.as_ref(), None).len(), 3);
+
+    // the excluded validator can't be excluded twice
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: hub2.to_string(),
+        excluded_validators: vec![validator3.clone(), validator3.clone()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    // only the validators of the registry can be excluded
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: hub2.to_string(),
+        excluded_validators: vec![validator3.clone(), String::from("validator4")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("Validator validator4 is not in the registry")
+    );
+
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: hub2.to_string(),
+        excluded_validators: vec![validator1.clone(), validator2.clone(), validator3.clone()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("Cannot exclude all the validators in the registry")
+    );
+    // a failed transaction is reverted on chain, restore the exclusions manually
+    let msg = ExecuteMsg::UpdateExcludedValidators {
+        hub_contract: hub2.to_string(),
+        excluded_validators: vec![validator3.clone()],
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    // removing a validator redelegates the stake of every hub
+    let msg = ExecuteMsg::RemoveValidator {
+        address: validator2.clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 4);
+    let redelegations_of = |hub: &Addr| -> Vec<CosmosMsg> {
+        res.messages
+            .iter()
+            .filter(|m| match &m.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                    contract_addr == hub.as_str()
+                }
+                _ => false,
+            })
+            .map(|m| m.msg.clone())
+            .collect()
+    };
+    assert_eq!(
+        redelegations_of(&hub1)[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub1.to_string(),
+            msg: to_binary(&RedelegateProxy {
+                src_validator: validator2.clone(),
+                redelegations: vec![(validator1.clone(), coin(20, "uluna"))],
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        redelegations_of(&hub2)[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: hub2.to_string(),
+            msg: to_binary(&RedelegateProxy {
+                src_validator: validator2,
+                redelegations: vec![(validator1.clone(), coin(8, "uluna"))],
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // the main hub can't be removed
+    let msg = ExecuteMsg::RemoveHub {
+        hub_contract: hub1.to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("cannot remove the main hub")
+    );
+
+    let msg = ExecuteMsg::RemoveHub {
+        hub_contract: hub2.to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let hubs: Vec<HubResponse> =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Hubs {}).unwrap()).unwrap();
+    assert_eq!(
+        hubs,
+        vec![HubResponse {
+            address: hub1.to_string(),
+            excluded_validators: vec![],
+        }]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetValidatorsForDelegation {
+            hub: Some(hub2.to_string()),
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("the hub is not registered")
+    );
+}
+
 #[macro_export]
 macro_rules! default_validator_with_delegations {
     ($total:expr) => {
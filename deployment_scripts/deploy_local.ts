@@ -31,8 +31,8 @@ async function main(): Promise<void> {
       mint: {minter: hubAddress, cap: null}}, new Coins({}))
 
   let rewardsDispatcherAddress = await instantiateContract(terra, test1, rewardsDispatcherCodeId,
-    {lido_fee_address: test1.key.accAddress,
-      lido_fee_rate: "0.05", hub_contract: hubAddress, bluna_reward_contract: rewardAddress,
+    {lido_fee_recipients: [{address: test1.key.accAddress, share: "1"}],
+      stluna_fee_rate: "0.05", bluna_fee_rate: "0.05", hub_contract: hubAddress, bluna_reward_contract: rewardAddress,
       stluna_reward_denom: "uluna", bluna_reward_denom: "uusd"}, new Coins({}))
 
   let validatorsRegistryAddress = await instantiateContract(terra, test1, validatorsRegistryCodeId,
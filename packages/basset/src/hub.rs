@@ -7,7 +7,10 @@ use serde::{Deserialize, Serialize};
 pub enum BondType {
     BLuna,
     StLuna,
-    BondRewards,
+    // the rewards released to stLuna holders over the given period
+    BondRewards(u64),
+    // the rewards bonded for stLuna minted to the recipient
+    BondRewardsForStLuna(String),
 }
 
 pub type UnbondRequest = Vec<(u64, Uint128, Uint128)>;
@@ -32,6 +35,13 @@ pub struct State {
     pub prev_hub_balance: Uint128,
     pub last_unbonded_time: u64,
     pub last_processed_batch: u64,
+    // the part of total_bond_stluna_amount not yet released to stLuna holders
+    #[serde(default)]
+    pub stluna_unreleased_rewards: Uint128,
+    #[serde(default)]
+    pub stluna_rewards_last_release: u64,
+    #[serde(default)]
+    pub stluna_rewards_release_end: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
@@ -80,13 +90,43 @@ impl State {
 
     pub fn update_stluna_exchange_rate(&mut self, total_issued: Uint128, requested: Uint128) {
         let actual_supply = total_issued + requested;
-        if self.total_bond_stluna_amount.is_zero() || actual_supply.is_zero() {
+        // the unreleased rewards are bonded but don't back the stLuna yet
+        let released_bond_amount = self
+            .total_bond_stluna_amount
+            .checked_sub(self.stluna_unreleased_rewards)
+            .unwrap_or_default();
+        if released_bond_amount.is_zero() || actual_supply.is_zero() {
             self.stluna_exchange_rate = Decimal::one()
         } else {
-            self.stluna_exchange_rate =
-                Decimal::from_ratio(self.total_bond_stluna_amount, actual_supply);
+            self.stluna_exchange_rate = Decimal::from_ratio(released_bond_amount, actual_supply);
         }
     }
+
+    /// Releases the part of the unreleased stLuna rewards linearly accrued by `now`
+    pub fn release_stluna_rewards(&mut self, now: u64) {
+        if self.stluna_unreleased_rewards.is_zero() || now <= self.stluna_rewards_last_release {
+            return;
+        }
+        if now >= self.stluna_rewards_release_end {
+            self.stluna_unreleased_rewards = Uint128::zero();
+        } else {
+            let released = self.stluna_unreleased_rewards.multiply_ratio(
+                now - self.stluna_rewards_last_release,
+                self.stluna_rewards_release_end - self.stluna_rewards_last_release,
+            );
+            self.stluna_unreleased_rewards -= released;
+        }
+        self.stluna_rewards_last_release = now;
+    }
+
+    /// Adds the rewards to the unreleased ones and restarts the release period,
+    /// the rest of the previous rewards is released together with the new ones
+    pub fn stream_stluna_rewards(&mut self, amount: Uint128, now: u64, release_period: u64) {
+        self.release_stluna_rewards(now);
+        self.stluna_unreleased_rewards += amount;
+        self.stluna_rewards_last_release = now;
+        self.stluna_rewards_release_end = now + release_period;
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -126,7 +166,18 @@ pub enum ExecuteMsg {
 
     BondForStLuna {},
 
-    BondRewards {},
+    /// Bonds the rewards from the rewards dispatcher without minting stLuna.
+    /// The rewards are released to stLuna holders linearly over `release_period` seconds
+    /// or at once if it is not set.
+    BondRewards {
+        release_period: Option<u64>,
+    },
+
+    /// Receives the rewards from the rewards dispatcher like BondRewards,
+    /// but issues `amount` / stluna_exchange_rate for the recipient.
+    BondRewardsForStLuna {
+        recipient: String,
+    },
 
     /// Update global index
     UpdateGlobalIndex {
@@ -267,6 +318,7 @@ pub struct StateResponse {
     pub prev_hub_balance: Uint128,
     pub last_unbonded_time: u64,
     pub last_processed_batch: u64,
+    pub stluna_unreleased_rewards: Uint128,
 
     // #[deprecated]
     pub total_bond_amount: Uint128,
@@ -1,5 +1,9 @@
+mod permit;
+mod swap;
 mod tax_querier;
 
+pub use permit::{permit_sign_doc, pubkey_to_address, verify_permit, PermitPayload};
+pub use swap::compute_swap_deviation;
 pub use tax_querier::{compute_lido_fee, deduct_tax};
 pub mod airdrop;
 pub mod contract_error;
@@ -0,0 +1,110 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{to_vec, Api, Binary, StdError, StdResult, Uint128};
+use cw20::Expiration;
+use ripemd160::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const ADDRESS_PREFIX: &str = "terra";
+
+/// The payload the owner signs to grant the allowance to the spender with a permit.
+/// The owner signs the compact JSON of the payload, with the fields in the order
+/// they are declared in, as the arbitrary data of an ADR-036 sign doc:
+/// the `signArbitrary` of the wallet signs the SHA-256 hash of the amino JSON of
+/// `{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"",
+/// "msgs":[{"type":"sign/MsgSignData","value":{"data":<base64 payload>,"signer":<owner>}}],
+/// "sequence":"0"}` with the secp256k1 key of the owner.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitPayload {
+    pub chain_id: String,
+    // the token contract the permit is granted for
+    pub contract: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub expires: Option<Expiration>,
+    pub nonce: u64,
+}
+
+/// Returns the account address of the compressed secp256k1 public key
+pub fn pubkey_to_address(pubkey: &[u8]) -> StdResult<String> {
+    let hash = Ripemd160::digest(&Sha256::digest(pubkey));
+    bech32::encode(ADDRESS_PREFIX, hash.to_base32(), Variant::Bech32)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+// the ADR-036 sign doc, the fields are declared in the sorted order of the amino JSON
+#[derive(Serialize)]
+struct SignDoc {
+    account_number: String,
+    chain_id: String,
+    fee: Fee,
+    memo: String,
+    msgs: Vec<SignMsg>,
+    sequence: String,
+}
+
+#[derive(Serialize)]
+struct Fee {
+    amount: Vec<()>,
+    gas: String,
+}
+
+#[derive(Serialize)]
+struct SignMsg {
+    #[serde(rename = "type")]
+    msg_type: String,
+    value: SignData,
+}
+
+#[derive(Serialize)]
+struct SignData {
+    data: Binary,
+    signer: String,
+}
+
+/// Returns the bytes of the ADR-036 sign doc the owner signs the payload in
+pub fn permit_sign_doc(payload: &PermitPayload) -> StdResult<Vec<u8>> {
+    to_vec(&SignDoc {
+        account_number: "0".to_string(),
+        chain_id: "".to_string(),
+        fee: Fee {
+            amount: vec![],
+            gas: "0".to_string(),
+        },
+        memo: "".to_string(),
+        msgs: vec![SignMsg {
+            msg_type: "sign/MsgSignData".to_string(),
+            value: SignData {
+                data: Binary(to_vec(payload)?),
+                signer: payload.owner.clone(),
+            },
+        }],
+        sequence: "0".to_string(),
+    })
+}
+
+/// Checks the sign doc of the payload is signed with the key of the owner
+pub fn verify_permit(
+    api: &dyn Api,
+    payload: &PermitPayload,
+    signature: &[u8],
+    pubkey: &[u8],
+) -> StdResult<()> {
+    if pubkey_to_address(pubkey)? != payload.owner {
+        return Err(StdError::generic_err(
+            "the public key doesn't belong to the owner",
+        ));
+    }
+
+    let hash = Sha256::digest(&permit_sign_doc(payload)?);
+    let verified = api
+        .secp256k1_verify(&hash, signature, pubkey)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    if !verified {
+        return Err(StdError::generic_err("invalid permit signature"));
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Uint128};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -19,8 +19,51 @@ pub enum ExecuteMsg {
     /// Swap all of the balances to uusd.
     SwapToRewardDenom {},
 
-    /// Update the global index
-    UpdateGlobalIndex {},
+    /// Update the global index.
+    /// The new rewards are released to the holders linearly over `release_period` seconds
+    /// or at once if it is not set.
+    UpdateGlobalIndex { release_period: Option<u64> },
+
+    /// Swap the balance of the reward denom to `new_denom` and pay the rewards in it.
+    /// The accrued rewards are converted at the rate the balance is swapped at.
+    MigrateRewardDenom { new_denom: String },
+
+    /// Complete the reward denom migration after the swap.
+    /// Only the contract itself is allowed to execute.
+    CompleteRewardDenomMigration {
+        new_denom: String,
+        prev_denom_amount: Uint128,
+        new_denom_balance: Uint128,
+    },
+
+    /// Bond the Luna the compounded rewards are swapped to.
+    /// Only the contract itself is allowed to execute.
+    BondCompoundedRewards {},
+
+    /// Fail the claim if the balance of the recipient has grown by less than `min_receive`
+    /// since `prev_balance` after the rewards are swapped and sent.
+    /// Only the contract itself is allowed to execute.
+    CheckReceivedRewards {
+        recipient: String,
+        prev_balance: Coin,
+        min_receive: Uint128,
+    },
+
+    /// Exclude the holder from the rewards or delegate its rewards to a beneficiary.
+    /// Only the owner of the hub is allowed to execute.
+    UpdateHolderStatus {
+        address: String,
+        status: HolderStatus,
+    },
+
+    /// Distribute the rewards paid in `denom` as they are, without swapping them.
+    /// The cw20 tokens are added as `cw20:<token address>`.
+    /// Only the owner of the hub is allowed to execute.
+    AddRewardDenom { denom: String },
+
+    /// Index the next `limit` holders stored before the holder stats were kept,
+    /// the migration indexes the first batch of them.
+    IndexHolders { limit: Option<u32> },
 
     ////////////////////
     /// bAsset's operations
@@ -40,7 +83,60 @@ pub enum ExecuteMsg {
     ///////////////////
 
     /// return the accrued reward in uusd to the user.
-    ClaimRewards { recipient: Option<String> },
+    /// The reward is swapped to `denom` if it is set, the claim fails
+    /// if the recipient gets less than `min_receive`, the swapped amount
+    /// is checked against the balance of the recipient after the swap.
+    /// The claim operators of the `holder` can claim on behalf of the holder,
+    /// the rewards are paid to the holder and only the holder sets
+    /// `recipient`, `denom` and `min_receive`.
+    /// The reward routes of the holder are applied before the swap.
+    /// The rewards in the other denoms are sent as they are,
+    /// only the `reward_denoms` are claimed if it is set.
+    ClaimRewards {
+        holder: Option<String>,
+        recipient: Option<String>,
+        denom: Option<String>,
+        min_receive: Option<Uint128>,
+        reward_denoms: Option<Vec<String>>,
+    },
+
+    /// Opt in or out of compounding the accrued rewards into bLuna
+    /// at each global index update.
+    SetAutoCompound { enabled: bool },
+
+    /// Send the bLuna compounded for the user.
+    WithdrawCompoundedRewards { recipient: Option<String> },
+
+    /// Claim the rewards of each of the holders to them or to their reward routes.
+    /// The sender must be allowed to claim the rewards of every holder.
+    ClaimRewardsFor { holders: Vec<String> },
+
+    /// Allow or disallow the operator to claim the rewards of the user.
+    SetClaimOperator { operator: String, enabled: bool },
+
+    /// Replace the routes the shares of each claim of the user are sent through.
+    SetRewardRoutes { routes: Vec<RewardRoute> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HolderStatus {
+    Regular {},
+    /// The balance of the holder accrues no rewards and doesn't dilute the global index
+    Excluded {},
+    /// The rewards of the holder are claimed to the beneficiary
+    Delegated {
+        beneficiary: String,
+    },
+}
+
+/// The `share` of each claim is sent to `recipient`,
+/// it is executed with `msg` if it is set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardRoute {
+    pub recipient: String,
+    pub share: Decimal,
+    pub msg: Option<Binary>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -51,6 +147,11 @@ pub enum QueryMsg {
     AccruedRewards {
         address: String,
     },
+    /// The accrued rewards along with the estimate of the share of the holder
+    /// in the rewards not added to the global index yet
+    PendingRewards {
+        address: String,
+    },
     Holder {
         address: String,
     },
@@ -58,6 +159,40 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// The holders with a nonzero balance ordered by the balance, ascending by default
+    HoldersByBalance {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        descending: Option<bool>,
+    },
+    /// The holder count and the totals of the holders
+    Stats {},
+    CompoundedRewards {
+        address: String,
+    },
+    ClaimSettings {
+        address: String,
+    },
+    HolderStatus {
+        address: String,
+    },
+    /// The checkpoints of the global index made by the updates,
+    /// the ones older than a year are pruned
+    IndexCheckpoints {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// An estimate of the rewards accrued by the holder between the `from` and `to` block times.
+    /// The rewards are accounted at the global index updates and the balance changes,
+    /// the ones released over a release period are counted at the update that claimed them.
+    /// The amounts accrued in each reward denom are summed up as they are, a range crossing
+    /// a reward denom migration mixes the denoms. The range can't start more than a year ago,
+    /// the checkpoints older than that are pruned.
+    RewardsBetween {
+        address: String,
+        from: u64,
+        to: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -71,11 +206,32 @@ pub struct StateResponse {
     pub global_index: Decimal,
     pub total_balance: Uint128,
     pub prev_reward_balance: Uint128,
+    pub unreleased_rewards: Uint128,
+    pub reward_indices: Vec<RewardIndexResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardIndexResponse {
+    pub denom: String,
+    pub global_index: Decimal,
+    pub prev_reward_balance: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AccruedRewardsResponse {
+    // the rewards in the reward denom
     pub rewards: Uint128,
+    // the rewards in all of the denoms
+    pub all_rewards: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsResponse {
+    // the accrued rewards and the pending ones
+    pub rewards: Uint128,
+    // the estimated share of the holder in the rewards not dispatched
+    // or not added to the global index yet
+    pub pending_rewards: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -91,5 +247,54 @@ pub struct HoldersResponse {
     pub holders: Vec<HolderResponse>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    // the number of the holders with a nonzero balance
+    pub holder_count: u64,
+    pub total_balance: Uint128,
+    // the pending rewards of the holders in the reward denom, the ones settled at their
+    // last updates along with the ones accrued by the global index since,
+    // the rewards of the auto-compounding holders are compounded instead
+    pub total_pending_rewards: Uint128,
+    // false till the holders stored before the stats were kept are indexed
+    pub holders_indexed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CompoundedRewardsResponse {
+    pub auto_compound: bool,
+    // the bLuna compounded for the holder and not withdrawn yet
+    pub compounded_rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimSettingsResponse {
+    pub operators: Vec<String>,
+    pub routes: Vec<RewardRoute>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderStatusResponse {
+    pub status: HolderStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexCheckpointResponse {
+    pub time: u64,
+    pub global_index: Decimal,
+    pub total_balance: Uint128,
+    pub rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexCheckpointsResponse {
+    pub checkpoints: Vec<IndexCheckpointResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardsBetweenResponse {
+    pub rewards: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MigrateMsg {}
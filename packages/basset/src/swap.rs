@@ -0,0 +1,33 @@
+use cosmwasm_std::{Decimal, Uint128};
+
+/// Returns the relative shortfall of a simulated market swap against the amount
+/// the oracle exchange rate promises. A swap that returns at least the expected
+/// amount has zero deviation.
+pub fn compute_swap_deviation(expected: Uint128, received: Uint128) -> Decimal {
+    if expected.is_zero() || received >= expected {
+        return Decimal::zero();
+    }
+    Decimal::from_ratio(expected - received, expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_compute_swap_deviation() {
+        assert_eq!(
+            compute_swap_deviation(Uint128::from(1000u128), Uint128::from(950u128)),
+            Decimal::from_str("0.05").unwrap()
+        );
+        assert_eq!(
+            compute_swap_deviation(Uint128::from(1000u128), Uint128::from(1100u128)),
+            Decimal::zero()
+        );
+        assert_eq!(
+            compute_swap_deviation(Uint128::zero(), Uint128::from(10u128)),
+            Decimal::zero()
+        );
+    }
+}
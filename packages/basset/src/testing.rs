@@ -1,6 +1,8 @@
-use crate::deduct_tax;
 use crate::mock_querier::mock_dependencies;
-use cosmwasm_std::{Coin, Decimal, Uint128};
+use crate::{deduct_tax, permit_sign_doc, verify_permit, PermitPayload};
+use cosmwasm_std::testing::MockApi;
+use cosmwasm_std::{Binary, Coin, Decimal, StdError, Uint128};
+use cw20::Expiration;
 
 #[test]
 fn test_deduct_tax() {
@@ -38,3 +40,44 @@ fn test_deduct_tax() {
         }
     );
 }
+
+#[test]
+fn test_verify_permit() {
+    let owner = "terra150rtrmj2f8vl9tem8qpfw36ylw5jg9j248sfme".to_string();
+    let mut payload = PermitPayload {
+        chain_id: "columbus-5".to_string(),
+        contract: "cosmos2contract".to_string(),
+        owner: owner.clone(),
+        spender: "addr0001".to_string(),
+        amount: Uint128::new(300u128),
+        expires: Some(Expiration::AtHeight(12445)),
+        nonce: 0,
+    };
+
+    // the amino JSON of the ADR-036 sign doc the wallet signs
+    let sign_doc = format!(
+        "{{\"account_number\":\"0\",\"chain_id\":\"\",\"fee\":{{\"amount\":[],\"gas\":\"0\"}},\
+         \"memo\":\"\",\"msgs\":[{{\"type\":\"sign/MsgSignData\",\"value\":{{\"data\":\
+         \"eyJjaGFpbl9pZCI6ImNvbHVtYnVzLTUiLCJjb250cmFjdCI6ImNvc21vczJjb250cmFjdCIsIm93bmVyIjoidGVy\
+         cmExNTBydHJtajJmOHZsOXRlbThxcGZ3MzZ5bHc1amc5ajI0OHNmbWUiLCJzcGVuZGVyIjoiYWRkcjAwMDEiLCJh\
+         bW91bnQiOiIzMDAiLCJleHBpcmVzIjp7ImF0X2hlaWdodCI6MTI0NDV9LCJub25jZSI6MH0=\",\
+         \"signer\":\"{}\"}}}}],\"sequence\":\"0\"}}",
+        owner
+    );
+    assert_eq!(permit_sign_doc(&payload).unwrap(), sign_doc.into_bytes());
+
+    // the signature of the sign doc made with the secp256k1 key of the owner
+    let api = MockApi::default();
+    let pubkey = Binary::from_base64("ApicC3bLVjlx/cm+8x7AbDVg8ySdbunl2DxXYlWW4F9v").unwrap();
+    let signature = Binary::from_base64(
+        "dk/NvG40bjjh27W78ZdoIhgprNQWYWJ+ST5w9/x51HVG7Og7Elh22QSmzYx51H2KwCmmDBvYzqT+bHvvisyQDg==",
+    )
+    .unwrap();
+    verify_permit(&api, &payload, &signature, &pubkey).unwrap();
+
+    payload.amount = Uint128::new(301u128);
+    assert_eq!(
+        verify_permit(&api, &payload, &signature, &pubkey).unwrap_err(),
+        StdError::generic_err("invalid permit signature")
+    );
+}